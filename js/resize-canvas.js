@@ -1,23 +1,42 @@
 function maximizeCanvas(id) {
   let canvas = document.getElementById(id);
-  canvas.width = document.body.clientWidth;
-  canvas.height = document.body.clientHeight;
+  if (document.fullscreenElement === canvas) {
+    // The fullscreen canvas covers the whole screen, not just the body
+    canvas.width = window.innerWidth;
+    canvas.height = window.innerHeight;
+  } else {
+    canvas.width = document.body.clientWidth;
+    canvas.height = document.body.clientHeight;
+  }
   console.log("resized");
 }
 
 export function setResizeHandler(id, callback, timeout) {
   var timer_id = undefined;
-  window.addEventListener("resize", function() {
+  function cancelPending() {
     if(timer_id != undefined) {
       clearTimeout(timer_id);
       timer_id = undefined;
     }
+  }
+  window.addEventListener("resize", function() {
+    cancelPending();
     timer_id = setTimeout(function() {
       timer_id = undefined;
       maximizeCanvas(id);
       callback();
     }, timeout);
   });
+  // Entering or leaving fullscreen resizes at once, without waiting for
+  // the window resize that comes along with it
+  document.addEventListener("fullscreenchange", function() {
+    cancelPending();
+    maximizeCanvas(id);
+    callback();
+  });
+  document.addEventListener("fullscreenerror", function() {
+    console.log("fullscreen request rejected");
+  });
 
   // Do one resize now
   maximizeCanvas(id)
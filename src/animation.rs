@@ -0,0 +1,119 @@
+use nalgebra::{Translation2, Vector2};
+
+use crate::supervisor::Scale;
+
+/// Duration of animated zooms in milliseconds.
+pub const VIEW_ANIMATION_MS: f64 = 150.0;
+
+/// Cubic ease-out, fast at first and slowing down towards the end.
+///
+/// Maps `t` in `[0, 1]` to `[0, 1]`, values outside are clamped.
+pub fn ease_out(t: f64) -> f64 {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+/// Transition of the view from one translation and scale to another.
+///
+/// The scale and the translation in screen pixels are interpolated
+/// linearly, so that the point fixed by a zoom also stays in place while
+/// animating towards it.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewAnimation {
+    from_scale: f64,
+    /// Translation multiplied by the scale, i.e. in screen pixels.
+    from_offset: Vector2<f64>,
+    to_trans: Translation2<f64>,
+    to_scale: Scale,
+    start_ms: f64,
+}
+
+impl ViewAnimation {
+    pub fn new(
+        (from_trans, from_scale): (Translation2<f64>, Scale),
+        (to_trans, to_scale): (Translation2<f64>, Scale),
+        start_ms: f64,
+    ) -> Self {
+        let from_scale = from_scale.raw();
+        Self {
+            from_scale,
+            from_offset: from_trans.vector * from_scale,
+            to_trans,
+            to_scale,
+            start_ms,
+        }
+    }
+
+    /// The view the animation ends with.
+    pub fn target(&self) -> (Translation2<f64>, Scale) {
+        (self.to_trans, self.to_scale)
+    }
+
+    pub fn is_finished(&self, now_ms: f64) -> bool {
+        now_ms - self.start_ms >= VIEW_ANIMATION_MS
+    }
+
+    /// The view at `now_ms`, with the kind of scale of the target.
+    pub fn view_at(&self, now_ms: f64) -> (Translation2<f64>, Scale) {
+        if self.is_finished(now_ms) {
+            return self.target();
+        }
+        let t = ease_out((now_ms - self.start_ms) / VIEW_ANIMATION_MS);
+        let to_scale = self.to_scale.raw();
+        let scale = self.from_scale + t * (to_scale - self.from_scale);
+        let offset = self.from_offset + t * (self.to_trans.vector * to_scale - self.from_offset);
+        let scale_kind = match self.to_scale {
+            Scale::Manual(_) => Scale::Manual(scale),
+            Scale::Auto(_) => Scale::Auto(scale),
+        };
+        (Translation2::from(offset / scale), scale_kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Point2;
+
+    use super::*;
+
+    #[test]
+    fn ease_out_slows_down() {
+        assert_eq!(ease_out(0.0), 0.0);
+        assert_eq!(ease_out(0.5), 0.875);
+        assert_eq!(ease_out(1.0), 1.0);
+        assert_eq!(ease_out(-1.0), 0.0);
+        assert_eq!(ease_out(2.0), 1.0);
+        // The first half covers more than the second
+        assert!(ease_out(0.25) - ease_out(0.0) > ease_out(1.0) - ease_out(0.75));
+    }
+
+    #[test]
+    fn scale_is_interpolated() {
+        let from = (Translation2::from([0.0, 0.0]), Scale::Auto(1.0));
+        let to = (Translation2::from([0.0, 0.0]), Scale::Manual(3.0));
+        let animation = ViewAnimation::new(from, to, 1000.0);
+        let scale = |ms| animation.view_at(1000.0 + ms).1.raw();
+        assert_eq!(scale(0.0), 1.0);
+        assert_eq!(scale(VIEW_ANIMATION_MS / 2.0), 1.0 + 2.0 * 0.875);
+        assert_eq!(scale(VIEW_ANIMATION_MS), 3.0);
+        assert!(!animation.is_finished(1100.0));
+        assert!(animation.is_finished(1000.0 + VIEW_ANIMATION_MS));
+        // The target decides whether the scale is manual
+        assert!(matches!(animation.view_at(1050.0).1, Scale::Manual(_)));
+        assert!(matches!(animation.view_at(5000.0), (_, Scale::Manual(s)) if s == 3.0));
+    }
+
+    #[test]
+    fn zoom_keeps_fixed_point_while_animating() {
+        // Zooming from 1 to 4 around the screen point (100, 50)
+        let cursor = Point2::from([100.0, 50.0]);
+        let from = (Translation2::from([10.0, -20.0]), Scale::Manual(1.0));
+        let world = from.0.inverse_transform_point(&cursor);
+        let to_trans = Translation2::from(cursor.coords / 4.0 - world.coords);
+        let animation = ViewAnimation::new(from, (to_trans, Scale::Manual(4.0)), 0.0);
+        for ms in [0.0, 30.0, 75.0, 120.0, VIEW_ANIMATION_MS] {
+            let (trans, scale) = animation.view_at(ms);
+            let screen = scale.raw() * trans.transform_point(&world);
+            assert!((screen - cursor).norm() < 1e-9);
+        }
+    }
+}
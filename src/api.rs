@@ -0,0 +1,143 @@
+//! A JavaScript interface to a Game of Life, for embedding the simulation
+//! into other pages without the yew frontend.
+//!
+//! ```js
+//! const life = new Simulation(20, 20); // Throws for empty or huge grids
+//! life.set_cell(1, 2, true);
+//! life.step();
+//! const cells = life.cells();
+//! ```
+
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    automaton::{Life, LifeStates},
+    supervisor::Supervisor,
+};
+
+/// A wrapping grid of [`Life`] cells, edited and stepped from JavaScript.
+///
+/// Coordinates outside of the grid wrap around.
+#[wasm_bindgen]
+pub struct Simulation {
+    supervisor: Supervisor<Life>,
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize) -> Result<Simulation, JsValue> {
+        Self::try_new(width, height).map_err(|err| JsValue::from_str(&err))
+    }
+
+    pub fn width(&self) -> usize {
+        self.supervisor.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.supervisor.height()
+    }
+
+    /// Number of generations computed since the start.
+    pub fn generation(&self) -> u64 {
+        self.supervisor.generation()
+    }
+
+    pub fn toggle(&mut self, x: isize, y: isize) {
+        self.supervisor.toggle(x, y);
+    }
+
+    pub fn step(&mut self) {
+        self.supervisor.update();
+    }
+
+    /// Number of alive cells.
+    pub fn population(&self) -> usize {
+        self.supervisor.population()
+    }
+
+    pub fn set_cell(&mut self, x: isize, y: isize, alive: bool) {
+        let state = if alive {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        };
+        self.supervisor.set(x, y, state);
+    }
+
+    pub fn get_cell(&self, x: isize, y: isize) -> bool {
+        self.supervisor.get(x, y) == Some(&LifeStates::Alife)
+    }
+
+    /// All cells row by row, `1` for alive and `0` for dead cells.
+    ///
+    /// Returned as a `Uint8Array` of `width * height` bytes.
+    pub fn cells(&self) -> Vec<u8> {
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.get_cell(x, y) as u8)
+            .collect()
+    }
+}
+
+impl Simulation {
+    /// Like [`Simulation::new`], without converting the error for JavaScript.
+    fn try_new(width: usize, height: usize) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("{}×{} grid is empty", width, height));
+        }
+        let supervisor = Supervisor::try_new(Life, width, height).map_err(|err| err.to_string())?;
+        Ok(Self { supervisor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Also run as regular tests on the host, the simulation needs no browser
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn blinker_oscillates() {
+        let mut life = Simulation::new(5, 5).unwrap();
+        for x in 1..4 {
+            life.toggle(x, 2);
+        }
+        assert_eq!(life.population(), 3);
+        life.step();
+        assert_eq!(life.generation(), 1);
+        assert!((1..4).all(|y| life.get_cell(2, y)));
+        assert!(!life.get_cell(1, 2) && !life.get_cell(3, 2));
+        let mut vertical = vec![0; 25];
+        for y in 1..4 {
+            vertical[y * 5 + 2] = 1;
+        }
+        assert_eq!(life.cells(), vertical);
+        life.step();
+        assert!((1..4).all(|x| life.get_cell(x, 2)));
+        assert_eq!(life.population(), 3);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn set_and_get_cells() {
+        let mut life = Simulation::new(4, 3).unwrap();
+        life.set_cell(3, 0, true);
+        assert!(life.get_cell(3, 0));
+        // Coordinates wrap around
+        assert!(life.get_cell(-1, 3));
+        life.set_cell(3, 0, false);
+        assert_eq!(life.population(), 0);
+        assert_eq!(life.cells().len(), 12);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn empty_and_huge_grids_are_rejected() {
+        assert!(Simulation::try_new(0, 0).is_err());
+        assert!(Simulation::try_new(5, 0).is_err());
+        assert!(Simulation::try_new(100_000, 100_000).is_err());
+        assert!(Simulation::try_new(usize::MAX, 2).is_err());
+        assert!(Simulation::try_new(1, 1).is_ok());
+    }
+}
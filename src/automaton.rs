@@ -1,87 +1,1238 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    borrow::Cow,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::{Index, IndexMut},
+};
 
-use wasm_bindgen::JsValue;
+use crate::{random::XorShift, theme::Theme};
 
-#[derive(Debug, Clone)]
+mod bit_grid;
+mod brians_brain;
+mod cyclic;
+mod elementary;
+mod forest_fire;
+mod generations;
+mod greenberg_hastings;
+pub mod hex;
+mod immigration;
+mod langtons_ant;
+mod larger_than_life;
+mod lenia;
+mod margolus;
+mod rock_paper_scissors;
+mod sandpile;
+mod smooth_life;
+mod turmite;
+mod wator;
+mod weighted_seeds;
+mod wireworld;
+
+pub use bit_grid::BitGrid;
+pub use brians_brain::BriansBrain;
+pub use cyclic::{Cyclic, CyclicState};
+pub use elementary::Elementary;
+pub use forest_fire::{ForestFire, ForestState};
+pub use generations::Generations;
+pub use greenberg_hastings::{Excitation, GreenbergHastings};
+pub use hex::HexLife;
+pub use immigration::Immigration;
+pub use langtons_ant::{AntCell, Heading, LangtonsAnt};
+pub use larger_than_life::LargerThanLife;
+pub use lenia::Lenia;
+pub use margolus::Margolus;
+pub use rock_paper_scissors::{RockPaperScissors, Species};
+pub use sandpile::Sandpile;
+pub use smooth_life::SmoothLife;
+pub use turmite::{
+    Transition, Turmite, Turn, MAX_TURMITE_COLORS, MAX_TURMITE_STATES, TURMITE_PRESETS,
+};
+pub use wator::{Creature, WaTor};
+pub use weighted_seeds::WeightedSeeds;
+pub use wireworld::{WireState, Wireworld};
+
+#[derive(Debug, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawGrid<State>")
+)]
 pub struct Grid<State> {
     width: usize,
     height: usize,
+    edge_behavior: EdgeBehavior,
+    #[cfg_attr(feature = "serde", serde(rename = "cells"))]
     grid: Vec<State>,
 }
 
-impl<State: Default> Grid<State> {
-    pub fn generate(width: usize, height: usize) -> Self {
-        let grid = (0..width * height).map(|_| Default::default()).collect();
-        Self {
-            width,
-            height,
-            grid,
-        }
+impl<State: Clone> Clone for Grid<State> {
+    fn clone(&self) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            edge_behavior: self.edge_behavior,
+            grid: self.grid.clone(),
+        }
+    }
+
+    /// Reuses the cells of `self`, so that double buffers don't allocate.
+    fn clone_from(&mut self, source: &Self) {
+        self.width = source.width;
+        self.height = source.height;
+        self.edge_behavior = source.edge_behavior;
+        self.grid.clone_from(&source.grid);
+    }
+}
+
+/// A deserialized [`Grid`] before checking its size.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawGrid<State> {
+    width: usize,
+    height: usize,
+    edge_behavior: EdgeBehavior,
+    cells: Vec<State>,
+}
+
+#[cfg(feature = "serde")]
+impl<State> TryFrom<RawGrid<State>> for Grid<State> {
+    type Error = GridError;
+
+    fn try_from(raw: RawGrid<State>) -> Result<Self, Self::Error> {
+        let expected = cell_count(raw.width, raw.height, MAX_CELLS)?;
+        if raw.cells.len() != expected {
+            return Err(GridError::CellCount {
+                expected,
+                actual: raw.cells.len(),
+            });
+        }
+        Ok(Self {
+            width: raw.width,
+            height: raw.height,
+            edge_behavior: raw.edge_behavior,
+            grid: raw.cells,
+        })
+    }
+}
+
+/// Version of the envelope written by [`Grid::to_json`].
+#[cfg(feature = "serde")]
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A versioned snapshot, `{"version": 1, "grid": {...}}`.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Envelope<G> {
+    version: u32,
+    grid: G,
+}
+
+/// Why a snapshot could not be read.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    Json(serde_json::Error),
+    /// The snapshot was written by a newer or unknown version.
+    UnsupportedVersion(u32),
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "{}", e),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// JSON snapshots, only available with the `serde` feature.
+#[cfg(feature = "serde")]
+impl<State: serde::Serialize + serde::de::DeserializeOwned> Grid<State> {
+    /// Serialize the grid as JSON, wrapped in an envelope with the [`SNAPSHOT_VERSION`].
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        let envelope = Envelope {
+            version: SNAPSHOT_VERSION,
+            grid: self,
+        };
+        Ok(serde_json::to_string(&envelope)?)
+    }
+
+    /// Read a snapshot written by [`Grid::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let envelope: Envelope<serde_json::Value> = serde_json::from_str(json)?;
+        if envelope.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(envelope.version));
+        }
+        Ok(serde_json::from_value(envelope.grid)?)
+    }
+}
+
+/// Default limit of the number of cells of a grid, see [`Grid::try_generate`].
+///
+/// With one byte per cell this is 16 MiB per grid, of which the
+/// [`Supervisor`](crate::supervisor::Supervisor) keeps several.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// Why a grid could not be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    /// The grid would have more than `max_cells` cells.
+    TooLarge {
+        width: usize,
+        height: usize,
+        max_cells: usize,
+    },
+    /// The number of cells doesn't match the width and height.
+    CellCount { expected: usize, actual: usize },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge {
+                width,
+                height,
+                max_cells,
+            } => write!(
+                f,
+                "{}×{} cells exceed the limit of {} cells",
+                width, height, max_cells
+            ),
+            Self::CellCount { expected, actual } => {
+                write!(f, "expected {} cells, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// Number of cells of a `width`×`height` grid, unless it exceeds `max_cells`.
+pub fn cell_count(width: usize, height: usize, max_cells: usize) -> Result<usize, GridError> {
+    width
+        .checked_mul(height)
+        .filter(|&cells| cells <= max_cells)
+        .ok_or(GridError::TooLarge {
+            width,
+            height,
+            max_cells,
+        })
+}
+
+/// What happens to coordinates outside of a [`Grid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeBehavior {
+    /// Coordinates wrap around, glueing the edges according to the [`Topology`].
+    Wrap(Topology),
+    /// Coordinates are clamped to the nearest edge cell.
+    Clamp,
+    /// There are no cells outside of the grid, see [`Grid::get`].
+    Dead,
+}
+
+impl Default for EdgeBehavior {
+    fn default() -> Self {
+        EdgeBehavior::Wrap(Topology::Torus)
+    }
+}
+
+/// How the edges of a wrapping [`Grid`] are glued together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    /// Leaving at one edge enters at the opposite one, in the same row or column.
+    #[default]
+    Torus,
+    /// The left and right edges are glued like on a torus, there are no
+    /// cells above or below the grid.
+    Cylinder,
+    /// The left and right edges are glued like on a torus. Leaving at the
+    /// top or bottom enters at the opposite edge in the mirrored column,
+    /// i.e. `x` becomes `width - 1 - x`.
+    KleinBottle,
+    /// Both pairs of edges are glued mirrored: crossing the top or bottom
+    /// mirrors the column, crossing the left or right mirrors the row.
+    Projective,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 4] = [
+        Topology::Torus,
+        Topology::Cylinder,
+        Topology::KleinBottle,
+        Topology::Projective,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Torus => "Torus",
+            Topology::Cylinder => "Cylinder",
+            Topology::KleinBottle => "Klein bottle",
+            Topology::Projective => "Projective plane",
+        }
+    }
+
+    /// Whether rows and columns can be wrapped independently of each other.
+    fn is_separable(self) -> bool {
+        matches!(self, Topology::Torus | Topology::Cylinder)
+    }
+
+    /// Map `(x, y)` into a `width`×`height` grid, `None` if there is no cell.
+    fn wrap(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (width, height) = (width as isize, height as isize);
+        // How often the coordinates crossed an edge, odd counts mirror
+        let (crossed_x, crossed_y) = (x.div_euclid(width), y.div_euclid(height));
+        let (mut x, mut y) = (x.rem_euclid(width), y.rem_euclid(height));
+        let mirror_x = match self {
+            Topology::Torus => false,
+            Topology::Cylinder if crossed_y != 0 => return None,
+            Topology::Cylinder => false,
+            Topology::KleinBottle | Topology::Projective => crossed_y.rem_euclid(2) == 1,
+        };
+        let mirror_y = self == Topology::Projective && crossed_x.rem_euclid(2) == 1;
+        if mirror_x {
+            x = width - 1 - x;
+        }
+        if mirror_y {
+            y = height - 1 - y;
+        }
+        Some((x as usize, y as usize))
+    }
+}
+
+impl EdgeBehavior {
+    /// Row-major index of `(x, y)` in a `width`×`height` grid.
+    fn index(self, x: isize, y: isize, width: usize, height: usize) -> Option<usize> {
+        let (x, y) = match self {
+            EdgeBehavior::Wrap(topology) => topology.wrap(x, y, width, height)?,
+            EdgeBehavior::Clamp => (
+                x.clamp(0, width as isize - 1) as usize,
+                y.clamp(0, height as isize - 1) as usize,
+            ),
+            EdgeBehavior::Dead => {
+                if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                    return None;
+                }
+                (x as usize, y as usize)
+            }
+        };
+        Some(x + y * width)
+    }
+
+    /// Whether rows and columns can be resolved independently of each other,
+    /// as [`BitGrid`] does.
+    pub(crate) fn is_separable(self) -> bool {
+        match self {
+            EdgeBehavior::Wrap(topology) => topology.is_separable(),
+            EdgeBehavior::Clamp | EdgeBehavior::Dead => true,
+        }
+    }
+}
+
+impl<State: Default> Grid<State> {
+    /// Create a grid of default cells.
+    ///
+    /// Only meant for sizes known to be small, use [`Grid::try_generate`]
+    /// for sizes chosen by the user.
+    pub fn generate(width: usize, height: usize) -> Self {
+        Self::with_edge_behavior(width, height, EdgeBehavior::default())
+    }
+
+    /// Create a grid of default cells, unless it has more than [`MAX_CELLS`] cells.
+    pub fn try_generate(width: usize, height: usize) -> Result<Self, GridError> {
+        cell_count(width, height, MAX_CELLS)?;
+        Ok(Self::generate(width, height))
+    }
+
+    pub fn with_edge_behavior(width: usize, height: usize, edge_behavior: EdgeBehavior) -> Self {
+        let grid = (0..width * height).map(|_| Default::default()).collect();
+        Self {
+            width,
+            height,
+            edge_behavior,
+            grid,
+        }
+    }
+
+    /// Change the size of the grid.
+    ///
+    /// Cells in the overlapping top-left region are kept, new cells are
+    /// set to the default state.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut old = mem::take(&mut self.grid).into_iter();
+        let mut grid = Vec::with_capacity(width * height);
+        for _ in 0..height.min(self.height) {
+            // Copy the overlapping part of the row and skip the rest
+            let mut row = old.by_ref().take(self.width);
+            grid.extend(row.by_ref().take(width));
+            row.for_each(drop);
+            grid.extend((self.width..width).map(|_| State::default()));
+        }
+        grid.extend((grid.len()..width * height).map(|_| State::default()));
+        self.grid = grid;
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Grow the grid by `margin` default cells on every side.
+    ///
+    /// The cell previously at `(x, y)` is at `(x + margin, y + margin)` afterwards.
+    pub fn pad(&mut self, margin: usize) {
+        let (width, height) = (self.width + 2 * margin, self.height + 2 * margin);
+        let mut old = mem::take(&mut self.grid).into_iter();
+        let mut grid = Vec::with_capacity(width * height);
+        grid.extend((0..margin * width).map(|_| State::default()));
+        for _ in 0..self.height {
+            grid.extend((0..margin).map(|_| State::default()));
+            grid.extend(old.by_ref().take(self.width));
+            grid.extend((0..margin).map(|_| State::default()));
+        }
+        grid.extend((0..margin * width).map(|_| State::default()));
+        self.grid = grid;
+        self.width = width;
+        self.height = height;
+    }
+}
+
+impl<State: Default + Clone> Grid<State> {
+    /// Copy of the `width`×`height` cells with their top-left corner at `(x, y)`.
+    pub fn sub_grid(&self, (x, y): (isize, isize), width: usize, height: usize) -> Self {
+        self.map_cells(width, height, |dx, dy| (x + dx, y + dy))
+    }
+
+    /// The grid rotated by 90° clockwise, swapping width and height.
+    pub fn rotated_cw(&self) -> Self {
+        let height = self.height as isize;
+        self.map_cells(self.height, self.width, |x, y| (y, height - 1 - x))
+    }
+
+    /// The grid rotated by 90° counterclockwise, swapping width and height.
+    pub fn rotated_ccw(&self) -> Self {
+        let width = self.width as isize;
+        self.map_cells(self.height, self.width, |x, y| (width - 1 - y, x))
+    }
+
+    /// The grid mirrored left to right.
+    pub fn flipped_h(&self) -> Self {
+        let width = self.width as isize;
+        self.map_cells(self.width, self.height, |x, y| (width - 1 - x, y))
+    }
+
+    /// The grid mirrored top to bottom.
+    pub fn flipped_v(&self) -> Self {
+        let height = self.height as isize;
+        self.map_cells(self.width, self.height, |x, y| (x, height - 1 - y))
+    }
+
+    /// A new grid, where each cell is copied from the position `source` returns for it.
+    fn map_cells(
+        &self,
+        width: usize,
+        height: usize,
+        source: impl Fn(isize, isize) -> (isize, isize),
+    ) -> Self {
+        let mut grid = Self::with_edge_behavior(width, height, self.edge_behavior);
+        for x in 0..width as isize {
+            for y in 0..height as isize {
+                grid[(x, y)] = self[source(x, y)].clone();
+            }
+        }
+        grid
+    }
+}
+
+/// Largest number of states offered for painting, see [`Automaton::states`].
+pub const MAX_PAINT_STATES: usize = 16;
+
+/// States that can be saved, which requires serde with the `serde` feature.
+#[cfg(feature = "serde")]
+pub trait SerdeState: serde::Serialize + serde::de::DeserializeOwned {}
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> SerdeState for T {}
+/// States that can be saved, which requires serde with the `serde` feature.
+#[cfg(not(feature = "serde"))]
+pub trait SerdeState {}
+#[cfg(not(feature = "serde"))]
+impl<T> SerdeState for T {}
+
+pub trait Automaton {
+    type State: Default + Clone + Hash + PartialEq + SerdeState;
+    type Dimension: Dimension;
+
+    fn update(&self, curr: (isize, isize), grid: &Grid<Self::State>) -> Self::State;
+
+    /// The state a click turns `curr` into.
+    ///
+    /// Repeated clicks cycle through all states that can be drawn, starting
+    /// with the state a click on a default cell paints.
+    fn toggle(&self, curr: Self::State) -> Self::State;
+
+    /// The neighbor count or sum that [`Automaton::update`] decides on, if there is one.
+    ///
+    /// Only used to show the counts, so it must not be expensive.
+    fn neighbor_count(&self, _pos: (isize, isize), _grid: &Grid<Self::State>) -> Option<i32> {
+        None
+    }
+
+    /// Add an agent with the given `id` to `curr`, or remove the agent on it.
+    ///
+    /// `None` if the automaton has no agents.
+    fn toggle_agent(&self, _curr: &Self::State, _id: u8) -> Option<Self::State> {
+        None
+    }
+
+    /// `curr` with one more grain of sand dropped onto it.
+    ///
+    /// `None` if the automaton has no sand.
+    fn drop_sand(&self, _curr: &Self::State) -> Option<Self::State> {
+        None
+    }
+
+    /// CSS color of `curr`, borrowed from `theme` where possible.
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str>;
+
+    /// Whether `curr` counts towards the population.
+    fn is_alive(&self, curr: &Self::State) -> bool;
+
+    /// A random state other than the default state.
+    ///
+    /// The default implementation toggles the default state, which is
+    /// sufficient for two-state automata.
+    fn random_state(&self, _rng: &mut impl FnMut() -> u32) -> Self::State {
+        self.toggle(Self::State::default())
+    }
+
+    /// The states that can be painted, starting with the default state.
+    ///
+    /// The default implementation follows [`Automaton::toggle`] from the
+    /// default state until it cycles, at most [`MAX_PAINT_STATES`] states.
+    fn states(&self) -> Vec<Self::State> {
+        let mut states = vec![Self::State::default()];
+        while states.len() < MAX_PAINT_STATES {
+            let next = self.toggle(states[states.len() - 1].clone());
+            if states.contains(&next) {
+                break;
+            }
+            states.push(next);
+        }
+        states
+    }
+
+    /// The rule string of this automaton, if it can be changed at runtime.
+    fn rule(&self) -> Option<String> {
+        None
+    }
+
+    /// Replace the rule of this automaton by parsing `rule`.
+    fn set_rule(&mut self, _rule: &str) -> Result<(), RuleParseError> {
+        Err(RuleParseError::Unsupported)
+    }
+
+    /// Compute the next generation of the whole grid at once.
+    ///
+    /// Automata with a faster way than calling [`Automaton::update`] for
+    /// every cell, or that depend on the `generation` of `grid`, can
+    /// implement this. `None` falls back to per-cell updates.
+    fn step_grid(&self, _grid: &Grid<Self::State>, _generation: u64) -> Option<Grid<Self::State>> {
+        None
+    }
+
+    /// Compute the generation before `grid`, which is generation `generation`.
+    ///
+    /// Only reversible automata can implement this, `None` means that the
+    /// previous generation is unknown. It is never called for generation zero.
+    fn step_grid_back(
+        &self,
+        _grid: &Grid<Self::State>,
+        _generation: u64,
+    ) -> Option<Grid<Self::State>> {
+        None
+    }
+
+    /// The rule as birth and survival counts, if this is a two-state
+    /// automaton on the Moore neighborhood.
+    ///
+    /// Dense grids of such automata are stepped bit-packed, see [`BitGrid`].
+    /// Alive cells must be `toggle` of the default state, which is dead.
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        None
+    }
+
+    /// Radius of the neighborhood for sparse updates, if supported.
+    ///
+    /// Sparse updates only evaluate cells within this (square) radius of
+    /// alive cells. This requires that every cell that is not alive is in the
+    /// default state and stays so unless an alive cell is within the radius.
+    fn sparse_radius(&self) -> Option<usize> {
+        None
+    }
+
+    /// Numeric parameters of the rule, adjusted with sliders in the settings.
+    fn parameters(&self) -> Vec<Parameter> {
+        Vec::new()
+    }
+
+    /// Set the parameter at `idx` in [`Automaton::parameters`] to `value`.
+    fn set_parameter(&mut self, _idx: usize, _value: f64) {}
+}
+
+/// A numeric parameter of a rule, see [`Automaton::parameters`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Granularity of the slider.
+    pub step: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleParseError {
+    /// The automaton has no configurable rule.
+    Unsupported,
+    /// The rule is missing the given section, i.e. `B` or `S`.
+    MissingSection(char),
+    /// A neighbor count was not a digit or out of range.
+    InvalidCount(char),
+    /// Unexpected trailing input.
+    Trailing(String),
+    /// The rule is not a valid rule number.
+    InvalidNumber(String),
+    /// The rule is not one of the named rules of the automaton.
+    UnknownRule(String),
+    /// The transition table of a [`Turmite`] is malformed.
+    InvalidTable(String),
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "this automaton has no configurable rule"),
+            Self::MissingSection(section) => write!(f, "missing section '{}'", section),
+            Self::InvalidCount(count) => write!(f, "invalid neighbor count '{}'", count),
+            Self::Trailing(rest) => write!(f, "unexpected input '{}'", rest),
+            Self::InvalidNumber(rule) => write!(f, "invalid rule number '{}'", rule),
+            Self::UnknownRule(rule) => write!(f, "unknown rule '{}'", rule),
+            Self::InvalidTable(rule) => write!(f, "invalid transition table '{}'", rule),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Life;
+
+pub trait Dimension {
+    /// Whether cells are hexagons, see [`hex`].
+    const HEXAGONAL: bool = false;
+    /// Whether the grid is a space-time diagram of a single row, with the
+    /// newest generation at the bottom and older ones above it.
+    const SPACETIME: bool = false;
+}
+pub enum D1 {}
+impl Dimension for D1 {
+    const SPACETIME: bool = true;
+}
+pub enum D2 {}
+impl Dimension for D2 {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LifeStates {
+    #[default]
+    Dead,
+    Alife,
+}
+
+/// Continuous state between zero and one, used by [`Lenia`] and [`SmoothLife`].
+///
+/// Compared and hashed by its bits, so that it can be used as a state.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mass(pub f64);
+
+impl PartialEq for Mass {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Mass {}
+
+impl Hash for Mass {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Automaton for Life {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let sum = live_moore_neighbors(pos_x, pos_y, grid);
+        let curr = grid[(pos_x, pos_y)].clone();
+        match (sum, curr) {
+            (2..=3, LifeStates::Alife) => LifeStates::Alife,
+            (3, LifeStates::Dead) => LifeStates::Alife,
+            _ => LifeStates::Dead,
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(live_moore_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        Some(LifeLike::default())
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+/// HighLife, `B36/S23`, known for its replicator.
+///
+/// Wraps a [`LifeLike`], so that the rule can still be edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighLife(pub LifeLike);
+
+impl Default for HighLife {
+    fn default() -> Self {
+        Self(LifeLike {
+            born: 1 << 3 | 1 << 6,
+            survive: 1 << 2 | 1 << 3,
+        })
+    }
+}
+
+impl Automaton for HighLife {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        self.0.update(pos, grid)
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        self.0.neighbor_count(pos, grid)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        self.0.toggle(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        self.0.style(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        self.0.is_alive(curr)
+    }
+
+    fn rule(&self) -> Option<String> {
+        self.0.rule()
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        self.0.set_rule(rule)
+    }
+
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        self.0.two_state_rule()
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        self.0.sparse_radius()
+    }
+}
+
+/// Seeds, `B2/S`: every live cell dies, dead cells with two live neighbors are born.
+///
+/// Wraps a [`LifeLike`], so that the rule can still be edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Seeds(pub LifeLike);
+
+impl Default for Seeds {
+    fn default() -> Self {
+        Self(LifeLike {
+            born: 1 << 2,
+            survive: 0,
+        })
+    }
+}
+
+impl Automaton for Seeds {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        self.0.update(pos, grid)
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        self.0.neighbor_count(pos, grid)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        self.0.toggle(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        self.0.style(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        self.0.is_alive(curr)
+    }
+
+    fn rule(&self) -> Option<String> {
+        self.0.rule()
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        self.0.set_rule(rule)
+    }
+
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        self.0.two_state_rule()
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        self.0.sparse_radius()
+    }
+}
+
+/// Day & Night, `B3678/S34678`.
+///
+/// The rule is symmetric under swapping live and dead cells, so patterns
+/// of dead cells in a live sea behave like their live counterparts. Colors
+/// are inverted to make these "night" patterns stand out.
+///
+/// Wraps a [`LifeLike`], so that the rule can still be edited. Picked as
+/// "Day & Night" in the settings menu, or opened with `?automaton=day-and-night`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayAndNight(pub LifeLike);
+
+impl Default for DayAndNight {
+    fn default() -> Self {
+        Self(LifeLike {
+            born: 1 << 3 | 1 << 6 | 1 << 7 | 1 << 8,
+            survive: 1 << 3 | 1 << 4 | 1 << 6 | 1 << 7 | 1 << 8,
+        })
+    }
+}
+
+impl Automaton for DayAndNight {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        self.0.update(pos, grid)
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        self.0.neighbor_count(pos, grid)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        self.0.toggle(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(&toggle_life(curr.clone()), theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        self.0.is_alive(curr)
+    }
+
+    fn rule(&self) -> Option<String> {
+        self.0.rule()
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        self.0.set_rule(rule)
+    }
+
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        self.0.two_state_rule()
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        self.0.sparse_radius()
+    }
+}
+
+/// A Life-like automaton with a runtime rule in `B3/S23` notation.
+///
+/// Bit `n` of `born` (`survive`) is set if a dead (live) cell with `n`
+/// live Moore neighbors will be alive in the next generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifeLike {
+    pub born: u16,
+    pub survive: u16,
+}
+
+impl LifeLike {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let (born, rest) = parse_section(rule, 'B')?;
+        let rest = rest
+            .strip_prefix('/')
+            .ok_or(RuleParseError::MissingSection('S'))?;
+        let (survive, rest) = parse_section(rest, 'S')?;
+        if !rest.is_empty() {
+            return Err(RuleParseError::Trailing(rest.to_owned()));
+        }
+        Ok(Self { born, survive })
+    }
+}
+
+impl Default for LifeLike {
+    /// Conway's Game of Life, `B3/S23`.
+    fn default() -> Self {
+        Self {
+            born: 1 << 3,
+            survive: 1 << 2 | 1 << 3,
+        }
+    }
+}
+
+impl fmt::Display for LifeLike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        write_counts(f, self.born)?;
+        write!(f, "/S")?;
+        write_counts(f, self.survive)
+    }
+}
+
+impl Automaton for LifeLike {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let sum = live_moore_neighbors(pos_x, pos_y, grid);
+        let mask = match grid[(pos_x, pos_y)] {
+            LifeStates::Dead => self.born,
+            LifeStates::Alife => self.survive,
+        };
+        if mask & 1 << sum != 0 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(live_moore_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
     }
-}
 
-pub trait Automaton {
-    type State: Default + Clone;
-    type Dimension: Dimension;
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
 
-    fn update(curr: (isize, isize), grid: &Grid<Self::State>) -> Self::State;
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        Some(*self)
+    }
 
-    fn toggle(curr: Self::State) -> Self::State;
+    fn sparse_radius(&self) -> Option<usize> {
+        // With B0, dead cells far away from any live cell are born
+        if self.born & 1 == 0 {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
 
-    fn style(curr: &Self::State) -> JsValue;
+/// Whether a totalistic rule counts the center cell into the sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotalisticKind {
+    /// The sum includes the center cell.
+    Inner,
+    /// The sum only includes the neighbors, the center is looked up separately.
+    Outer,
 }
 
-pub struct Life;
+/// A two-state totalistic rule on the Moore neighborhood.
+///
+/// `table[center]` is a bitmask over sums, bit `n` set means that a cell
+/// in state `center` (0 = dead, 1 = alive) with sum `n` will be alive in
+/// the next generation. With [`TotalisticKind::Outer`], `table[0]` and
+/// `table[1]` are the familiar birth and survival sets.
+///
+/// Rules are written in `B3/S23` notation, inner-totalistic rules carry
+/// an additional `/I` suffix, e.g. `B3/S23/I`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotalisticRule {
+    pub kind: TotalisticKind,
+    pub table: [u16; 2],
+}
 
-pub trait Dimension {}
-pub enum D2 {}
-impl Dimension for D2 {}
+impl TotalisticRule {
+    /// Number of live cells in the neighborhood, including the cell itself for inner rules.
+    fn sum(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<LifeStates>) -> u8 {
+        let neighbors = live_moore_neighbors(pos_x, pos_y, grid);
+        match (self.kind, &grid[(pos_x, pos_y)]) {
+            (TotalisticKind::Inner, LifeStates::Alife) => neighbors + 1,
+            _ => neighbors,
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub enum LifeStates {
-    Dead,
-    Alife,
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let (rule, kind) = match rule.strip_suffix("/I") {
+            Some(rule) => (rule, TotalisticKind::Inner),
+            None => (rule, TotalisticKind::Outer),
+        };
+        let LifeLike { born, survive } = LifeLike::parse(rule)?;
+        Ok(Self {
+            kind,
+            table: [born, survive],
+        })
+    }
 }
 
-impl Default for LifeStates {
+impl Default for TotalisticRule {
     fn default() -> Self {
-        Self::Dead
+        let LifeLike { born, survive } = LifeLike::default();
+        Self {
+            kind: TotalisticKind::Outer,
+            table: [born, survive],
+        }
     }
 }
 
-impl Automaton for Life {
+impl fmt::Display for TotalisticRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [born, survive] = self.table;
+        write!(f, "{}", LifeLike { born, survive })?;
+        if self.kind == TotalisticKind::Inner {
+            write!(f, "/I")?;
+        }
+        Ok(())
+    }
+}
+
+impl Automaton for TotalisticRule {
     type State = LifeStates;
     type Dimension = D2;
 
-    fn update((pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
-        let sum: u8 = MooreNeighbors::<1>::new()
-            .filter(|(x, y)| *x != 0 || *y != 0)
-            .map(|(x, y)| match &grid[(x + pos_x, y + pos_y)] {
-                LifeStates::Dead => 0,
-                LifeStates::Alife => 1,
-            })
-            .sum();
-        let curr = grid[(pos_x, pos_y)].clone();
-        match (sum, curr) {
-            (2..=3, LifeStates::Alife) => LifeStates::Alife,
-            (3, LifeStates::Dead) => LifeStates::Alife,
-            _ => LifeStates::Dead,
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let center = (grid[pos] == LifeStates::Alife) as usize;
+        if self.table[center] & 1 << self.sum(pos, grid) != 0 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        Some(self.sum(pos, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn two_state_rule(&self) -> Option<LifeLike> {
+        let [born, survive] = self.table;
+        (self.kind == TotalisticKind::Outer).then_some(LifeLike { born, survive })
+    }
+}
+
+/// Count the direct Moore neighbors of `(pos_x, pos_y)` for which `pred` holds.
+///
+/// Neighbors outside of the grid are never counted.
+fn count_moore_neighbors<State>(
+    pos_x: isize,
+    pos_y: isize,
+    grid: &Grid<State>,
+    pred: impl Fn(&State) -> bool,
+) -> u8 {
+    MooreNeighbors::<1>::new()
+        .filter(|(x, y)| *x != 0 || *y != 0)
+        .filter_map(|(x, y)| grid.get(x + pos_x, y + pos_y))
+        .filter(|state| pred(state))
+        .count() as u8
+}
+
+/// Sum of `value` over the cells at the given offsets from `(pos_x, pos_y)`,
+/// each multiplied by the weight of its offset.
+///
+/// Cells outside of the grid don't contribute.
+fn weighted_sum<State>(
+    pos_x: isize,
+    pos_y: isize,
+    grid: &Grid<State>,
+    weights: &[((isize, isize), i32)],
+    value: impl Fn(&State) -> i32,
+) -> i32 {
+    weights
+        .iter()
+        .filter_map(|&((x, y), weight)| Some(weight * value(grid.get(x + pos_x, y + pos_y)?)))
+        .sum()
+}
+
+/// An asynchronous update pass for automata whose cells move, like [`WaTor`].
+///
+/// Unlike [`Automaton::update`], which computes one cell from the previous
+/// generation, `visit` changes the next generation in place and may write to
+/// any cell, e.g. to move its content to a neighbor. Every cell is visited
+/// once, in an order shuffled by `rng`. `visit` returns the position the
+/// content of the cell moved to, which is not visited again in this pass.
+fn move_pass<State: Clone>(
+    grid: &Grid<State>,
+    rng: &mut XorShift,
+    mut visit: impl FnMut(&mut Grid<State>, (isize, isize), &mut XorShift) -> Option<(isize, isize)>,
+) -> Grid<State> {
+    let (width, height) = (grid.width(), grid.height());
+    let mut order: Vec<_> = (0..width * height).collect();
+    // Fisher-Yates shuffle
+    for idx in (1..order.len()).rev() {
+        order.swap(idx, rng.next_u64() as usize % (idx + 1));
+    }
+    let mut next = grid.clone();
+    let mut moved = vec![false; width * height];
+    for idx in order {
+        if moved[idx] {
+            continue;
+        }
+        let pos = ((idx % width) as isize, (idx / width) as isize);
+        if let Some((x, y)) = visit(&mut next, pos, rng) {
+            moved[y as usize * width + x as usize] = true;
         }
     }
+    next
+}
+
+fn live_moore_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<LifeStates>) -> u8 {
+    count_moore_neighbors(pos_x, pos_y, grid, |state| *state == LifeStates::Alife)
+}
+
+fn toggle_life(curr: LifeStates) -> LifeStates {
+    match curr {
+        LifeStates::Dead => LifeStates::Alife,
+        LifeStates::Alife => LifeStates::Dead,
+    }
+}
+
+fn style_life(curr: &LifeStates, theme: &Theme) -> Cow<'static, str> {
+    match curr {
+        LifeStates::Dead => theme.palette.dead.into(),
+        LifeStates::Alife => theme.palette.alive.into(),
+    }
+}
+
+/// Adds a quarter of the maximum mass, wrapping around to zero.
+fn toggle_mass(curr: Mass) -> Mass {
+    match curr.0 {
+        mass if mass >= 1.0 => Mass(0.0),
+        mass => Mass((mass + 0.25).min(1.0)),
+    }
+}
+
+/// A ramp from dark violet to yellow.
+fn style_mass(curr: &Mass, theme: &Theme) -> Cow<'static, str> {
+    match curr.0 {
+        mass if mass <= 0.0 => theme.palette.dead.into(),
+        mass => format!(
+            "hsl({:.0}, 80%, {:.0}%)",
+            270.0 - 210.0 * mass,
+            20.0 + 40.0 * mass
+        )
+        .into(),
+    }
+}
 
-    fn toggle(curr: Self::State) -> Self::State {
-        match curr {
-            LifeStates::Dead => LifeStates::Alife,
-            LifeStates::Alife => LifeStates::Dead,
+/// Parse `<prefix><digits>` into a bitmask over the digits.
+fn parse_section(input: &str, prefix: char) -> Result<(u16, &str), RuleParseError> {
+    let input = input
+        .strip_prefix(prefix)
+        .ok_or(RuleParseError::MissingSection(prefix))?;
+    let end = input.find('/').unwrap_or(input.len());
+    let mut mask = 0;
+    for count in input[..end].chars() {
+        match count.to_digit(10) {
+            Some(n) if n <= 9 => mask |= 1 << n,
+            _ => return Err(RuleParseError::InvalidCount(count)),
         }
     }
+    Ok((mask, &input[end..]))
+}
 
-    fn style(curr: &Self::State) -> JsValue {
-        match curr {
-            LifeStates::Dead => JsValue::from_str("#1d2021"),
-            LifeStates::Alife => JsValue::from_str("#ebdbb2"),
+fn write_counts(f: &mut fmt::Formatter<'_>, mask: u16) -> fmt::Result {
+    for n in 0..=9 {
+        if mask & 1 << n != 0 {
+            write!(f, "{}", n)?;
         }
     }
+    Ok(())
 }
 
 impl<State> Grid<State> {
@@ -91,18 +1242,30 @@ impl<State> Grid<State> {
     pub fn height(&self) -> usize {
         self.height
     }
-    fn to_idx(&self, x: isize, y: isize) -> usize {
-        let x = if x >= 0 {
-            x as usize % self.width
-        } else {
-            self.width - (x.abs() as usize % self.width)
-        };
-        let y = if y >= 0 {
-            y as usize % self.height
-        } else {
-            self.height - (y.abs() as usize % self.height)
-        };
-        x + y * self.width
+    pub fn edge_behavior(&self) -> EdgeBehavior {
+        self.edge_behavior
+    }
+    pub fn set_edge_behavior(&mut self, edge_behavior: EdgeBehavior) {
+        self.edge_behavior = edge_behavior;
+    }
+    /// Get the cell at the given coordinates.
+    ///
+    /// Returns `None` if the coordinates are outside of a grid
+    /// with [`EdgeBehavior::Dead`].
+    pub fn get(&self, x: isize, y: isize) -> Option<&State> {
+        self.to_idx(x, y).map(|idx| &self.grid[idx])
+    }
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut State> {
+        self.to_idx(x, y).map(move |idx| &mut self.grid[idx])
+    }
+    /// The position inside the grid that `(x, y)` refers to, according to the edge behavior.
+    pub fn normalize(&self, x: isize, y: isize) -> Option<(isize, isize)> {
+        self.to_idx(x, y)
+            .map(|idx| ((idx % self.width) as isize, (idx / self.width) as isize))
+    }
+
+    fn to_idx(&self, x: isize, y: isize) -> Option<usize> {
+        self.edge_behavior.index(x, y, self.width, self.height)
     }
 }
 
@@ -110,14 +1273,13 @@ impl<State> Index<(isize, isize)> for Grid<State> {
     type Output = State;
 
     fn index(&self, (x, y): (isize, isize)) -> &Self::Output {
-        &self.grid[self.to_idx(x, y)]
+        self.get(x, y).expect("Index outside of grid")
     }
 }
 
 impl<State> IndexMut<(isize, isize)> for Grid<State> {
     fn index_mut(&mut self, (x, y): (isize, isize)) -> &mut Self::Output {
-        let idx = self.to_idx(x, y);
-        &mut self.grid[idx]
+        self.get_mut(x, y).expect("Index outside of grid")
     }
 }
 
@@ -138,6 +1300,12 @@ impl<const RANGE: u16> MooreNeighbors<RANGE> {
     }
 }
 
+impl<const RANGE: u16> Default for MooreNeighbors<RANGE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const RANGE: u16> Iterator for MooreNeighbors<RANGE> {
     type Item = (isize, isize);
 
@@ -164,11 +1332,83 @@ impl<const RANGE: u16> Iterator for MooreNeighbors<RANGE> {
     }
 }
 
+pub struct VonNeumannNeighbors<const RANGE: u16> {
+    curr_x: isize,
+    curr_y: isize,
+    done: bool,
+}
+
+impl<const RANGE: u16> VonNeumannNeighbors<RANGE> {
+    pub fn new() -> Self {
+        Self {
+            curr_x: 0,
+            curr_y: -(RANGE as isize),
+            done: false,
+        }
+    }
+}
+
+impl<const RANGE: u16> Default for VonNeumannNeighbors<RANGE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const RANGE: u16> Iterator for VonNeumannNeighbors<RANGE> {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let range = RANGE as isize;
+        let ret = (self.curr_x, self.curr_y);
+        // Width of the diamond in the current row
+        let row_range = range - self.curr_y.abs();
+        if self.curr_x < row_range {
+            // Next column
+            self.curr_x += 1;
+        } else if self.curr_y < range {
+            // Next row
+            self.curr_y += 1;
+            self.curr_x = -(range - self.curr_y.abs());
+        } else {
+            // Last one
+            self.done = true;
+        }
+        Some(ret)
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::collections::HashSet;
 
     use super::*;
+    use crate::theme::THEMES;
+
+    #[test]
+    fn style_follows_theme() {
+        let life = LifeLike::default();
+        let styles: HashSet<_> = THEMES
+            .iter()
+            .map(|theme| life.style(&LifeStates::Alife, theme))
+            .collect();
+        assert_eq!(styles.len(), THEMES.len());
+        assert_eq!(life.style(&LifeStates::Dead, &THEMES[0]), "#1d2021");
+    }
+
+    #[test]
+    fn life_style_colors() {
+        let theme = Theme::default();
+        assert_eq!(Life.style(&LifeStates::Dead, &theme), theme.palette.dead);
+        assert_eq!(Life.style(&LifeStates::Alife, &theme), theme.palette.alive);
+        assert_eq!(Life.style(&LifeStates::Alife, &THEMES[0]), "#ebdbb2");
+        assert!(matches!(
+            Life.style(&LifeStates::Dead, &theme),
+            Cow::Borrowed(_)
+        ));
+    }
 
     #[test]
     fn moore_neighborhood_zero() {
@@ -199,4 +1439,472 @@ mod tests {
         }
         assert_eq!(neighs, eq.into_iter().collect());
     }
+
+    #[test]
+    fn weighted_sum_over_neighborhood() {
+        // Weights 1 to 9 in reading order
+        let weights: Vec<_> = MooreNeighbors::<1>::new()
+            .map(|(x, y)| ((x, y), 5 + x as i32 + 3 * y as i32))
+            .collect();
+        let mut grid: Grid<u8> = Grid::with_edge_behavior(4, 4, EdgeBehavior::Dead);
+        grid[(1, 0)] = 1;
+        grid[(0, 1)] = 2;
+        grid[(2, 2)] = 3;
+        grid[(3, 3)] = 4;
+        let sum = |x, y| weighted_sum(x, y, &grid, &weights, |&v| v as i32);
+        assert_eq!(sum(1, 1), 2 + 2 * 4 + 3 * 9);
+        // Cells outside of the grid count as nothing
+        assert_eq!(sum(0, 0), 6 + 2 * 8);
+        assert_eq!(sum(3, 3), 3 + 4 * 5);
+    }
+
+    #[test]
+    fn von_neumann_neighborhood_zero() {
+        let neighs: Vec<_> = VonNeumannNeighbors::<0>::new().collect();
+        assert_eq!(neighs, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn von_neumann_neighborhood_one() {
+        let neighs: HashSet<_> = VonNeumannNeighbors::<1>::new().collect();
+        let eq = vec![(0, -1), (-1, 0), (0, 0), (1, 0), (0, 1)];
+        assert_eq!(neighs, eq.into_iter().collect());
+    }
+
+    #[test]
+    fn von_neumann_neighborhood_two() {
+        let neighs: Vec<_> = VonNeumannNeighbors::<2>::new().collect();
+        let mut eq = vec![];
+        for x in -2_isize..=2 {
+            for y in -2_isize..=2 {
+                if x.abs() + y.abs() <= 2 {
+                    eq.push((x, y));
+                }
+            }
+        }
+        assert_eq!(neighs.len(), 13);
+        assert_eq!(
+            neighs.into_iter().collect::<HashSet<_>>(),
+            eq.into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn life_like_rule_strings() {
+        let high_life = LifeLike::parse("B36/S23").unwrap();
+        assert_eq!(high_life.born, 1 << 3 | 1 << 6);
+        assert_eq!(high_life.survive, 1 << 2 | 1 << 3);
+        assert_eq!(high_life.to_string(), "B36/S23");
+        let seeds = LifeLike::parse("B2/S").unwrap();
+        assert_eq!(seeds.survive, 0);
+        assert_eq!(seeds.to_string(), "B2/S");
+        assert_eq!(LifeLike::default(), LifeLike::parse("B3/S23").unwrap());
+    }
+
+    #[test]
+    fn life_like_malformed_rule_strings() {
+        assert_eq!(
+            LifeLike::parse(""),
+            Err(RuleParseError::MissingSection('B'))
+        );
+        assert_eq!(
+            LifeLike::parse("B3"),
+            Err(RuleParseError::MissingSection('S'))
+        );
+        assert_eq!(
+            LifeLike::parse("B3/23"),
+            Err(RuleParseError::MissingSection('S'))
+        );
+        assert_eq!(
+            LifeLike::parse("B3a/S23"),
+            Err(RuleParseError::InvalidCount('a'))
+        );
+        assert_eq!(
+            LifeLike::parse("B3/S23/I"),
+            Err(RuleParseError::Trailing("/I".to_owned()))
+        );
+    }
+
+    #[test]
+    fn high_life_birth_on_six() {
+        // The center (2, 2) has exactly six live neighbors
+        let grid = board(&[(1, 1), (2, 1), (3, 1), (1, 3), (2, 3), (3, 3)]);
+        let high_life = LifeLike::parse("B36/S23").unwrap();
+        assert_eq!(step(&high_life, &grid)[(2, 2)], LifeStates::Alife);
+        assert_eq!(step(&LifeLike::default(), &grid)[(2, 2)], LifeStates::Dead);
+    }
+
+    fn right_edge_line(edge_behavior: EdgeBehavior) -> Grid<LifeStates> {
+        let mut grid = Grid::with_edge_behavior(5, 5, edge_behavior);
+        for y in 1..=3 {
+            grid[(4, y)] = LifeStates::Alife;
+        }
+        grid
+    }
+
+    #[test]
+    fn wrapping_edges_influence_opposite_side() {
+        let grid = step(&Life, &right_edge_line(EdgeBehavior::default()));
+        assert_eq!(grid[(0, 2)], LifeStates::Alife);
+    }
+
+    #[test]
+    fn bounded_edges_do_not_influence_opposite_side() {
+        for edge_behavior in [EdgeBehavior::Clamp, EdgeBehavior::Dead] {
+            let grid = step(&Life, &right_edge_line(edge_behavior));
+            assert_eq!(grid[(0, 2)], LifeStates::Dead);
+            // The line itself still oscillates
+            assert_eq!(grid[(3, 2)], LifeStates::Alife);
+        }
+    }
+
+    #[test]
+    fn klein_bottle_mirrors_across_top_edge() {
+        let wrapping = |topology| {
+            let mut grid = Grid::with_edge_behavior(5, 4, EdgeBehavior::Wrap(topology));
+            grid[(1, 3)] = LifeStates::Alife;
+            grid
+        };
+        // The neighbor above (1, 0) is in the bottom row
+        let torus = wrapping(Topology::Torus);
+        assert_eq!(torus.get(1, -1), Some(&LifeStates::Alife));
+        assert_eq!(torus.get(3, -1), Some(&LifeStates::Dead));
+        // ..but in the mirrored column on a Klein bottle
+        let klein = wrapping(Topology::KleinBottle);
+        assert_eq!(klein.get(1, -1), Some(&LifeStates::Dead));
+        assert_eq!(klein.get(3, -1), Some(&LifeStates::Alife));
+        // Crossing twice restores the column, left and right are not mirrored
+        assert_eq!(klein.get(1, -5), Some(&LifeStates::Alife));
+        assert_eq!(klein.get(6, 3), Some(&LifeStates::Alife));
+    }
+
+    #[test]
+    fn topologies_glue_edges() {
+        let index = |topology, x, y| EdgeBehavior::Wrap(topology).index(x, y, 5, 4);
+        assert_eq!(index(Topology::Cylinder, -1, 2), Some(4 + 2 * 5));
+        assert_eq!(index(Topology::Cylinder, 2, -1), None);
+        assert_eq!(index(Topology::Cylinder, 2, 4), None);
+        // Both directions are mirrored on the projective plane
+        assert_eq!(index(Topology::Projective, 1, 4), Some(3));
+        assert_eq!(index(Topology::Projective, 5, 1), Some(2 * 5));
+    }
+
+    #[test]
+    fn oversized_grids_are_rejected() {
+        assert!(Grid::<LifeStates>::try_generate(100, 50).is_ok());
+        assert!(Grid::<LifeStates>::try_generate(MAX_CELLS, 1).is_ok());
+        let too_large = GridError::TooLarge {
+            width: 100_000,
+            height: 100_000,
+            max_cells: MAX_CELLS,
+        };
+        assert_eq!(
+            Grid::<LifeStates>::try_generate(100_000, 100_000),
+            Err(too_large)
+        );
+        assert!(Grid::<LifeStates>::try_generate(MAX_CELLS + 1, 1).is_err());
+        // The product would overflow
+        assert!(cell_count(usize::MAX, 2, usize::MAX).is_err());
+        assert_eq!(cell_count(usize::MAX, 1, usize::MAX), Ok(usize::MAX));
+        assert_eq!(cell_count(0, 5, 0), Ok(0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_snapshots_round_trip() {
+        let mut grid = Grid::generate(3, 2);
+        grid.set_edge_behavior(EdgeBehavior::Wrap(Topology::KleinBottle));
+        grid[(2, 1)] = LifeStates::Alife;
+        let json = grid.to_json().unwrap();
+        assert!(json.starts_with(r#"{"version":1,"grid":{"width":3,"height":2,"#));
+        assert_eq!(Grid::from_json(&json).unwrap(), grid);
+
+        let mut ants: Grid<langtons_ant::AntCell> = Grid::generate(2, 2);
+        ants[(0, 1)].ant = Some(langtons_ant::Heading::West);
+        assert_eq!(Grid::from_json(&ants.to_json().unwrap()).unwrap(), ants);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_json_snapshots_are_rejected() {
+        let json = Grid::<LifeStates>::generate(2, 2).to_json().unwrap();
+        let newer = json.replacen(r#""version":1"#, r#""version":2"#, 1);
+        assert!(matches!(
+            Grid::<LifeStates>::from_json(&newer),
+            Err(SnapshotError::UnsupportedVersion(2))
+        ));
+        let wrong_size = json.replacen(r#""width":2"#, r#""width":3"#, 1);
+        assert!(Grid::<LifeStates>::from_json(&wrong_size).is_err());
+        assert!(Grid::<LifeStates>::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn dead_edges_have_no_cells() {
+        let grid: Grid<LifeStates> = Grid::with_edge_behavior(5, 5, EdgeBehavior::Dead);
+        assert!(grid.get(-1, 0).is_none());
+        assert!(grid.get(0, 5).is_none());
+        assert!(grid.get(4, 4).is_some());
+    }
+
+    #[test]
+    fn resize_keeps_overlapping_cells() {
+        let mut grid: Grid<u8> = Grid::generate(3, 3);
+        for x in 0..3 {
+            for y in 0..3 {
+                grid[(x, y)] = (1 + x + 3 * y) as u8;
+            }
+        }
+        grid.resize(5, 5);
+        assert_eq!((grid.width(), grid.height()), (5, 5));
+        for x in 0..5 {
+            for y in 0..5 {
+                let expected = if x < 3 && y < 3 { 1 + x + 3 * y } else { 0 };
+                assert_eq!(grid[(x, y)], expected as u8);
+            }
+        }
+        grid.resize(2, 1);
+        assert_eq!(grid.grid, vec![1, 2]);
+    }
+
+    #[test]
+    fn pad_moves_cells_by_margin() {
+        let mut grid: Grid<u8> = Grid::generate(3, 2);
+        for x in 0..3 {
+            for y in 0..2 {
+                grid[(x, y)] = (1 + x + 3 * y) as u8;
+            }
+        }
+        grid.pad(2);
+        assert_eq!((grid.width(), grid.height()), (7, 6));
+        for x in 0..7 {
+            for y in 0..6 {
+                let inside = (2..5).contains(&x) && (2..4).contains(&y);
+                let expected = if inside { 1 + (x - 2) + 3 * (y - 2) } else { 0 };
+                assert_eq!(grid[(x, y)], expected as u8);
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_and_flip_grid() {
+        // 0 1 2
+        // 3 4 5
+        let mut grid = Grid::generate(3, 2);
+        for (idx, cell) in grid.grid.iter_mut().enumerate() {
+            *cell = idx;
+        }
+        assert_eq!(grid.rotated_cw().grid, vec![3, 0, 4, 1, 5, 2]);
+        assert_eq!(grid.rotated_ccw().grid, vec![2, 5, 1, 4, 0, 3]);
+        assert_eq!(grid.flipped_h().grid, vec![2, 1, 0, 5, 4, 3]);
+        assert_eq!(grid.flipped_v().grid, vec![3, 4, 5, 0, 1, 2]);
+        assert_eq!(grid.rotated_cw().width(), 2);
+        assert_eq!(grid.sub_grid((1, 0), 2, 2).grid, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn negative_coordinates_wrap() {
+        let grid: Grid<LifeStates> = Grid::generate(20, 20);
+        assert_eq!(grid.to_idx(-1, -1), Some(19 + 19 * 20));
+        assert_eq!(grid.to_idx(-20, -20), Some(0));
+        assert_eq!(grid.to_idx(-21, 0), Some(19));
+        assert_eq!(grid.to_idx(20, 41), Some(20));
+    }
+
+    pub(crate) fn step<A: Automaton>(automaton: &A, grid: &Grid<A::State>) -> Grid<A::State> {
+        let mut next = grid.clone();
+        for x in 0..grid.width() as isize {
+            for y in 0..grid.height() as isize {
+                next[(x, y)] = automaton.update((x, y), grid);
+            }
+        }
+        next
+    }
+
+    fn board(cells: &[(isize, isize)]) -> Grid<LifeStates> {
+        let mut grid = Grid::generate(10, 10);
+        for &cell in cells {
+            grid[cell] = LifeStates::Alife;
+        }
+        grid
+    }
+
+    const GLIDER_AND_BLINKER: [(isize, isize); 8] = [
+        (1, 0),
+        (2, 1),
+        (0, 2),
+        (1, 2),
+        (2, 2),
+        (6, 6),
+        (7, 6),
+        (8, 6),
+    ];
+
+    /// Number of generations until `cells` reappear in Life, up to `max`.
+    fn life_period(cells: &[(isize, isize)], max: usize) -> Option<usize> {
+        let mut start = Grid::generate(17, 17);
+        for &cell in cells {
+            start[cell] = LifeStates::Alife;
+        }
+        let mut grid = start.clone();
+        (1..=max).find(|_| {
+            grid = step(&Life, &grid);
+            grid == start
+        })
+    }
+
+    #[test]
+    fn life_oscillator_periods() {
+        let blinker = [(1, 2), (2, 2), (3, 2)];
+        let toad = [(2, 2), (3, 2), (4, 2), (1, 3), (2, 3), (3, 3)];
+        let beacon = [(1, 1), (2, 1), (1, 2), (4, 3), (3, 4), (4, 4)];
+        let pulsar: Vec<_> = [0, 5, 7, 12]
+            .iter()
+            .flat_map(|&line| [2, 3, 4, 8, 9, 10].map(|cell| [(line, cell), (cell, line)]))
+            .flatten()
+            .map(|(x, y)| (x + 2, y + 2))
+            .collect();
+        assert_eq!(life_period(&[(1, 1), (2, 1), (1, 2), (2, 2)], 5), Some(1));
+        assert_eq!(life_period(&blinker, 5), Some(2));
+        assert_eq!(life_period(&toad, 5), Some(2));
+        assert_eq!(life_period(&beacon, 5), Some(2));
+        assert_eq!(life_period(&pulsar, 5), Some(3));
+        // Gliders move away instead
+        assert_eq!(
+            life_period(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)], 5),
+            None
+        );
+    }
+
+    #[test]
+    fn highlife_replicates() {
+        let replicator = [
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (1, 1),
+            (4, 1),
+            (0, 2),
+            (4, 2),
+            (0, 3),
+            (3, 3),
+            (0, 4),
+            (1, 4),
+            (2, 4),
+        ];
+        let mut grid = Grid::generate(31, 31);
+        for &(x, y) in &replicator {
+            grid[(x + 13, y + 13)] = LifeStates::Alife;
+        }
+        let population = |grid: &Grid<LifeStates>| {
+            (0..31)
+                .flat_map(|x| (0..31).map(move |y| (x, y)))
+                .filter(|&pos| grid[pos] == LifeStates::Alife)
+                .count()
+        };
+        let rule = HighLife::default();
+        assert_eq!(rule.rule().as_deref(), Some("B36/S23"));
+        let high_life = (0..12).fold(grid.clone(), |grid, _| step(&rule, &grid));
+        assert_eq!(population(&high_life), 2 * replicator.len());
+        let life_like = LifeLike::parse("B36/S23").unwrap();
+        assert_eq!(
+            high_life,
+            (0..12).fold(grid.clone(), |grid, _| step(&life_like, &grid))
+        );
+        // Life cannot sustain it
+        let life = (0..12).fold(grid, |grid, _| step(&Life, &grid));
+        assert_ne!(population(&life), 2 * replicator.len());
+    }
+
+    #[test]
+    fn seeds_domino_spreads_and_dies() {
+        let grid = board(&[(2, 2), (3, 2)]);
+        let seeds = Seeds::default();
+        assert_eq!(seeds.rule().as_deref(), Some("B2/S"));
+        assert_eq!(seeds.sparse_radius(), Some(1));
+        let next = step(&seeds, &grid);
+        assert_eq!(next, board(&[(2, 1), (3, 1), (2, 3), (3, 3)]));
+        assert_eq!(next, step(&LifeLike::parse("B2/S").unwrap(), &grid));
+        assert_eq!(step(&seeds, &board(&[(2, 2)])), board(&[]));
+    }
+
+    #[test]
+    fn day_and_night_is_symmetric() {
+        let invert = |grid: &Grid<LifeStates>| {
+            let mut inverted = grid.clone();
+            for x in 0..10 {
+                for y in 0..10 {
+                    inverted[(x, y)] = toggle_life(grid[(x, y)].clone());
+                }
+            }
+            inverted
+        };
+        let grid = board(&GLIDER_AND_BLINKER);
+        let day_and_night = DayAndNight::default();
+        assert_eq!(day_and_night.rule().as_deref(), Some("B3678/S34678"));
+        let next = step(&day_and_night, &grid);
+        assert_eq!(next, step(&LifeLike::parse("B3678/S34678").unwrap(), &grid));
+        assert_eq!(step(&day_and_night, &invert(&grid)), invert(&next));
+        let theme = &THEMES[0];
+        assert_eq!(
+            day_and_night.style(&LifeStates::Alife, theme),
+            Life.style(&LifeStates::Dead, theme)
+        );
+    }
+
+    #[test]
+    fn neighbor_counts() {
+        let grid = board(&[(1, 1), (2, 1), (3, 1)]);
+        let count = |automaton: &dyn Fn((isize, isize)) -> Option<i32>| {
+            [(2, 0), (2, 1), (0, 0), (5, 5)].map(automaton)
+        };
+        let life = count(&|pos| Life.neighbor_count(pos, &grid));
+        assert_eq!(life, [Some(3), Some(2), Some(1), Some(0)]);
+        let outer = TotalisticRule::parse("B3/S23").unwrap();
+        let inner = TotalisticRule::parse("B3/S23/I").unwrap();
+        assert_eq!(count(&|pos| outer.neighbor_count(pos, &grid)), life);
+        // Inner totalistic rules count the cell itself
+        let inner = count(&|pos| inner.neighbor_count(pos, &grid));
+        assert_eq!(inner, [Some(3), Some(3), Some(1), Some(0)]);
+        assert_eq!(Margolus::default().neighbor_count((2, 0), &grid), None);
+    }
+
+    #[test]
+    fn outer_totalistic_conway_matches_life() {
+        let rule = TotalisticRule::parse("B3/S23").unwrap();
+        let mut life_grid = board(&GLIDER_AND_BLINKER);
+        let mut rule_grid = life_grid.clone();
+        for _ in 0..8 {
+            life_grid = step(&Life, &life_grid);
+            rule_grid = step(&rule, &rule_grid);
+            assert_eq!(life_grid.grid, rule_grid.grid);
+        }
+    }
+
+    #[test]
+    fn inner_totalistic_differs_from_outer() {
+        let outer = TotalisticRule::parse("B3/S23").unwrap();
+        let inner = TotalisticRule {
+            kind: TotalisticKind::Inner,
+            ..outer.clone()
+        };
+        let grid = board(&GLIDER_AND_BLINKER);
+        assert_ne!(step(&outer, &grid).grid, step(&inner, &grid).grid);
+    }
+
+    #[test]
+    fn totalistic_rule_strings() {
+        let rule = TotalisticRule::parse("B36/S23/I").unwrap();
+        assert_eq!(rule.kind, TotalisticKind::Inner);
+        assert_eq!(rule.table, [1 << 3 | 1 << 6, 1 << 2 | 1 << 3]);
+        assert_eq!(rule.to_string(), "B36/S23/I");
+        assert_eq!(TotalisticRule::default().to_string(), "B3/S23");
+        assert_eq!(
+            TotalisticRule::parse("S23"),
+            Err(RuleParseError::MissingSection('B'))
+        );
+        assert_eq!(
+            TotalisticRule::parse("B3/S2x"),
+            Err(RuleParseError::InvalidCount('x'))
+        );
+    }
 }
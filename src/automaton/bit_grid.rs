@@ -0,0 +1,346 @@
+use super::{EdgeBehavior, Grid, LifeStates};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Bit-packed grid for two-state automata, one bit per cell.
+///
+/// Every row starts at a new word, so that stepping can count the
+/// neighbors of a whole word of cells at once. Bits past the end of a row
+/// are always clear.
+///
+/// Indexing follows [`Grid`], including its [`EdgeBehavior`]. Stepping
+/// resolves rows and columns separately, so mirrored topologies are not
+/// supported there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    edge_behavior: EdgeBehavior,
+    /// Number of words per row.
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl Default for BitGrid {
+    fn default() -> Self {
+        Self::with_edge_behavior(0, 0, EdgeBehavior::default())
+    }
+}
+
+impl BitGrid {
+    pub fn with_edge_behavior(width: usize, height: usize, edge_behavior: EdgeBehavior) -> Self {
+        let stride = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            height,
+            edge_behavior,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    /// Pack a Life grid, alive cells become set bits.
+    pub fn from_life(grid: &Grid<LifeStates>) -> Self {
+        let mut bits = Self::default();
+        bits.pack(grid, |state| *state == LifeStates::Alife);
+        bits
+    }
+
+    /// Unpack into a Life grid.
+    pub fn to_life(&self) -> Grid<LifeStates> {
+        let mut grid = Grid::with_edge_behavior(self.width, self.height, self.edge_behavior);
+        self.unpack_into(&mut grid, &LifeStates::Alife);
+        grid
+    }
+
+    /// Replace the bits with the cells of `grid`, setting those for which
+    /// `is_alive` holds. The words are reused if the size matches.
+    pub fn pack<State>(&mut self, grid: &Grid<State>, is_alive: impl Fn(&State) -> bool) {
+        self.width = grid.width();
+        self.height = grid.height();
+        self.edge_behavior = grid.edge_behavior();
+        self.stride = self.width.div_ceil(WORD_BITS);
+        self.words.clear();
+        self.words.resize(self.stride * self.height, 0);
+        for (y, row) in grid.grid.chunks(self.width.max(1)).enumerate() {
+            for (x, state) in row.iter().enumerate() {
+                if is_alive(state) {
+                    self.words[y * self.stride + x / WORD_BITS] |= 1 << (x % WORD_BITS);
+                }
+            }
+        }
+    }
+
+    /// Overwrite the cells of `grid`, which has the same size, with `alive`
+    /// for set bits and the default state otherwise.
+    pub fn unpack_into<State: Clone + Default>(&self, grid: &mut Grid<State>, alive: &State) {
+        debug_assert_eq!((grid.width(), grid.height()), (self.width, self.height));
+        for (y, row) in grid.grid.chunks_mut(self.width.max(1)).enumerate() {
+            for (x, state) in row.iter_mut().enumerate() {
+                *state = if self.bit(x, y) {
+                    alive.clone()
+                } else {
+                    State::default()
+                };
+            }
+        }
+    }
+
+    /// Whether the cell is set, `false` outside of a grid with [`EdgeBehavior::Dead`].
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        self.edge_behavior
+            .index(x, y, self.width, self.height)
+            .map(|idx| self.bit(idx % self.width, idx / self.width))
+            .unwrap_or(false)
+    }
+
+    /// Set or clear the cell, cells outside of a grid with [`EdgeBehavior::Dead`] are ignored.
+    pub fn set(&mut self, x: isize, y: isize, value: bool) {
+        if let Some(idx) = self.edge_behavior.index(x, y, self.width, self.height) {
+            let (x, y) = (idx % self.width, idx / self.width);
+            let mask = 1 << (x % WORD_BITS);
+            let word = &mut self.words[y * self.stride + x / WORD_BITS];
+            if value {
+                *word |= mask;
+            } else {
+                *word &= !mask;
+            }
+        }
+    }
+
+    /// The next generation of Conway's Game of Life (B3/S23).
+    pub fn step_life(&self) -> Self {
+        let mut next = Self::default();
+        self.step_life_like(1 << 3, 1 << 2 | 1 << 3, &mut next);
+        next
+    }
+
+    /// Write the next generation of the Life-like rule into `next`, which
+    /// is resized if necessary.
+    ///
+    /// Bit `n` of `born` and `survive` is set if cells with `n` alive
+    /// neighbors are born or survive, like in [`super::LifeLike`].
+    pub fn step_life_like(&self, born: u16, survive: u16, next: &mut Self) {
+        next.width = self.width;
+        next.height = self.height;
+        next.edge_behavior = self.edge_behavior;
+        next.stride = self.stride;
+        next.words.clear();
+        next.words.resize(self.words.len(), 0);
+        if self.width == 0 {
+            return;
+        }
+        // Resolve the edge behavior once per row and column instead of per neighbor
+        let column = |x| self.edge_behavior.index(x, 0, self.width, 1);
+        let row = |y| self.edge_behavior.index(0, y, 1, self.height);
+        let (left, right) = (column(-1), column(self.width as isize));
+        let last_mask = match self.width % WORD_BITS {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        };
+        for y in 0..self.height {
+            // Missing rows, e.g. beyond dead edges, count as empty
+            let rows = [row(y as isize - 1), Some(y), row(y as isize + 1)].map(|y| {
+                y.map(|y| {
+                    let row = self.row(y);
+                    let bit = |x: Option<usize>| {
+                        x.map_or(0, |x| row[x / WORD_BITS] >> (x % WORD_BITS) & 1)
+                    };
+                    (row, (bit(left), bit(right)))
+                })
+            });
+            for idx in 0..self.stride {
+                let [above, center, below] = rows.map(|row| match row {
+                    Some((row, edges)) => {
+                        let (west, east) = shifted(row, idx, edges, self.width);
+                        [west, row[idx], east]
+                    }
+                    None => [0; 3],
+                });
+                let count = count_bits([
+                    above[0], above[1], above[2], center[0], center[2], below[0], below[1],
+                    below[2],
+                ]);
+                let alive = center[1];
+                let mut word = 0;
+                for n in 0..=8 {
+                    let is_n = equals(count, n);
+                    if born >> n & 1 == 1 {
+                        word |= is_n & !alive;
+                    }
+                    if survive >> n & 1 == 1 {
+                        word |= is_n & alive;
+                    }
+                }
+                if idx == self.stride - 1 {
+                    word &= last_mask;
+                }
+                next.words[y * self.stride + idx] = word;
+            }
+        }
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    fn bit(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.stride + x / WORD_BITS] >> (x % WORD_BITS) & 1 == 1
+    }
+}
+
+/// The western and eastern neighbors of the cells in word `idx` of `row`,
+/// i.e. the words with every cell replaced by its left and right neighbor.
+///
+/// `(left, right)` are the bits beyond the first and last cell of the row
+/// of length `width`.
+fn shifted(row: &[u64], idx: usize, (left, right): (u64, u64), width: usize) -> (u64, u64) {
+    let carry_west = match idx {
+        0 => left,
+        _ => row[idx - 1] >> (WORD_BITS - 1),
+    };
+    let west = row[idx] << 1 | carry_west;
+    let mut east = row[idx] >> 1;
+    if idx + 1 < row.len() {
+        east |= row[idx + 1] << (WORD_BITS - 1);
+    } else {
+        // The bit past the last cell is clear
+        east |= right << ((width - 1) % WORD_BITS);
+    }
+    (west, east)
+}
+
+/// Add up eight words bitwise, the result holds bit `k` of every sum in word `k`.
+fn count_bits([n1, n2, n3, n4, n5, n6, n7, n8]: [u64; 8]) -> [u64; 4] {
+    let (s0, c0) = full_add(n1, n2, n3);
+    let (s1, c1) = full_add(n4, n5, n6);
+    let (s2, c2) = (n7 ^ n8, n7 & n8);
+    let (ones, c3) = full_add(s0, s1, s2);
+    // Sum the four carries of weight two
+    let (t0, t1) = full_add(c0, c1, c2);
+    let (twos, c4) = (t0 ^ c3, t0 & c3);
+    let (fours, eights) = (t1 ^ c4, t1 & c4);
+    [ones, twos, fours, eights]
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    (a ^ b ^ c, a & b | c & (a ^ b))
+}
+
+/// Mask of the cells for which the sum in `count` is `n`.
+fn equals(count: [u64; 4], n: usize) -> u64 {
+    count
+        .iter()
+        .enumerate()
+        .fold(!0, |mask, (bit, &word)| match n >> bit & 1 {
+            1 => mask & word,
+            _ => mask & !word,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::*;
+    use crate::{
+        automaton::{tests::step, Life, LifeLike, Topology},
+        random::XorShift,
+    };
+
+    #[test]
+    fn matches_bool_reference() {
+        let mut rng = XorShift::new(42);
+        let (width, height) = (37, 23);
+        let mut bits = BitGrid::with_edge_behavior(width, height, EdgeBehavior::default());
+        let mut reference = vec![false; width * height];
+        for _ in 0..5000 {
+            let x = (rng.next_u32() as usize % width) as isize;
+            let y = (rng.next_u32() as usize % height) as isize;
+            let value = rng.next_f64() < 0.5;
+            bits.set(x, y, value);
+            reference[x as usize + y as usize * width] = value;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(bits.get(x as isize, y as isize), reference[x + y * width]);
+            }
+        }
+        // Indexing outside wraps like `Grid`
+        assert_eq!(bits.get(-1, -1), reference[width * height - 1]);
+        assert_eq!(bits.get(width as isize, 0), reference[0]);
+    }
+
+    #[test]
+    fn dead_edges() {
+        let mut bits = BitGrid::with_edge_behavior(3, 3, EdgeBehavior::Dead);
+        bits.set(-1, 0, true);
+        assert_eq!(bits, BitGrid::with_edge_behavior(3, 3, EdgeBehavior::Dead));
+        assert!(!bits.get(3, 0));
+    }
+
+    /// Random Life grids of the given size with every separable edge behavior.
+    fn random_grids(width: usize, height: usize, seed: u64) -> Vec<Grid<LifeStates>> {
+        let mut rng = XorShift::new(seed);
+        [
+            EdgeBehavior::default(),
+            EdgeBehavior::Wrap(Topology::Cylinder),
+            EdgeBehavior::Clamp,
+            EdgeBehavior::Dead,
+        ]
+        .map(|edge_behavior| {
+            let mut grid = Grid::with_edge_behavior(width, height, edge_behavior);
+            for state in grid.grid.iter_mut() {
+                if rng.next_f64() < 0.4 {
+                    *state = LifeStates::Alife;
+                }
+            }
+            grid
+        })
+        .into()
+    }
+
+    #[test]
+    fn step_matches_life() {
+        // Rows within a word, across word boundaries and of exactly one word
+        for (width, height) in [(20, 15), (130, 6), (64, 5), (1, 3)] {
+            for mut grid in random_grids(width, height, 7) {
+                let mut bits = BitGrid::from_life(&grid);
+                assert_eq!(bits.to_life(), grid);
+                for _ in 0..10 {
+                    grid = step(&Life, &grid);
+                    bits = bits.step_life();
+                    assert_eq!(bits.to_life(), grid);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn step_matches_life_like() {
+        // Including a rule with B0, which must not set bits past the rows
+        for rule in ["B36/S23", "B3678/S34678", "B2/S", "B0/S8", "B012345678/S"] {
+            let rule = LifeLike::parse(rule).unwrap();
+            for mut grid in random_grids(70, 9, 3) {
+                let (mut bits, mut next) = (BitGrid::from_life(&grid), BitGrid::default());
+                for _ in 0..5 {
+                    grid = step(&rule, &grid);
+                    bits.step_life_like(rule.born, rule.survive, &mut next);
+                    mem::swap(&mut bits, &mut next);
+                    assert_eq!(bits.to_life(), grid, "{}", rule);
+                }
+            }
+        }
+    }
+
+    /// A `BitGrid` needs a single bit per cell, while `Grid<LifeStates>`
+    /// needs a byte, i.e. eight times as much. Stepping also counts the
+    /// neighbors of a whole word of cells with a few bitwise additions,
+    /// instead of looking up eight neighbors per cell.
+    #[test]
+    fn uses_one_bit_per_cell() {
+        let bits = BitGrid::with_edge_behavior(512, 512, EdgeBehavior::default());
+        assert_eq!(mem::size_of_val(&bits.words[..]), 512 * 512 / 8);
+        assert_eq!(mem::size_of::<LifeStates>(), 1);
+    }
+}
@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{count_moore_neighbors, Automaton, Grid, D2};
+
+/// Brian's Brain, a three-state automaton.
+///
+/// An `Off` cell turns `On` if exactly two of its Moore neighbors are `On`,
+/// `On` cells always start `Dying` and `Dying` cells always turn `Off`.
+#[derive(Debug, Clone, Default)]
+pub struct BriansBrain;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrainState {
+    #[default]
+    Off,
+    Dying,
+    On,
+}
+
+fn on_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<BrainState>) -> u8 {
+    count_moore_neighbors(pos_x, pos_y, grid, |s| *s == BrainState::On)
+}
+
+impl Automaton for BriansBrain {
+    type State = BrainState;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        match grid[(pos_x, pos_y)] {
+            BrainState::Off => {
+                if on_neighbors(pos_x, pos_y, grid) == 2 {
+                    BrainState::On
+                } else {
+                    BrainState::Off
+                }
+            }
+            BrainState::On => BrainState::Dying,
+            BrainState::Dying => BrainState::Off,
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(on_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            BrainState::Off => BrainState::On,
+            BrainState::On => BrainState::Dying,
+            BrainState::Dying => BrainState::Off,
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == BrainState::On
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % 2 {
+            0 => BrainState::Dying,
+            _ => BrainState::On,
+        }
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            BrainState::Off => theme.palette.dead.into(),
+            BrainState::Dying => theme.palette.blue.into(),
+            BrainState::On => theme.palette.alive.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::tests::step;
+
+    #[test]
+    fn three_state_transitions() {
+        let mut grid = Grid::generate(6, 6);
+        grid[(1, 1)] = BrainState::On;
+        grid[(1, 2)] = BrainState::On;
+        grid[(4, 4)] = BrainState::Dying;
+        let next = step(&BriansBrain, &grid);
+        // On cells start dying, dying cells turn off
+        assert_eq!(next[(1, 1)], BrainState::Dying);
+        assert_eq!(next[(1, 2)], BrainState::Dying);
+        assert_eq!(next[(4, 4)], BrainState::Off);
+        // Off cells with exactly two On neighbors turn on
+        assert_eq!(next[(0, 1)], BrainState::On);
+        assert_eq!(next[(2, 2)], BrainState::On);
+        // Off cells with one On neighbor stay off
+        assert_eq!(next[(0, 3)], BrainState::Off);
+        // A second step lets the dying cells turn off
+        let next = step(&BriansBrain, &next);
+        assert_eq!(next[(1, 1)], BrainState::Off);
+    }
+
+    #[test]
+    fn toggle_cycles_through_all_states() {
+        let states: Vec<_> = std::iter::successors(Some(BrainState::Off), |state| {
+            Some(BriansBrain.toggle(state.clone()))
+        })
+        .take(4)
+        .collect();
+        assert_eq!(
+            states,
+            [
+                BrainState::Off,
+                BrainState::On,
+                BrainState::Dying,
+                BrainState::Off
+            ]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{Automaton, Grid, RuleParseError, D2};
+
+/// Largest supported neighborhood radius.
+pub const MAX_RADIUS: usize = 10;
+/// Largest supported number of colors.
+pub const MAX_COLORS: u8 = 64;
+
+/// The cyclic cellular automaton.
+///
+/// Every cell has one of `colors` colors. A cell advances to the next color,
+/// wrapping around to zero after the last one, if at least `threshold` cells
+/// in its neighborhood already have that color. From random soup, this grows
+/// spiral "demons". Rules use the `R1/T1/C14/NN` notation, with `NM` for the
+/// Moore and `NN` for the von Neumann neighborhood.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cyclic {
+    pub radius: usize,
+    pub threshold: u32,
+    /// Number of colors, at least two.
+    pub colors: u8,
+    /// Use the von Neumann instead of the Moore neighborhood.
+    pub von_neumann: bool,
+}
+
+/// Color of a cell in a [`Cyclic`] automaton.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CyclicState(pub u8);
+
+impl Default for Cyclic {
+    /// Griffeath's demons, `R1/T1/C14/NN`.
+    fn default() -> Self {
+        Self {
+            radius: 1,
+            threshold: 1,
+            colors: 14,
+            von_neumann: true,
+        }
+    }
+}
+
+impl Cyclic {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char| {
+            let part = sections
+                .next()
+                .ok_or(RuleParseError::MissingSection(section))?;
+            part.strip_prefix(section)
+                .ok_or(RuleParseError::MissingSection(section))
+        };
+        let number = |value: &str| -> Result<usize, _> {
+            value
+                .parse()
+                .map_err(|_| RuleParseError::InvalidNumber(value.to_owned()))
+        };
+        let radius = match number(next('R')?)? {
+            radius @ 1..=MAX_RADIUS => radius,
+            radius => return Err(RuleParseError::InvalidNumber(radius.to_string())),
+        };
+        let threshold = number(next('T')?)? as u32;
+        let colors = match number(next('C')?)? {
+            colors if (2..=MAX_COLORS as usize).contains(&colors) => colors as u8,
+            colors => return Err(RuleParseError::InvalidNumber(colors.to_string())),
+        };
+        let von_neumann = match next('N')? {
+            "M" => false,
+            "N" => true,
+            other => return Err(RuleParseError::UnknownRule(format!("N{}", other))),
+        };
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        Ok(Self {
+            radius,
+            threshold,
+            colors,
+            von_neumann,
+        })
+    }
+
+    fn successor(&self, state: CyclicState) -> CyclicState {
+        CyclicState((state.0 + 1) % self.colors)
+    }
+
+    /// Number of neighbors of `(pos_x, pos_y)` that have the next color.
+    fn count(&self, pos_x: isize, pos_y: isize, grid: &Grid<CyclicState>) -> u32 {
+        let successor = self.successor(grid[(pos_x, pos_y)]);
+        let radius = self.radius as isize;
+        let mut count = 0;
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let outside = self.von_neumann && x.abs() + y.abs() > radius;
+                if (x, y) == (0, 0) || outside {
+                    continue;
+                }
+                if grid.get(pos_x + x, pos_y + y) == Some(&successor) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+impl fmt::Display for Cyclic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{}/T{}/C{}/N{}",
+            self.radius,
+            self.threshold,
+            self.colors,
+            if self.von_neumann { 'N' } else { 'M' }
+        )
+    }
+}
+
+impl Automaton for Cyclic {
+    type State = CyclicState;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let curr = grid[(pos_x, pos_y)];
+        if self.count(pos_x, pos_y, grid) >= self.threshold {
+            self.successor(curr)
+        } else {
+            curr
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(self.count(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        self.successor(curr)
+    }
+
+    /// Colors are spread evenly around the hue circle.
+    fn style(&self, curr: &Self::State, _theme: &Theme) -> Cow<'static, str> {
+        let hue = 360.0 * (curr.0 % self.colors) as f64 / self.colors as f64;
+        format!("hsl({:.0}, 70%, 50%)", hue).into()
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        curr.0 != 0
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        CyclicState(1 + (rng() % (self.colors as u32 - 1)) as u8)
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{automaton::tests::step, theme::THEMES};
+
+    #[test]
+    fn parse_rules() {
+        let demons = Cyclic::parse("R1/T1/C14/NN").unwrap();
+        assert_eq!(demons, Cyclic::default());
+        assert_eq!(demons.to_string(), "R1/T1/C14/NN");
+        assert_eq!(
+            Cyclic::parse("R1/T3/C3/NM"),
+            Ok(Cyclic {
+                radius: 1,
+                threshold: 3,
+                colors: 3,
+                von_neumann: false,
+            })
+        );
+        assert_eq!(
+            Cyclic::parse("R1/T3"),
+            Err(RuleParseError::MissingSection('C'))
+        );
+        assert_eq!(
+            Cyclic::parse("R1/T3/C1/NM"),
+            Err(RuleParseError::InvalidNumber("1".to_owned()))
+        );
+        assert_eq!(
+            Cyclic::parse("R1/T3/C3/NX"),
+            Err(RuleParseError::UnknownRule("NX".to_owned()))
+        );
+    }
+
+    #[test]
+    fn cells_advance_past_threshold() {
+        let rule = Cyclic::parse("R1/T2/C3/NM").unwrap();
+        let mut grid = Grid::generate(5, 5);
+        grid[(1, 1)] = CyclicState(1);
+        grid[(3, 1)] = CyclicState(1);
+        grid[(2, 3)] = CyclicState(2);
+        let next = step(&rule, &grid);
+        // Two neighbors with the next color
+        assert_eq!(next[(2, 2)], CyclicState(1));
+        assert_eq!(next[(2, 1)], CyclicState(1));
+        // Only one
+        assert_eq!(next[(0, 1)], CyclicState(0));
+        // The last color wraps around only with enough zeros next to it
+        assert_eq!(next[(2, 3)], CyclicState(0));
+        assert_eq!(next[(1, 1)], CyclicState(1));
+    }
+
+    #[test]
+    fn colors_are_distinct() {
+        let rule = Cyclic::default();
+        let styles: HashSet<_> = (0..rule.colors)
+            .map(|color| rule.style(&CyclicState(color), &THEMES[0]))
+            .collect();
+        assert_eq!(styles.len(), rule.colors as usize);
+        assert_eq!(rule.states().len(), rule.colors as usize);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{style_life, toggle_life, Automaton, Grid, LifeStates, RuleParseError, D1};
+
+/// Elementary one-dimensional automaton, given by its Wolfram rule number.
+///
+/// The grid shows a space-time diagram: every generation moves all rows up
+/// by one and computes a new bottom row, where each cell depends on itself
+/// and its left and right neighbor in the previous bottom row. With
+/// [`Automaton::step_grid`], the rows are shifted in place and only the
+/// bottom row is computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elementary {
+    pub rule: u8,
+}
+
+impl Elementary {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix('W').unwrap_or(rule);
+        rule.parse()
+            .map(|rule| Self { rule })
+            .map_err(|_| RuleParseError::InvalidNumber(rule.to_owned()))
+    }
+}
+
+impl Default for Elementary {
+    /// Rule 30.
+    fn default() -> Self {
+        Self { rule: 30 }
+    }
+}
+
+impl fmt::Display for Elementary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule)
+    }
+}
+
+impl Automaton for Elementary {
+    type State = LifeStates;
+    type Dimension = D1;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let bottom = grid.height() as isize - 1;
+        if pos_y < bottom {
+            return grid[(pos_x, pos_y + 1)].clone();
+        }
+        let alive = |dx| matches!(grid.get(pos_x + dx, bottom), Some(LifeStates::Alife));
+        let idx = (alive(-1) as u8) << 2 | (alive(0) as u8) << 1 | alive(1) as u8;
+        if self.rule >> idx & 1 == 1 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, _generation: u64) -> Option<Grid<Self::State>> {
+        let mut next = grid.clone();
+        // Every row moves up, the old top row ends up at the bottom
+        next.grid.rotate_left(grid.width());
+        let bottom = grid.height() as isize - 1;
+        for x in 0..grid.width() as isize {
+            next[(x, bottom)] = self.update((x, bottom), grid);
+        }
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::tests::step;
+
+    fn bottom_row(grid: &Grid<LifeStates>) -> String {
+        let bottom = grid.height() as isize - 1;
+        (0..grid.width() as isize)
+            .map(|x| match grid[(x, bottom)] {
+                LifeStates::Alife => '#',
+                LifeStates::Dead => '.',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rule_90_from_single_cell() {
+        let mut grid = Grid::generate(7, 3);
+        grid[(3, 2)] = LifeStates::Alife;
+        let automaton = Elementary { rule: 90 };
+        let next = step(&automaton, &grid);
+        assert_eq!(bottom_row(&next), "..#.#..");
+        // The previous generation moved up
+        assert_eq!(next[(3, 1)], LifeStates::Alife);
+        assert_eq!(next[(3, 0)], LifeStates::Dead);
+        let next = step(&automaton, &next);
+        assert_eq!(bottom_row(&next), ".#...#.");
+        assert_eq!(next[(3, 0)], LifeStates::Alife);
+    }
+
+    #[test]
+    fn step_grid_shifts_rows() {
+        let mut grid = Grid::generate(11, 6);
+        grid[(5, 5)] = LifeStates::Alife;
+        grid[(2, 0)] = LifeStates::Alife;
+        let automaton = Elementary { rule: 110 };
+        for generation in 0..8 {
+            let next = automaton.step_grid(&grid, generation).unwrap();
+            assert_eq!(next, step(&automaton, &grid));
+            grid = next;
+        }
+    }
+
+    #[test]
+    fn parse_rule_number() {
+        assert_eq!(Elementary::parse("110"), Ok(Elementary { rule: 110 }));
+        assert_eq!(Elementary::parse(" W30 "), Ok(Elementary { rule: 30 }));
+        assert_eq!(
+            Elementary::parse("256"),
+            Err(RuleParseError::InvalidNumber("256".to_owned()))
+        );
+    }
+}
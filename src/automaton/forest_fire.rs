@@ -0,0 +1,233 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{random::CellNoise, theme::Theme};
+
+use super::{Automaton, Grid, Parameter, RuleParseError, D2};
+
+/// The Drossel–Schwabl forest-fire model.
+///
+/// Burning trees burn down and leave empty cells, trees next to a burning
+/// tree catch fire. Otherwise, trees grow on empty cells with probability
+/// `growth` and trees are struck by lightning with probability `lightning`
+/// in every generation. Neighbors are the von Neumann neighborhood.
+///
+/// Like [`RockPaperScissors`](super::RockPaperScissors), the chance events
+/// are drawn from a [`CellNoise`] in [`Automaton::step_grid`], so
+/// [`Automaton::update`] alone only spreads the fire. Rules are written as
+/// `P0.01/F0.0001`, growth and lightning probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForestFire {
+    pub growth: f64,
+    pub lightning: f64,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForestState {
+    #[default]
+    Empty,
+    Tree,
+    Burning,
+}
+
+/// Largest growth and lightning probabilities offered by the sliders.
+const MAX_GROWTH: f64 = 0.1;
+const MAX_LIGHTNING: f64 = 0.001;
+
+impl Default for ForestFire {
+    fn default() -> Self {
+        Self {
+            growth: 0.01,
+            lightning: 0.0001,
+            seed: 0,
+        }
+    }
+}
+
+impl ForestFire {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char| {
+            let value = sections
+                .next()
+                .and_then(|part| part.strip_prefix(section))
+                .ok_or(RuleParseError::MissingSection(section))?;
+            match value.parse() {
+                Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+                _ => Err(RuleParseError::InvalidNumber(value.to_owned())),
+            }
+        };
+        let growth = next('P')?;
+        let lightning = next('F')?;
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        Ok(Self {
+            growth,
+            lightning,
+            ..Self::default()
+        })
+    }
+
+    fn burning_neighbors(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<ForestState>) -> u8 {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .filter(|(dx, dy)| grid.get(pos_x + dx, pos_y + dy) == Some(&ForestState::Burning))
+            .count() as u8
+    }
+}
+
+impl fmt::Display for ForestFire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P{}/F{}", self.growth, self.lightning)
+    }
+}
+
+impl Automaton for ForestFire {
+    type State = ForestState;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        match grid[pos] {
+            ForestState::Burning => ForestState::Empty,
+            ForestState::Tree if self.burning_neighbors(pos, grid) > 0 => ForestState::Burning,
+            state => state,
+        }
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        Some(self.burning_neighbors(pos, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            ForestState::Empty => ForestState::Tree,
+            ForestState::Tree => ForestState::Burning,
+            ForestState::Burning => ForestState::Empty,
+        }
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            ForestState::Empty => theme.palette.dead.into(),
+            ForestState::Tree => theme.palette.alive.into(),
+            ForestState::Burning => theme.palette.red.into(),
+        }
+    }
+
+    /// Counts the trees.
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == ForestState::Tree
+    }
+
+    fn random_state(&self, _rng: &mut impl FnMut() -> u32) -> Self::State {
+        ForestState::Tree
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self {
+            seed: self.seed,
+            ..Self::parse(rule)?
+        };
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, generation: u64) -> Option<Grid<Self::State>> {
+        let noise = CellNoise::new(self.seed);
+        let mut next = grid.clone();
+        for x in 0..grid.width() as isize {
+            for y in 0..grid.height() as isize {
+                let chance = || noise.rng((x, y), generation).next_f64();
+                next[(x, y)] = match self.update((x, y), grid) {
+                    ForestState::Empty if chance() < self.growth => ForestState::Tree,
+                    ForestState::Tree if chance() < self.lightning => ForestState::Burning,
+                    state => state,
+                };
+            }
+        }
+        Some(next)
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter {
+                name: "Growth p",
+                value: self.growth,
+                min: 0.0,
+                max: MAX_GROWTH,
+                step: MAX_GROWTH / 1000.0,
+            },
+            Parameter {
+                name: "Lightning f",
+                value: self.lightning,
+                min: 0.0,
+                max: MAX_LIGHTNING,
+                step: MAX_LIGHTNING / 1000.0,
+            },
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        match idx {
+            0 => self.growth = value.clamp(0.0, 1.0),
+            1 => self.lightning = value.clamp(0.0, 1.0),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        let forest = ForestFire::parse("P0.01/F0.0001").unwrap();
+        assert_eq!(forest, ForestFire::default());
+        assert_eq!(forest.to_string(), "P0.01/F0.0001");
+        assert_eq!(
+            ForestFire::parse("P0.01"),
+            Err(RuleParseError::MissingSection('F'))
+        );
+        assert_eq!(
+            ForestFire::parse("P2/F0"),
+            Err(RuleParseError::InvalidNumber("2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn fire_spreads_and_burns_down() {
+        let still = ForestFire::parse("P0/F0").unwrap();
+        let mut grid = Grid::generate(5, 5);
+        for x in 0..5 {
+            grid[(x, 2)] = ForestState::Tree;
+        }
+        grid[(0, 2)] = ForestState::Burning;
+        grid[(2, 0)] = ForestState::Tree;
+        let next = still.step_grid(&grid, 0).unwrap();
+        assert_eq!(next[(0, 2)], ForestState::Empty);
+        assert_eq!(next[(1, 2)], ForestState::Burning);
+        // Wrapping around, the fire spreads to the left as well
+        assert_eq!(next[(4, 2)], ForestState::Burning);
+        assert_eq!(next[(2, 2)], ForestState::Tree);
+        assert_eq!(next[(2, 0)], ForestState::Tree);
+    }
+
+    #[test]
+    fn parameters_are_probabilities() {
+        let mut forest = ForestFire::parse("P1/F0").unwrap();
+        let grown = forest.step_grid(&Grid::generate(4, 4), 0).unwrap();
+        assert!(grown.grid.iter().all(|s| *s == ForestState::Tree));
+        forest.set_parameter(0, 0.0);
+        forest.set_parameter(1, 1.0);
+        assert_eq!(forest.parameters()[1].value, 1.0);
+        let struck = forest.step_grid(&grown, 1).unwrap();
+        assert!(struck.grid.iter().all(|s| *s == ForestState::Burning));
+        assert_eq!(forest.to_string(), "P0/F1");
+    }
+}
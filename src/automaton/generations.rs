@@ -0,0 +1,248 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{
+    count_moore_neighbors, parse_section, write_counts, Automaton, Grid, RuleParseError, D2,
+};
+
+/// A Generations rule, Life with additional dying states.
+///
+/// Alive cells survive with a neighbor count in `survive`, otherwise they
+/// pass through `states - 2` dying stages before they are dead. Dead cells
+/// are born with a neighbor count in `born`. Only alive cells count as
+/// neighbors, so Brian's Brain is `B2/S/C3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generations {
+    pub born: u16,
+    pub survive: u16,
+    /// Number of states including dead and alive, at least two.
+    pub states: u8,
+}
+
+/// State of a cell in a [`Generations`] automaton.
+///
+/// `0` is dead, `1` is alive and larger values are the dying stages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationsState(pub u8);
+
+impl GenerationsState {
+    pub const DEAD: Self = Self(0);
+    pub const ALIVE: Self = Self(1);
+}
+
+impl Generations {
+    /// Parse a rule in `B2/S345/C4` or `345/2/4` (survive/born/states) notation.
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let rule = rule.trim();
+        if rule.starts_with('B') {
+            let (born, rest) = parse_section(rule, 'B')?;
+            let rest = rest
+                .strip_prefix('/')
+                .ok_or(RuleParseError::MissingSection('S'))?;
+            let (survive, rest) = parse_section(rest, 'S')?;
+            let states = rest
+                .strip_prefix("/C")
+                .ok_or(RuleParseError::MissingSection('C'))?;
+            Self::new(born, survive, states)
+        } else {
+            let mut sections = rule.split('/');
+            let mut next = |section| {
+                sections
+                    .next()
+                    .ok_or(RuleParseError::MissingSection(section))
+            };
+            let survive = parse_counts(next('S')?)?;
+            let born = parse_counts(next('B')?)?;
+            let states = next('C')?;
+            if let Some(rest) = sections.next() {
+                return Err(RuleParseError::Trailing(format!("/{}", rest)));
+            }
+            Self::new(born, survive, states)
+        }
+    }
+
+    fn new(born: u16, survive: u16, states: &str) -> Result<Self, RuleParseError> {
+        match states.parse() {
+            Ok(states) if states >= 2 => Ok(Self {
+                born,
+                survive,
+                states,
+            }),
+            _ => Err(RuleParseError::InvalidNumber(states.to_owned())),
+        }
+    }
+}
+
+/// Parse a bit mask of neighbor counts, like `345`.
+fn parse_counts(counts: &str) -> Result<u16, RuleParseError> {
+    counts
+        .chars()
+        .try_fold(0, |mask, count| match count.to_digit(10) {
+            Some(n) if n <= 8 => Ok(mask | 1 << n),
+            _ => Err(RuleParseError::InvalidCount(count)),
+        })
+}
+
+impl Default for Generations {
+    /// Star Wars, `B2/S345/C4`.
+    fn default() -> Self {
+        Self {
+            born: 1 << 2,
+            survive: 1 << 3 | 1 << 4 | 1 << 5,
+            states: 4,
+        }
+    }
+}
+
+impl fmt::Display for Generations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        write_counts(f, self.born)?;
+        write!(f, "/S")?;
+        write_counts(f, self.survive)?;
+        write!(f, "/C{}", self.states)
+    }
+}
+
+/// Count the alive Moore neighbors, dying cells don't count.
+fn live_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<GenerationsState>) -> u8 {
+    count_moore_neighbors(pos_x, pos_y, grid, |s| *s == GenerationsState::ALIVE)
+}
+
+impl Automaton for Generations {
+    type State = GenerationsState;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let alive = || live_neighbors(pos_x, pos_y, grid);
+        match grid[(pos_x, pos_y)] {
+            GenerationsState::DEAD if self.born >> alive() & 1 == 1 => GenerationsState::ALIVE,
+            GenerationsState::DEAD => GenerationsState::DEAD,
+            GenerationsState::ALIVE if self.survive >> alive() & 1 == 1 => GenerationsState::ALIVE,
+            // Start or continue dying, dead after the last stage
+            GenerationsState(stage) if stage + 1 < self.states => GenerationsState(stage + 1),
+            GenerationsState(_) => GenerationsState::DEAD,
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(live_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            GenerationsState::DEAD => GenerationsState::ALIVE,
+            _ => GenerationsState::DEAD,
+        }
+    }
+
+    /// Alive cells are light, dying stages fade from orange to blue.
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match *curr {
+            GenerationsState::DEAD => theme.palette.dead.into(),
+            GenerationsState::ALIVE => theme.palette.alive.into(),
+            GenerationsState(stage) => {
+                // Dying stages are 2..states, spread them over the gradient
+                let t = (stage - 2) as f64 / (self.states.saturating_sub(3).max(1)) as f64;
+                format!("hsl({:.0}, 60%, {:.0}%)", 30.0 + 170.0 * t, 55.0 - 20.0 * t).into()
+            }
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == GenerationsState::ALIVE
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{
+        brians_brain::{BrainState, BriansBrain},
+        tests::step,
+    };
+
+    #[test]
+    fn parse_rules() {
+        let star_wars = Generations::parse("345/2/4").unwrap();
+        assert_eq!(star_wars, Generations::default());
+        assert_eq!(Generations::parse("B2/S345/C4"), Ok(star_wars));
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+        assert_eq!(
+            Generations::parse("/2/3"),
+            Ok(Generations {
+                born: 1 << 2,
+                survive: 0,
+                states: 3
+            })
+        );
+        assert_eq!(
+            Generations::parse("345/2"),
+            Err(RuleParseError::MissingSection('C'))
+        );
+        assert_eq!(
+            Generations::parse("B2/S345/C1"),
+            Err(RuleParseError::InvalidNumber("1".to_owned()))
+        );
+        assert_eq!(
+            Generations::parse("3x/2/4"),
+            Err(RuleParseError::InvalidCount('x'))
+        );
+    }
+
+    #[test]
+    fn dying_stages() {
+        // A lonely cell does not survive in Star Wars and takes two stages to die
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = GenerationsState::ALIVE;
+        let automaton = Generations::default();
+        let next = step(&automaton, &grid);
+        assert_eq!(next[(2, 2)], GenerationsState(2));
+        let next = step(&automaton, &next);
+        assert_eq!(next[(2, 2)], GenerationsState(3));
+        let next = step(&automaton, &next);
+        assert_eq!(next[(2, 2)], GenerationsState::DEAD);
+        // Dying cells don't count as neighbors, so nothing is born
+        assert!(next.grid.iter().all(|s| *s == GenerationsState::DEAD));
+    }
+
+    #[test]
+    fn matches_brians_brain() {
+        let automaton = Generations::parse("B2/S/C3").unwrap();
+        let mut brain = Grid::generate(8, 8);
+        let mut generations = Grid::generate(8, 8);
+        for pos in [(1, 1), (1, 2), (5, 5), (6, 5), (4, 4)] {
+            brain[pos] = BrainState::On;
+            generations[pos] = GenerationsState::ALIVE;
+        }
+        for _ in 0..6 {
+            brain = step(&BriansBrain, &brain);
+            generations = step(&automaton, &generations);
+            for x in 0..8 {
+                for y in 0..8 {
+                    let expected = match brain[(x, y)] {
+                        BrainState::Off => GenerationsState::DEAD,
+                        BrainState::On => GenerationsState::ALIVE,
+                        BrainState::Dying => GenerationsState(2),
+                    };
+                    assert_eq!(generations[(x, y)], expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{Automaton, Grid, Parameter, RuleParseError, D2};
+
+/// Longest refractory period offered by the slider.
+const MAX_REFRACTORY: u8 = 20;
+
+/// The Greenberg–Hastings model of an excitable medium.
+///
+/// A resting cell is excited if at least `threshold` of its von Neumann
+/// neighbors are excited. Excited cells become refractory for `refractory`
+/// generations and rest again afterwards, so that waves cannot travel
+/// backwards. Broken wave fronts curl up into spirals. Rules are written as
+/// `R2/T1`, refractory length and threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GreenbergHastings {
+    pub refractory: u8,
+    pub threshold: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Excitation {
+    #[default]
+    Resting,
+    Excited,
+    /// Number of generations left until the cell rests.
+    Refractory(u8),
+}
+
+impl Default for GreenbergHastings {
+    fn default() -> Self {
+        Self {
+            refractory: 2,
+            threshold: 1,
+        }
+    }
+}
+
+impl GreenbergHastings {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char, range: std::ops::RangeInclusive<u8>| {
+            let value = sections
+                .next()
+                .and_then(|part| part.strip_prefix(section))
+                .ok_or(RuleParseError::MissingSection(section))?;
+            match value.parse() {
+                Ok(count) if range.contains(&count) => Ok(count),
+                _ => Err(RuleParseError::InvalidNumber(value.to_owned())),
+            }
+        };
+        let refractory = next('R', 0..=MAX_REFRACTORY)?;
+        let threshold = next('T', 1..=4)?;
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        Ok(Self {
+            refractory,
+            threshold,
+        })
+    }
+
+    /// The state following an excitation.
+    fn after_excited(&self) -> Excitation {
+        match self.refractory {
+            0 => Excitation::Resting,
+            len => Excitation::Refractory(len),
+        }
+    }
+
+    fn excited_neighbors(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Excitation>) -> u8 {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .filter(|(dx, dy)| grid.get(pos_x + dx, pos_y + dy) == Some(&Excitation::Excited))
+            .count() as u8
+    }
+}
+
+impl fmt::Display for GreenbergHastings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "R{}/T{}", self.refractory, self.threshold)
+    }
+}
+
+impl Automaton for GreenbergHastings {
+    type State = Excitation;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        match grid[pos] {
+            Excitation::Resting if self.excited_neighbors(pos, grid) >= self.threshold => {
+                Excitation::Excited
+            }
+            Excitation::Resting => Excitation::Resting,
+            Excitation::Excited => self.after_excited(),
+            Excitation::Refractory(left) if left > 1 => Excitation::Refractory(left - 1),
+            Excitation::Refractory(_) => Excitation::Resting,
+        }
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        Some(self.excited_neighbors(pos, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            Excitation::Resting => Excitation::Excited,
+            Excitation::Excited => self.after_excited(),
+            Excitation::Refractory(_) => Excitation::Resting,
+        }
+    }
+
+    /// Refractory cells fade from blue towards the resting color.
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match *curr {
+            Excitation::Resting => theme.palette.dead.into(),
+            Excitation::Excited => theme.palette.yellow.into(),
+            Excitation::Refractory(left) => {
+                let t = left.min(self.refractory.max(1)) as f64 / self.refractory.max(1) as f64;
+                format!("hsl(220, 60%, {:.0}%)", 15.0 + 35.0 * t).into()
+            }
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr != Excitation::Resting
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % (self.refractory as u32 + 1) {
+            0 => Excitation::Excited,
+            left => Excitation::Refractory(left as u8),
+        }
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter {
+                name: "Refractory length",
+                value: self.refractory as f64,
+                min: 0.0,
+                max: MAX_REFRACTORY as f64,
+                step: 1.0,
+            },
+            Parameter {
+                name: "Threshold",
+                value: self.threshold as f64,
+                min: 1.0,
+                max: 4.0,
+                step: 1.0,
+            },
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        match idx {
+            0 => self.refractory = value.round().clamp(0.0, MAX_REFRACTORY as f64) as u8,
+            1 => self.threshold = value.round().clamp(1.0, 4.0) as u8,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::tests::step;
+
+    #[test]
+    fn parse_rules() {
+        let rule = GreenbergHastings::parse("R2/T1").unwrap();
+        assert_eq!(rule, GreenbergHastings::default());
+        assert_eq!(rule.to_string(), "R2/T1");
+        assert_eq!(
+            GreenbergHastings::parse("R2"),
+            Err(RuleParseError::MissingSection('T'))
+        );
+        assert_eq!(
+            GreenbergHastings::parse("R2/T5"),
+            Err(RuleParseError::InvalidNumber("5".to_owned()))
+        );
+    }
+
+    #[test]
+    fn waves_travel_away_from_the_refractory_tail() {
+        let rule = GreenbergHastings::default();
+        let mut grid = Grid::generate(9, 1);
+        grid[(4, 0)] = Excitation::Excited;
+        grid[(3, 0)] = Excitation::Refractory(2);
+        let next = step(&rule, &grid);
+        assert_eq!(next[(5, 0)], Excitation::Excited);
+        assert_eq!(next[(4, 0)], Excitation::Refractory(2));
+        assert_eq!(next[(3, 0)], Excitation::Refractory(1));
+        let next = step(&rule, &next);
+        assert_eq!(next[(6, 0)], Excitation::Excited);
+        assert_eq!(next[(3, 0)], Excitation::Resting);
+        assert_eq!(next[(4, 0)], Excitation::Refractory(1));
+    }
+
+    #[test]
+    fn refractory_length_is_adjustable() {
+        let mut rule = GreenbergHastings::default();
+        assert_eq!(rule.states().len(), 3);
+        rule.set_parameter(0, 5.0);
+        assert_eq!(rule.to_string(), "R5/T1");
+        assert_eq!(rule.toggle(Excitation::Excited), Excitation::Refractory(5));
+        rule.set_parameter(0, 0.0);
+        assert_eq!(rule.toggle(Excitation::Excited), Excitation::Resting);
+        assert_eq!(rule.states().len(), 2);
+    }
+}
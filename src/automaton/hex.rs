@@ -0,0 +1,236 @@
+//! Hexagonal grids.
+//!
+//! Cells use axial coordinates: the grid position `(x, y)` is the axial
+//! coordinate `(q, r)`, so a rectangular [`Grid`] is a rhombus of pointy-top
+//! hexagons, where every row is shifted by half a cell to the right
+//! relative to the row above. Wrapping edges turn the rhombus into a torus.
+
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{
+    style_life, toggle_life, Automaton, Dimension, Grid, LifeLike, LifeStates, RuleParseError,
+};
+
+/// Marker for automata on a hexagonal grid.
+pub enum Hex {}
+
+impl Dimension for Hex {
+    const HEXAGONAL: bool = true;
+}
+
+/// The six axial offsets of the neighbors of a hexagonal cell.
+#[derive(Debug, Clone, Default)]
+pub struct HexNeighbors {
+    idx: usize,
+}
+
+impl HexNeighbors {
+    const OFFSETS: [(isize, isize); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Iterator for HexNeighbors {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = Self::OFFSETS.get(self.idx)?;
+        self.idx += 1;
+        Some(*offset)
+    }
+}
+
+/// Center of the hexagon at axial `(q, r)`, for hexagons `width` wide.
+///
+/// The top-left corner of the bounding box of hexagon `(0, 0)` is the origin.
+pub fn axial_to_pixel((q, r): (isize, isize), width: f64) -> (f64, f64) {
+    let (q, r) = (q as f64, r as f64);
+    let x = width * (q + r / 2.0 + 0.5);
+    let y = width * (3f64.sqrt() / 2.0 * r + 1.0 / 3f64.sqrt());
+    (x, y)
+}
+
+/// The axial coordinate of the hexagon containing `(x, y)`, see [`axial_to_pixel`].
+pub fn pixel_to_axial((x, y): (f64, f64), width: f64) -> (isize, isize) {
+    let r = (y / width - 1.0 / 3f64.sqrt()) * 2.0 / 3f64.sqrt();
+    let q = x / width - 0.5 - r / 2.0;
+    // Round in cube coordinates, fixing the component with the largest error
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as isize, rr as isize)
+}
+
+/// Corners of the hexagon at axial `(q, r)`, starting at the top, clockwise.
+pub fn hexagon_corners(pos: (isize, isize), width: f64) -> [(f64, f64); 6] {
+    let (cx, cy) = axial_to_pixel(pos, width);
+    let radius = width / 3f64.sqrt();
+    [0, 1, 2, 3, 4, 5].map(|idx| {
+        let angle = std::f64::consts::PI / 3.0 * idx as f64 - std::f64::consts::FRAC_PI_2;
+        (cx + radius * angle.cos(), cy + radius * angle.sin())
+    })
+}
+
+/// A Life-like automaton on a hexagonal grid, counting the six neighbors.
+///
+/// The default rule B2/S34 supports gliders and oscillators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexLife {
+    pub rule: LifeLike,
+}
+
+impl Default for HexLife {
+    fn default() -> Self {
+        Self {
+            rule: LifeLike {
+                born: 1 << 2,
+                survive: 1 << 3 | 1 << 4,
+            },
+        }
+    }
+}
+
+/// Count the alive neighbors of the hexagon at `(pos_x, pos_y)`.
+fn live_hex_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<LifeStates>) -> u8 {
+    HexNeighbors::new()
+        .filter_map(|(x, y)| grid.get(pos_x + x, pos_y + y))
+        .filter(|state| **state == LifeStates::Alife)
+        .count() as u8
+}
+
+impl Automaton for HexLife {
+    type State = LifeStates;
+    type Dimension = Hex;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let live = live_hex_neighbors(pos_x, pos_y, grid);
+        let mask = match grid[(pos_x, pos_y)] {
+            LifeStates::Alife => self.rule.survive,
+            LifeStates::Dead => self.rule.born,
+        };
+        if mask >> live & 1 == 1 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(live_hex_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.rule.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        self.rule = LifeLike::parse(rule)?;
+        Ok(())
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        // The hexagonal neighbors are a subset of the Moore neighbors
+        self.rule.sparse_radius()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::automaton::tests::step;
+
+    #[test]
+    fn six_neighbors() {
+        let neighbors: HashSet<_> = HexNeighbors::new().collect();
+        assert_eq!(neighbors.len(), 6);
+        assert!(!neighbors.contains(&(0, 0)));
+        // Neighbors come in opposite pairs
+        assert!(neighbors.iter().all(|(q, r)| neighbors.contains(&(-q, -r))));
+        // All neighbors have the same distance from the center
+        let center = axial_to_pixel((0, 0), 10.0);
+        for pos in neighbors {
+            let (x, y) = axial_to_pixel(pos, 10.0);
+            let dist = ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
+            assert!((dist - 10.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn axial_pixel_conversion() {
+        let close = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| {
+            (ax - bx).abs() < 1e-9 && (ay - by).abs() < 1e-9
+        };
+        let h = 3f64.sqrt() / 2.0;
+        assert!(close(axial_to_pixel((0, 0), 2.0), (1.0, 2.0 / 3f64.sqrt())));
+        assert!(close(axial_to_pixel((1, 0), 2.0), (3.0, 2.0 / 3f64.sqrt())));
+        // The next row is shifted by half a hexagon
+        assert!(close(
+            axial_to_pixel((0, 1), 2.0),
+            (2.0, 2.0 / 3f64.sqrt() + 2.0 * h)
+        ));
+        for q in -3..3 {
+            for r in -3..3 {
+                let (x, y) = axial_to_pixel((q, r), 7.0);
+                assert_eq!(pixel_to_axial((x, y), 7.0), (q, r));
+                // Points near the corners still belong to the hexagon
+                for (cx, cy) in hexagon_corners((q, r), 7.0) {
+                    let near = (x + 0.9 * (cx - x), y + 0.9 * (cy - y));
+                    assert_eq!(pixel_to_axial(near, 7.0), (q, r));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_life_b2() {
+        let mut grid = Grid::generate(8, 8);
+        grid[(3, 3)] = LifeStates::Alife;
+        grid[(4, 3)] = LifeStates::Alife;
+        let next = step(&HexLife::default(), &grid);
+        // Only the two cells adjacent to both are born, the pair dies
+        let alive: HashSet<_> = (0..8)
+            .flat_map(|x| (0..8).map(move |y| (x, y)))
+            .filter(|&pos| next[pos] == LifeStates::Alife)
+            .collect();
+        assert_eq!(alive, HashSet::from([(4, 2), (3, 4)]));
+    }
+
+    #[test]
+    fn hex_neighbor_count() {
+        let mut grid = Grid::generate(8, 8);
+        grid[(3, 3)] = LifeStates::Alife;
+        grid[(4, 3)] = LifeStates::Alife;
+        let count = |pos| HexLife::default().neighbor_count(pos, &grid);
+        assert_eq!(count((4, 2)), Some(2));
+        assert_eq!(count((3, 3)), Some(1));
+        // Diagonal in the grid, but not adjacent as hexagons
+        assert_eq!(count((2, 2)), Some(0));
+    }
+}
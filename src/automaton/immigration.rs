@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{count_moore_neighbors, Automaton, Grid, D2};
+
+/// The Immigration game, Conway's Life with two colors of live cells.
+///
+/// Survival and birth follow B3/S23 on the total number of live neighbors.
+/// Surviving cells keep their color, newborn cells take the majority color
+/// of their three live neighbors.
+#[derive(Debug, Clone, Default)]
+pub struct Immigration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImmigrationState {
+    #[default]
+    Dead,
+    Red,
+    Blue,
+}
+
+/// Count the red and the blue Moore neighbors.
+fn live_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<ImmigrationState>) -> (u8, u8) {
+    let red = count_moore_neighbors(pos_x, pos_y, grid, |s| *s == ImmigrationState::Red);
+    let blue = count_moore_neighbors(pos_x, pos_y, grid, |s| *s == ImmigrationState::Blue);
+    (red, blue)
+}
+
+impl Automaton for Immigration {
+    type State = ImmigrationState;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let (red, blue) = live_neighbors(pos_x, pos_y, grid);
+        match (red + blue, grid[(pos_x, pos_y)]) {
+            (2..=3, curr @ (ImmigrationState::Red | ImmigrationState::Blue)) => curr,
+            (3, ImmigrationState::Dead) if red > blue => ImmigrationState::Red,
+            (3, ImmigrationState::Dead) => ImmigrationState::Blue,
+            _ => ImmigrationState::Dead,
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        let (red, blue) = live_neighbors(pos_x, pos_y, grid);
+        Some((red + blue) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            ImmigrationState::Dead => ImmigrationState::Red,
+            ImmigrationState::Red => ImmigrationState::Blue,
+            ImmigrationState::Blue => ImmigrationState::Dead,
+        }
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            ImmigrationState::Dead => theme.palette.dead.into(),
+            ImmigrationState::Red => theme.palette.red.into(),
+            ImmigrationState::Blue => theme.palette.blue.into(),
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr != ImmigrationState::Dead
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % 2 {
+            0 => ImmigrationState::Red,
+            _ => ImmigrationState::Blue,
+        }
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{tests::step, EdgeBehavior};
+    use ImmigrationState::{Blue, Dead, Red};
+
+    /// The state of the center of a 3×3 grid born from the given parents.
+    fn birth(parents: [ImmigrationState; 3]) -> ImmigrationState {
+        let mut grid = Grid::with_edge_behavior(3, 3, EdgeBehavior::Dead);
+        for (pos, state) in [(0, 0), (2, 0), (1, 2)].into_iter().zip(parents) {
+            grid[pos] = state;
+        }
+        step(&Immigration, &grid)[(1, 1)]
+    }
+
+    #[test]
+    fn birth_takes_majority_color() {
+        assert_eq!(birth([Red, Red, Red]), Red);
+        assert_eq!(birth([Red, Blue, Red]), Red);
+        assert_eq!(birth([Blue, Red, Blue]), Blue);
+        assert_eq!(birth([Blue, Blue, Blue]), Blue);
+        assert_eq!(birth([Red, Blue, Dead]), Dead);
+    }
+
+    #[test]
+    fn survivors_keep_their_color() {
+        let mut grid = Grid::generate(5, 5);
+        // A blinker with a blue center
+        grid[(1, 2)] = Red;
+        grid[(2, 2)] = Blue;
+        grid[(3, 2)] = Red;
+        let next = step(&Immigration, &grid);
+        assert_eq!(next[(2, 2)], Blue);
+        assert_eq!(next[(2, 1)], Red);
+        assert_eq!(next[(2, 3)], Red);
+        assert_eq!(next[(1, 2)], Dead);
+    }
+}
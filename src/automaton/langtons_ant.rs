@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{Automaton, Grid, D2};
+
+/// Langton's Ant, a mobile agent walking the grid.
+///
+/// On a white cell the ant turns right, on a black cell it turns left.
+/// It then flips the color of its cell and moves forward by one cell.
+///
+/// The ant is stored as part of the cell it stands on, so a cell's next
+/// state only depends on its von Neumann neighbors: the cell flips if the
+/// ant leaves it, and it receives the ant of a neighbor that turns towards it.
+/// This keeps the ant in sync with the history when undoing steps.
+#[derive(Debug, Clone, Default)]
+pub struct LangtonsAnt;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AntCell {
+    pub black: bool,
+    pub ant: Option<Heading>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub(super) const ALL: [Heading; 4] =
+        [Heading::North, Heading::East, Heading::South, Heading::West];
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Heading::North => Heading::East,
+            Heading::East => Heading::South,
+            Heading::South => Heading::West,
+            Heading::West => Heading::North,
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.turn_right().turn_right().turn_right()
+    }
+
+    /// Grid offset of one step in this direction, with y pointing down.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Heading::North => (0, -1),
+            Heading::East => (1, 0),
+            Heading::South => (0, 1),
+            Heading::West => (-1, 0),
+        }
+    }
+}
+
+impl AntCell {
+    /// Direction the ant on this cell will move in, if there is one.
+    fn next_heading(&self) -> Option<Heading> {
+        self.ant.map(|heading| {
+            if self.black {
+                heading.turn_left()
+            } else {
+                heading.turn_right()
+            }
+        })
+    }
+}
+
+impl Automaton for LangtonsAnt {
+    type State = AntCell;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let curr = &grid[(pos_x, pos_y)];
+        let ant = Heading::ALL.iter().find_map(|&towards| {
+            let (dx, dy) = towards.offset();
+            let heading = grid.get(pos_x + dx, pos_y + dy)?.next_heading()?;
+            (heading.offset() == (-dx, -dy)).then_some(heading)
+        });
+        AntCell {
+            black: curr.black ^ curr.ant.is_some(),
+            ant,
+        }
+    }
+
+    /// Cycle through white, black, an ant on white and an ant on black.
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        let (black, ant) = match (curr.black, curr.ant) {
+            (false, None) => (true, None),
+            (true, None) => (false, Some(Heading::North)),
+            (false, Some(_)) => (true, Some(Heading::North)),
+            (true, Some(_)) => (false, None),
+        };
+        AntCell { black, ant }
+    }
+
+    fn toggle_agent(&self, curr: &Self::State, _id: u8) -> Option<Self::State> {
+        let ant = match curr.ant {
+            Some(_) => None,
+            None => Some(Heading::North),
+        };
+        Some(AntCell { ant, ..*curr })
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        curr.black
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            AntCell { ant: Some(_), .. } => theme.palette.red.into(),
+            AntCell { black: true, .. } => theme.palette.alive.into(),
+            AntCell { black: false, .. } => theme.palette.dead.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::tests::step;
+
+    fn find_ant(grid: &Grid<AntCell>) -> Option<((isize, isize), Heading)> {
+        (0..grid.width() as isize)
+            .flat_map(|x| (0..grid.height() as isize).map(move |y| (x, y)))
+            .find_map(|pos| grid[pos].ant.map(|heading| (pos, heading)))
+    }
+
+    #[test]
+    fn early_trajectory() {
+        let mut grid: Grid<AntCell> = Grid::generate(8, 8);
+        grid[(5, 5)].ant = Some(Heading::North);
+        let expected = [
+            // Four right turns on white cells walk a square
+            ((6, 5), Heading::East),
+            ((6, 6), Heading::South),
+            ((5, 6), Heading::West),
+            ((5, 5), Heading::North),
+            // Back on the now black start cell the ant turns left
+            ((4, 5), Heading::West),
+            ((4, 4), Heading::North),
+        ];
+        for (pos, heading) in expected {
+            grid = step(&LangtonsAnt, &grid);
+            assert_eq!(find_ant(&grid), Some((pos, heading)));
+        }
+        // The start cell was flipped twice, the others once
+        assert!(!grid[(5, 5)].black);
+        assert!(grid[(6, 5)].black && grid[(6, 6)].black && grid[(5, 6)].black);
+        assert!(grid[(4, 5)].black);
+    }
+
+    #[test]
+    fn ant_wraps_around() {
+        let mut grid: Grid<AntCell> = Grid::generate(4, 4);
+        grid[(3, 0)].ant = Some(Heading::North);
+        let grid = step(&LangtonsAnt, &grid);
+        assert_eq!(find_ant(&grid), Some(((0, 0), Heading::East)));
+    }
+}
@@ -0,0 +1,236 @@
+use std::{borrow::Cow, fmt, ops::RangeInclusive};
+
+use crate::theme::Theme;
+
+use super::{style_life, toggle_life, Automaton, Grid, LifeStates, RuleParseError, D2};
+
+/// Largest supported neighborhood radius.
+pub const MAX_RADIUS: usize = 10;
+
+/// Larger than Life, counting alive cells in a Moore neighborhood of any radius.
+///
+/// A dead cell is born if the count is within `born`, an alive cell
+/// survives if it is within `survive`. The center cell is counted as well
+/// if `include_center` is set. Rules use the `R5,C0,M1,S34..58,B34..45,NM`
+/// notation, where `C` has to be zero or two, as only two states are supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargerThanLife {
+    pub radius: usize,
+    pub include_center: bool,
+    pub born: RangeInclusive<u32>,
+    pub survive: RangeInclusive<u32>,
+}
+
+impl Default for LargerThanLife {
+    /// Bosco's Rule, which has gliders and oscillators.
+    fn default() -> Self {
+        Self {
+            radius: 5,
+            include_center: true,
+            born: 34..=45,
+            survive: 34..=58,
+        }
+    }
+}
+
+impl LargerThanLife {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let (mut radius, mut include_center, mut born, mut survive) = (None, false, None, None);
+        for part in rule.trim().split(',') {
+            let (key, value) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+            match key {
+                "R" => match value.parse() {
+                    Ok(r @ 1..=MAX_RADIUS) => radius = Some(r),
+                    _ => return Err(RuleParseError::InvalidNumber(value.to_owned())),
+                },
+                "C" if matches!(value, "0" | "2") => {}
+                "M" if matches!(value, "0" | "1") => include_center = value == "1",
+                "S" => survive = Some(parse_range(value)?),
+                "B" => born = Some(parse_range(value)?),
+                "N" if value == "M" => {}
+                "C" | "M" => return Err(RuleParseError::InvalidNumber(value.to_owned())),
+                "N" => return Err(RuleParseError::UnknownRule(part.to_owned())),
+                _ => return Err(RuleParseError::Trailing(part.to_owned())),
+            }
+        }
+        Ok(Self {
+            radius: radius.ok_or(RuleParseError::MissingSection('R'))?,
+            include_center,
+            born: born.ok_or(RuleParseError::MissingSection('B'))?,
+            survive: survive.ok_or(RuleParseError::MissingSection('S'))?,
+        })
+    }
+
+    /// Number of alive cells within the radius of `(pos_x, pos_y)`.
+    ///
+    /// The offsets are computed from the radius on every call, so that it
+    /// can change at runtime.
+    fn count(&self, pos_x: isize, pos_y: isize, grid: &Grid<LifeStates>) -> u32 {
+        let radius = self.radius as isize;
+        let mut count = 0;
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                if (x, y) == (0, 0) && !self.include_center {
+                    continue;
+                }
+                if grid.get(pos_x + x, pos_y + y) == Some(&LifeStates::Alife) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+/// Parse an inclusive range like `34..58`.
+fn parse_range(range: &str) -> Result<RangeInclusive<u32>, RuleParseError> {
+    let invalid = || RuleParseError::InvalidNumber(range.to_owned());
+    let (low, high) = range.split_once("..").ok_or_else(invalid)?;
+    let low = low.parse().map_err(|_| invalid())?;
+    let high = high.parse().map_err(|_| invalid())?;
+    Ok(low..=high)
+}
+
+impl fmt::Display for LargerThanLife {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{},C0,M{},S{}..{},B{}..{},NM",
+            self.radius,
+            self.include_center as u8,
+            self.survive.start(),
+            self.survive.end(),
+            self.born.start(),
+            self.born.end()
+        )
+    }
+}
+
+impl Automaton for LargerThanLife {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let count = self.count(pos_x, pos_y, grid);
+        let range = match grid[(pos_x, pos_y)] {
+            LifeStates::Dead => &self.born,
+            LifeStates::Alife => &self.survive,
+        };
+        if range.contains(&count) {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(self.count(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        // Births without alive cells around happen anywhere
+        (!self.born.contains(&0)).then_some(self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{tests::step, EdgeBehavior};
+
+    #[test]
+    fn parse_rules() {
+        let bosco = LargerThanLife::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+        assert_eq!(bosco, LargerThanLife::default());
+        assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        let life = LargerThanLife::parse("R1,M0,S2..3,B3..3").unwrap();
+        assert_eq!(
+            life,
+            LargerThanLife {
+                radius: 1,
+                include_center: false,
+                born: 3..=3,
+                survive: 2..=3,
+            }
+        );
+        assert_eq!(
+            LargerThanLife::parse("R2,S2..3"),
+            Err(RuleParseError::MissingSection('B'))
+        );
+        assert_eq!(
+            LargerThanLife::parse("R0,S2..3,B3..3"),
+            Err(RuleParseError::InvalidNumber("0".to_owned()))
+        );
+        assert_eq!(
+            LargerThanLife::parse("R2,C3,S2..3,B3..3"),
+            Err(RuleParseError::InvalidNumber("3".to_owned()))
+        );
+        assert_eq!(
+            LargerThanLife::parse("R2,S2-3,B3..3"),
+            Err(RuleParseError::InvalidNumber("2-3".to_owned()))
+        );
+    }
+
+    #[test]
+    fn count_at_radius_two() {
+        let mut grid = Grid::with_edge_behavior(9, 9, EdgeBehavior::Dead);
+        for pos in [(4, 4), (2, 2), (6, 4), (4, 7), (1, 4)] {
+            grid[pos] = LifeStates::Alife;
+        }
+        let mut automaton = LargerThanLife::parse("R2,M0,S0..0,B0..0").unwrap();
+        // The corner and the cell two to the right are in range, the others too far
+        assert_eq!(automaton.neighbor_count((4, 4), &grid), Some(2));
+        automaton.include_center = true;
+        assert_eq!(automaton.neighbor_count((4, 4), &grid), Some(3));
+        // Changing the radius at runtime takes effect immediately
+        automaton.radius = 3;
+        assert_eq!(automaton.neighbor_count((4, 4), &grid), Some(5));
+    }
+
+    #[test]
+    fn thresholds_decide_birth_and_survival() {
+        let automaton = LargerThanLife::parse("R2,M0,S3..4,B2..2").unwrap();
+        let mut grid = Grid::with_edge_behavior(9, 9, EdgeBehavior::Dead);
+        // A vertical line of four cells
+        for y in 2..6 {
+            grid[(4, y)] = LifeStates::Alife;
+        }
+        let next = step(&automaton, &grid);
+        // The ends see two others, too few to survive, the middle cells see three
+        assert_eq!(next[(4, 2)], LifeStates::Dead);
+        assert_eq!(next[(4, 3)], LifeStates::Alife);
+        assert_eq!(next[(4, 4)], LifeStates::Alife);
+        assert_eq!(next[(4, 5)], LifeStates::Dead);
+        // Cells seeing exactly two of the line are born
+        assert_eq!(next[(4, 1)], LifeStates::Alife);
+        assert_eq!(next[(2, 1)], LifeStates::Alife);
+        // Cells seeing one or all four are not
+        assert_eq!(next[(3, 4)], LifeStates::Dead);
+        assert_eq!(next[(4, 0)], LifeStates::Dead);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{style_mass, toggle_mass, Automaton, Grid, Mass, Parameter, RuleParseError, D2};
+
+/// Largest kernel radius offered by the slider.
+const MAX_RADIUS: usize = 20;
+/// Largest number of steps per unit of time offered by the slider.
+const MAX_TIME: usize = 50;
+
+/// Lenia, a continuous generalization of Life.
+///
+/// Cells hold a [`Mass`] between zero and one. The potential of a cell is
+/// the sum of the masses around it, weighted by a smooth ring-shaped kernel
+/// of the given `radius`. The growth function maps potentials close to `mu`,
+/// within about `sigma`, to growth and all others to decay, and every
+/// generation adds `1 / time` of the growth to each cell. Rules are written
+/// as `R13/T10/M0.15/S0.015`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lenia {
+    radius: usize,
+    time: usize,
+    mu: f64,
+    sigma: f64,
+    /// Offsets and weights of the normalized kernel, see [`Lenia::kernel`].
+    kernel: Vec<(isize, isize, f64)>,
+}
+
+impl Default for Lenia {
+    /// Parameters of Orbium, the most common glider.
+    fn default() -> Self {
+        Self::new(13, 10, 0.15, 0.015)
+    }
+}
+
+impl Lenia {
+    pub fn new(radius: usize, time: usize, mu: f64, sigma: f64) -> Self {
+        let radius = radius.clamp(1, MAX_RADIUS);
+        Self {
+            radius,
+            time: time.clamp(1, MAX_TIME),
+            mu,
+            sigma,
+            kernel: Self::kernel(radius),
+        }
+    }
+
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char| {
+            sections
+                .next()
+                .and_then(|part| part.strip_prefix(section))
+                .ok_or(RuleParseError::MissingSection(section))
+        };
+        let (radius, time, mu, sigma) = (next('R')?, next('T')?, next('M')?, next('S')?);
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        let invalid = |value: &str| RuleParseError::InvalidNumber(value.to_owned());
+        let radius = match radius.parse() {
+            Ok(radius @ 1..=MAX_RADIUS) => radius,
+            _ => return Err(invalid(radius)),
+        };
+        let time = match time.parse() {
+            Ok(time @ 1..=MAX_TIME) => time,
+            _ => return Err(invalid(time)),
+        };
+        let mu = match mu.parse() {
+            Ok(mu) if (0.0..=1.0).contains(&mu) => mu,
+            _ => return Err(invalid(mu)),
+        };
+        let sigma = match sigma.parse() {
+            Ok(sigma) if sigma > 0.0 && sigma <= 1.0 => sigma,
+            _ => return Err(invalid(sigma)),
+        };
+        Ok(Self::new(radius, time, mu, sigma))
+    }
+
+    /// The smooth ring `exp(4 - 1 / (r (1 - r)))` at the relative distance
+    /// `r` from the center, normalized to a sum of one.
+    fn kernel(radius: usize) -> Vec<(isize, isize, f64)> {
+        let radius = radius as isize;
+        let mut kernel = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let r = ((dx * dx + dy * dy) as f64).sqrt() / radius as f64;
+                if r > 0.0 && r < 1.0 {
+                    kernel.push((dx, dy, (4.0 - 1.0 / (r * (1.0 - r))).exp()));
+                }
+            }
+        }
+        let sum: f64 = kernel.iter().map(|(_, _, weight)| weight).sum();
+        for (_, _, weight) in &mut kernel {
+            *weight /= sum;
+        }
+        kernel
+    }
+
+    /// Weighted sum of the masses around `(pos_x, pos_y)`.
+    fn potential(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Mass>) -> f64 {
+        self.kernel
+            .iter()
+            .filter_map(|(dx, dy, weight)| Some(grid.get(pos_x + dx, pos_y + dy)?.0 * weight))
+            .sum()
+    }
+
+    /// Between -1 and 1, largest for potentials equal to `mu`.
+    fn growth(&self, potential: f64) -> f64 {
+        let distance = (potential - self.mu) / self.sigma;
+        2.0 * (-distance * distance / 2.0).exp() - 1.0
+    }
+}
+
+impl fmt::Display for Lenia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{}/T{}/M{}/S{}",
+            self.radius, self.time, self.mu, self.sigma
+        )
+    }
+}
+
+impl Automaton for Lenia {
+    type State = Mass;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let growth = self.growth(self.potential(pos, grid));
+        Mass((grid[pos].0 + growth / self.time as f64).clamp(0.0, 1.0))
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_mass(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_mass(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        curr.0 > 0.0
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        Mass(rng() as f64 / u32::MAX as f64)
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter {
+                name: "Radius",
+                value: self.radius as f64,
+                min: 1.0,
+                max: MAX_RADIUS as f64,
+                step: 1.0,
+            },
+            Parameter {
+                name: "Time T",
+                value: self.time as f64,
+                min: 1.0,
+                max: MAX_TIME as f64,
+                step: 1.0,
+            },
+            Parameter {
+                name: "Growth μ",
+                value: self.mu,
+                min: 0.0,
+                max: 0.5,
+                step: 0.001,
+            },
+            Parameter {
+                name: "Growth σ",
+                value: self.sigma,
+                min: 0.001,
+                max: 0.1,
+                step: 0.001,
+            },
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        let (mut radius, mut time, mut mu, mut sigma) =
+            (self.radius, self.time, self.mu, self.sigma);
+        match idx {
+            0 => radius = value.round() as usize,
+            1 => time = value.round() as usize,
+            2 => mu = value.clamp(0.0, 1.0),
+            3 => sigma = value.clamp(0.001, 1.0),
+            _ => return,
+        }
+        *self = Self::new(radius, time, mu, sigma);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{automaton::tests::step, theme::THEMES};
+
+    #[test]
+    fn parse_rules() {
+        let orbium = Lenia::parse("R13/T10/M0.15/S0.015").unwrap();
+        assert_eq!(orbium, Lenia::default());
+        assert_eq!(orbium.to_string(), "R13/T10/M0.15/S0.015");
+        assert_eq!(
+            Lenia::parse("R13/T10"),
+            Err(RuleParseError::MissingSection('M'))
+        );
+        assert_eq!(
+            Lenia::parse("R0/T10/M0.15/S0.015"),
+            Err(RuleParseError::InvalidNumber("0".to_owned()))
+        );
+        assert_eq!(
+            Lenia::parse("R13/T10/M0.15/S0"),
+            Err(RuleParseError::InvalidNumber("0".to_owned()))
+        );
+    }
+
+    #[test]
+    fn kernel_is_normalized_ring() {
+        let lenia = Lenia::default();
+        let sum: f64 = lenia.kernel.iter().map(|(_, _, weight)| weight).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert!(lenia.kernel.iter().all(|&(dx, dy, _)| (dx, dy) != (0, 0)));
+        let weight = |x, y| {
+            lenia
+                .kernel
+                .iter()
+                .find(|k| (k.0, k.1) == (x, y))
+                .unwrap()
+                .2
+        };
+        // Heaviest halfway out, and symmetric
+        assert!(weight(6, 0) > weight(2, 0) && weight(6, 0) > weight(11, 0));
+        assert_eq!(weight(3, 4), weight(-4, 3));
+    }
+
+    #[test]
+    fn mass_grows_near_mu_and_decays_elsewhere() {
+        let lenia = Lenia::parse("R2/T4/M0.5/S0.1").unwrap();
+        let mut grid = Grid::generate(8, 8);
+        for x in 0..8 {
+            for y in 0..8 {
+                grid[(x, y)] = Mass(0.5);
+            }
+        }
+        // The potential of a uniform grid is its mass, which is exactly mu
+        assert!((step(&lenia, &grid)[(0, 0)].0 - 0.75).abs() < 1e-9);
+        // An empty grid stays empty
+        let empty = Grid::generate(8, 8);
+        assert_eq!(step(&lenia, &empty), empty);
+    }
+
+    #[test]
+    fn masses_have_distinct_colors() {
+        let lenia = Lenia::default();
+        let states = lenia.states();
+        assert_eq!(states.len(), 5);
+        let styles: HashSet<_> = states
+            .iter()
+            .map(|mass| lenia.style(mass, &THEMES[0]))
+            .collect();
+        assert_eq!(styles.len(), states.len());
+    }
+}
@@ -0,0 +1,283 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{style_life, toggle_life, Automaton, Grid, LifeStates, RuleParseError, D2};
+
+/// Reversible block automaton on the Margolus neighborhood.
+///
+/// The grid is partitioned into 2×2 blocks and every block is replaced
+/// according to the rule's lookup table. The partition is shifted by one
+/// cell diagonally in odd generations, so that information can travel
+/// between blocks. Since the lookup table is a permutation, every
+/// generation can be computed back from its successor.
+///
+/// Blocks wrap around the grid's edges. On grids with odd dimensions, the
+/// cells that don't belong to a whole block keep their state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Margolus {
+    pub rule: MargolusRule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MargolusRule {
+    /// Blocks without exactly two live cells are inverted, blocks with
+    /// exactly three live cells are additionally rotated by 180°.
+    #[default]
+    Critters,
+    /// Blocks with all cells in the same state are inverted.
+    Tron,
+}
+
+impl MargolusRule {
+    /// The next state for each of the 16 block states.
+    ///
+    /// Bits 0 to 3 of a block state are its top-left, top-right,
+    /// bottom-left and bottom-right cell.
+    fn table(self) -> [u8; 16] {
+        let mut table = [0; 16];
+        for (block, next) in (0u8..).zip(table.iter_mut()) {
+            *next = match (self, block.count_ones()) {
+                (MargolusRule::Critters, 2) => block,
+                (MargolusRule::Critters, 3) => rotate_half(!block & 0b1111),
+                (MargolusRule::Critters, _) => !block & 0b1111,
+                (MargolusRule::Tron, 0 | 4) => !block & 0b1111,
+                (MargolusRule::Tron, _) => block,
+            };
+        }
+        table
+    }
+
+    /// The previous state for each of the 16 block states.
+    fn inverse_table(self) -> [u8; 16] {
+        let mut inverse = [0; 16];
+        for (block, next) in (0u8..).zip(self.table()) {
+            inverse[next as usize] = block;
+        }
+        inverse
+    }
+}
+
+impl Margolus {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let rule = match rule.trim().to_lowercase().as_str() {
+            "critters" => MargolusRule::Critters,
+            "tron" => MargolusRule::Tron,
+            _ => return Err(RuleParseError::UnknownRule(rule.to_owned())),
+        };
+        Ok(Self { rule })
+    }
+
+    /// Compute the generation before `grid`, which is generation `generation`.
+    ///
+    /// Returns `None` for generation zero, since the partition of earlier
+    /// generations is unknown.
+    pub fn step_back(&self, grid: &Grid<LifeStates>, generation: u64) -> Option<Grid<LifeStates>> {
+        let previous = generation.checked_sub(1)?;
+        Some(step_blocks(
+            grid,
+            partition_offset(previous),
+            &self.rule.inverse_table(),
+        ))
+    }
+}
+
+impl fmt::Display for Margolus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            MargolusRule::Critters => write!(f, "Critters"),
+            MargolusRule::Tron => write!(f, "Tron"),
+        }
+    }
+}
+
+impl Automaton for Margolus {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    /// The state of the cell after applying the rule with the unshifted partition.
+    ///
+    /// Cells can't know the generation, so [`Automaton::step_grid`] is used instead.
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let origin = (pos_x & !1, pos_y & !1);
+        let next = self.rule.table()[block_at(grid, origin) as usize];
+        let bit = (pos_x & 1) | (pos_y & 1) << 1;
+        if next >> bit & 1 == 1 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, generation: u64) -> Option<Grid<Self::State>> {
+        Some(step_blocks(
+            grid,
+            partition_offset(generation),
+            &self.rule.table(),
+        ))
+    }
+
+    fn step_grid_back(
+        &self,
+        grid: &Grid<Self::State>,
+        generation: u64,
+    ) -> Option<Grid<Self::State>> {
+        self.step_back(grid, generation)
+    }
+}
+
+/// Offset of the block partition used to compute the successor of `generation`.
+fn partition_offset(generation: u64) -> isize {
+    (generation % 2) as isize
+}
+
+/// Swap the top-left with the bottom-right and the top-right with the bottom-left cell.
+fn rotate_half(block: u8) -> u8 {
+    (block & 1) << 3 | (block & 2) << 1 | (block & 4) >> 1 | (block & 8) >> 3
+}
+
+/// Cells of the block with its top-left corner at `(x, y)`, wrapping around the edges.
+fn cells((x, y): (isize, isize), width: usize, height: usize) -> [(isize, isize); 4] {
+    let (right, bottom) = ((x + 1) % width as isize, (y + 1) % height as isize);
+    [(x, y), (right, y), (x, bottom), (right, bottom)]
+}
+
+fn block_at(grid: &Grid<LifeStates>, origin: (isize, isize)) -> u8 {
+    cells(origin, grid.width(), grid.height())
+        .iter()
+        .enumerate()
+        .filter(|(_, pos)| grid[**pos] == LifeStates::Alife)
+        .fold(0, |block, (bit, _)| block | 1 << bit)
+}
+
+/// Replace every block of the partition shifted by `offset` according to `table`.
+fn step_blocks(grid: &Grid<LifeStates>, offset: isize, table: &[u8; 16]) -> Grid<LifeStates> {
+    let (width, height) = (grid.width() as isize, grid.height() as isize);
+    // Skip blocks that would overlap with the first block on odd grids
+    let whole = |origin: isize, size: isize| origin + 1 < size || size % 2 == 0;
+    let mut next = grid.clone();
+    for x in (offset..width).step_by(2).filter(|&x| whole(x, width)) {
+        for y in (offset..height).step_by(2).filter(|&y| whole(y, height)) {
+            let block = table[block_at(grid, (x, y)) as usize];
+            for (bit, pos) in cells((x, y), grid.width(), grid.height())
+                .into_iter()
+                .enumerate()
+            {
+                next[pos] = if block >> bit & 1 == 1 {
+                    LifeStates::Alife
+                } else {
+                    LifeStates::Dead
+                };
+            }
+        }
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::XorShift;
+
+    fn random_grid(rng: &mut XorShift, width: usize, height: usize) -> Grid<LifeStates> {
+        let mut grid = Grid::generate(width, height);
+        for x in 0..width as isize {
+            for y in 0..height as isize {
+                if rng.next_f64() < 0.5 {
+                    grid[(x, y)] = LifeStates::Alife;
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn tables_are_permutations() {
+        for rule in [MargolusRule::Critters, MargolusRule::Tron] {
+            let table = rule.table();
+            let inverse = rule.inverse_table();
+            for block in 0..16 {
+                assert_eq!(inverse[table[block] as usize] as usize, block);
+            }
+        }
+    }
+
+    #[test]
+    fn step_back_restores_grid() {
+        let mut rng = XorShift::new(42);
+        for rule in [MargolusRule::Critters, MargolusRule::Tron] {
+            let margolus = Margolus { rule };
+            for (width, height) in [(8, 8), (6, 10), (7, 5)] {
+                let original = random_grid(&mut rng, width, height);
+                let mut grid = original.clone();
+                for generation in 0..6 {
+                    grid = margolus.step_grid(&grid, generation).unwrap();
+                }
+                for generation in (1..=6).rev() {
+                    grid = margolus.step_back(&grid, generation).unwrap();
+                }
+                assert_eq!(grid, original);
+            }
+        }
+    }
+
+    #[test]
+    fn partition_alternates() {
+        let margolus = Margolus {
+            rule: MargolusRule::Tron,
+        };
+        // Empty blocks are inverted with either partition
+        let grid = Grid::generate(4, 4);
+        let even = margolus.step_grid(&grid, 0).unwrap();
+        assert_eq!(even[(0, 0)], LifeStates::Alife);
+        assert_eq!(even[(3, 3)], LifeStates::Alife);
+        assert_eq!(even, margolus.step_grid(&grid, 1).unwrap());
+        // A single live cell at the origin only spoils the shifted block around it
+        let mut grid = Grid::generate(4, 4);
+        grid[(1, 1)] = LifeStates::Alife;
+        let odd = margolus.step_grid(&grid, 1).unwrap();
+        assert_eq!(odd[(2, 2)], LifeStates::Dead);
+        assert_eq!(odd[(3, 3)], LifeStates::Alife);
+        assert_eq!(odd[(0, 0)], LifeStates::Alife);
+    }
+
+    #[test]
+    fn parse_rule() {
+        assert_eq!(
+            Margolus::parse("tron"),
+            Ok(Margolus {
+                rule: MargolusRule::Tron
+            })
+        );
+        assert_eq!(Margolus::default().to_string(), "Critters");
+        assert!(Margolus::parse("billiard").is_err());
+    }
+
+    #[test]
+    fn step_back_needs_earlier_generation() {
+        assert!(Margolus::default()
+            .step_back(&Grid::generate(2, 2), 0)
+            .is_none());
+    }
+}
@@ -0,0 +1,236 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{random::CellNoise, theme::Theme};
+
+use super::{count_moore_neighbors, Automaton, Grid, RuleParseError, D2};
+
+/// Three species competing in a game of rock–paper–scissors.
+///
+/// Each species is beaten by the next one: paper beats rock, scissors beat
+/// paper and rock beats scissors. A cell is taken over by the species that
+/// beats it if at least `threshold` of its Moore neighbors belong to it,
+/// which produces spiral waves.
+///
+/// With `noise`, every cell turns into a random species with a probability
+/// of `noise` per mille in every generation. The noise is drawn from a
+/// [`CellNoise`], so it only depends on the cell and the generation, see
+/// [`Automaton::step_grid`]. [`Automaton::update`] alone applies the rule
+/// without noise. Rules are written as `T3/N1`, threshold and noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RockPaperScissors {
+    pub threshold: u8,
+    /// Probability of a random change in per mille.
+    pub noise: u16,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Species {
+    #[default]
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Species {
+    pub const ALL: [Species; 3] = [Species::Rock, Species::Paper, Species::Scissors];
+
+    /// The species that beats this one.
+    pub fn predator(self) -> Self {
+        match self {
+            Species::Rock => Species::Paper,
+            Species::Paper => Species::Scissors,
+            Species::Scissors => Species::Rock,
+        }
+    }
+}
+
+impl Default for RockPaperScissors {
+    fn default() -> Self {
+        Self {
+            threshold: 3,
+            noise: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl RockPaperScissors {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let threshold = sections
+            .next()
+            .and_then(|section| section.strip_prefix('T'))
+            .ok_or(RuleParseError::MissingSection('T'))?;
+        let threshold = match threshold.parse() {
+            Ok(threshold @ 1..=8) => threshold,
+            _ => return Err(RuleParseError::InvalidNumber(threshold.to_owned())),
+        };
+        let noise = match sections.next() {
+            Some(section) => {
+                let noise = section
+                    .strip_prefix('N')
+                    .ok_or_else(|| RuleParseError::Trailing(format!("/{}", section)))?;
+                match noise.parse() {
+                    Ok(noise @ 0..=1000) => noise,
+                    _ => return Err(RuleParseError::InvalidNumber(noise.to_owned())),
+                }
+            }
+            None => 0,
+        };
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        Ok(Self {
+            threshold,
+            noise,
+            ..Self::default()
+        })
+    }
+
+    fn predators(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Species>) -> u8 {
+        let predator = grid[(pos_x, pos_y)].predator();
+        count_moore_neighbors(pos_x, pos_y, grid, |species| *species == predator)
+    }
+}
+
+impl fmt::Display for RockPaperScissors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "T{}", self.threshold)?;
+        if self.noise > 0 {
+            write!(f, "/N{}", self.noise)?;
+        }
+        Ok(())
+    }
+}
+
+impl Automaton for RockPaperScissors {
+    type State = Species;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let curr = grid[pos];
+        if self.predators(pos, grid) >= self.threshold {
+            curr.predator()
+        } else {
+            curr
+        }
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        Some(self.predators(pos, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        curr.predator()
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            Species::Rock => theme.palette.red.into(),
+            Species::Paper => theme.palette.yellow.into(),
+            Species::Scissors => theme.palette.blue.into(),
+        }
+    }
+
+    /// Rock is the background species, the population counts the other two.
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr != Species::Rock
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % 2 {
+            0 => Species::Paper,
+            _ => Species::Scissors,
+        }
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self {
+            seed: self.seed,
+            ..Self::parse(rule)?
+        };
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, generation: u64) -> Option<Grid<Self::State>> {
+        let noise = CellNoise::new(self.seed);
+        let mut next = grid.clone();
+        for x in 0..grid.width() as isize {
+            for y in 0..grid.height() as isize {
+                next[(x, y)] = self.update((x, y), grid);
+                if self.noise > 0 {
+                    let mut rng = noise.rng((x, y), generation);
+                    if rng.next_u32() % 1000 < self.noise as u32 {
+                        next[(x, y)] = Species::ALL[rng.next_u32() as usize % 3];
+                    }
+                }
+            }
+        }
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(
+            RockPaperScissors::parse("T3"),
+            Ok(RockPaperScissors::default())
+        );
+        let noisy = RockPaperScissors::parse("T2/N15").unwrap();
+        assert_eq!((noisy.threshold, noisy.noise), (2, 15));
+        assert_eq!(noisy.to_string(), "T2/N15");
+        assert_eq!(
+            RockPaperScissors::parse("3"),
+            Err(RuleParseError::MissingSection('T'))
+        );
+        assert_eq!(
+            RockPaperScissors::parse("T9"),
+            Err(RuleParseError::InvalidNumber("9".to_owned()))
+        );
+        assert_eq!(
+            RockPaperScissors::parse("T3/N1001"),
+            Err(RuleParseError::InvalidNumber("1001".to_owned()))
+        );
+    }
+
+    #[test]
+    fn predators_take_over() {
+        let rule = RockPaperScissors::default();
+        let mut grid = Grid::generate(5, 5);
+        for pos in [(1, 1), (2, 1), (3, 1)] {
+            grid[pos] = Species::Paper;
+        }
+        grid[(2, 3)] = Species::Scissors;
+        let next = rule.step_grid(&grid, 0).unwrap();
+        // Rock with three paper neighbors is taken over
+        assert_eq!(next[(2, 2)], Species::Paper);
+        // With two it survives
+        assert_eq!(next[(1, 2)], Species::Rock);
+        // Paper without scissors next to it stays
+        assert_eq!(next[(2, 1)], Species::Paper);
+        // A lone scissors cell is beaten by the surrounding rock
+        assert_eq!(next[(2, 3)], Species::Rock);
+    }
+
+    #[test]
+    fn noise_is_seeded() {
+        let grid = Grid::generate(20, 20);
+        let noisy = RockPaperScissors::parse("T3/N100").unwrap();
+        let next = noisy.step_grid(&grid, 5).unwrap();
+        assert_eq!(noisy.step_grid(&grid, 5), Some(next.clone()));
+        let changed = next.grid.iter().filter(|s| **s != Species::Rock).count();
+        assert!((5..=60).contains(&changed), "{}", changed);
+        let quiet = RockPaperScissors::default();
+        assert_eq!(quiet.step_grid(&grid, 5), Some(grid));
+    }
+}
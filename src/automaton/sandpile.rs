@@ -0,0 +1,140 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{Automaton, Grid, D2};
+
+/// Grain count at which a cell topples.
+pub const TOPPLE: u32 = 4;
+
+/// The Abelian sandpile.
+///
+/// Every cell holds a number of grains. Cells with at least four grains
+/// topple, giving one grain to each of their von Neumann neighbors. All
+/// unstable cells topple at once, which leads to the same stable pile as
+/// toppling them one by one. On a grid with [`EdgeBehavior::Dead`] edges,
+/// grains toppled over the edge are lost, otherwise sand is conserved.
+///
+/// Grains are added with [`Automaton::drop_sand`].
+///
+/// [`EdgeBehavior::Dead`]: super::EdgeBehavior::Dead
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sandpile;
+
+impl Sandpile {
+    fn toppling_neighbors(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<u32>) -> u32 {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .filter(|(dx, dy)| matches!(grid.get(pos_x + dx, pos_y + dy), Some(&c) if c >= TOPPLE))
+            .count() as u32
+    }
+}
+
+impl Automaton for Sandpile {
+    type State = u32;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let curr = grid[pos];
+        let kept = if curr >= TOPPLE { curr - TOPPLE } else { curr };
+        kept.saturating_add(self.toppling_neighbors(pos, grid))
+    }
+
+    fn neighbor_count(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Option<i32> {
+        Some(self.toppling_neighbors(pos, grid) as i32)
+    }
+
+    /// Cycles through the stable grain counts.
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        (curr + 1) % TOPPLE
+    }
+
+    fn drop_sand(&self, curr: &Self::State) -> Option<Self::State> {
+        Some(curr.saturating_add(1))
+    }
+
+    /// A ramp from dark blue to pale yellow, unstable cells are red.
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match *curr {
+            0 => theme.palette.dead.into(),
+            grains if grains < TOPPLE => {
+                let t = (grains - 1) as f64 / (TOPPLE - 2) as f64;
+                format!(
+                    "hsl({:.0}, 70%, {:.0}%)",
+                    230.0 - 180.0 * t,
+                    35.0 + 35.0 * t
+                )
+                .into()
+            }
+            _ => theme.palette.red.into(),
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr > 0
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        1 + rng() % (TOPPLE - 1)
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        automaton::{tests::step, EdgeBehavior},
+        theme::THEMES,
+    };
+
+    fn grains(grid: &Grid<u32>) -> u32 {
+        grid.grid.iter().sum()
+    }
+
+    #[test]
+    fn cells_topple_onto_neighbors() {
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = 5;
+        grid[(2, 1)] = 3;
+        let next = step(&Sandpile, &grid);
+        assert_eq!(next[(2, 2)], 1);
+        assert_eq!(next[(2, 1)], 4);
+        assert_eq!(next[(1, 2)], 1);
+        assert_eq!(next[(0, 0)], 0);
+        assert_eq!(grains(&next), grains(&grid));
+    }
+
+    #[test]
+    fn piles_settle_and_lose_sand_at_dead_edges() {
+        let mut grid = Grid::with_edge_behavior(7, 7, EdgeBehavior::Dead);
+        for _ in 0..100 {
+            grid[(3, 3)] = Sandpile.drop_sand(&grid[(3, 3)]).unwrap();
+            while grid.grid.iter().any(|&c| c >= TOPPLE) {
+                grid = step(&Sandpile, &grid);
+            }
+        }
+        assert!(grains(&grid) < 100);
+        // The stable pile keeps the symmetry of the drop site
+        for x in 0..7 {
+            for y in 0..7 {
+                assert_eq!(grid[(x, y)], grid[(6 - x, y)]);
+                assert_eq!(grid[(x, y)], grid[(y, x)]);
+            }
+        }
+    }
+
+    #[test]
+    fn grain_counts_have_distinct_colors() {
+        let styles: HashSet<_> = (0..=TOPPLE)
+            .map(|grains| Sandpile.style(&grains, &THEMES[0]))
+            .collect();
+        assert_eq!(styles.len(), TOPPLE as usize + 1);
+        assert_eq!(Sandpile.states(), vec![0, 1, 2, 3]);
+    }
+}
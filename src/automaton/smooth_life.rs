@@ -0,0 +1,287 @@
+use std::{borrow::Cow, fmt};
+
+use crate::theme::Theme;
+
+use super::{style_mass, toggle_mass, Automaton, Grid, Mass, Parameter, RuleParseError, D2};
+
+/// Largest outer radius offered by the slider.
+const MAX_RADIUS: usize = 30;
+/// Widths of the smooth steps over the outer and inner filling.
+const ALPHA_N: f64 = 0.028;
+const ALPHA_M: f64 = 0.147;
+/// Smaller fillings are rounded to zero, so that empty regions stay empty.
+const EPSILON: f64 = 1.0 / 256.0;
+
+/// Offsets of a neighborhood from its center cell.
+type Offsets = Vec<(isize, isize)>;
+
+/// Rafler's SmoothLife, Life on continuous states and neighborhoods.
+///
+/// The inner filling `m` of a cell is the average [`Mass`] in the disk of a
+/// third of the `radius` around it, the outer filling `n` the average in the
+/// annulus between that disk and the full `radius`. Dead cells, with `m`
+/// below one half, are born if `n` lies in `birth`, living cells survive if
+/// `n` lies in `death`. Between the two and at the interval bounds, the rule
+/// is blended by smooth steps. Rules are written as
+/// `R12/B0.278-0.365/D0.267-0.445`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothLife {
+    radius: usize,
+    birth: (f64, f64),
+    death: (f64, f64),
+    /// Offsets in the inner disk, including the cell itself.
+    inner: Offsets,
+    /// Offsets in the outer annulus.
+    outer: Offsets,
+}
+
+impl Default for SmoothLife {
+    fn default() -> Self {
+        Self::new(12, (0.278, 0.365), (0.267, 0.445))
+    }
+}
+
+/// Smooth step from zero to one around `a`.
+fn sigmoid(x: f64, a: f64, alpha: f64) -> f64 {
+    1.0 / (1.0 + (-(x - a) * 4.0 / alpha).exp())
+}
+
+impl SmoothLife {
+    pub fn new(radius: usize, birth: (f64, f64), death: (f64, f64)) -> Self {
+        let radius = radius.clamp(3, MAX_RADIUS);
+        let (inner, outer) = Self::neighborhoods(radius);
+        Self {
+            radius,
+            birth,
+            death,
+            inner,
+            outer,
+        }
+    }
+
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char| {
+            sections
+                .next()
+                .and_then(|part| part.strip_prefix(section))
+                .ok_or(RuleParseError::MissingSection(section))
+        };
+        let (radius, birth, death) = (next('R')?, next('B')?, next('D')?);
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        let radius = match radius.parse() {
+            Ok(radius @ 3..=MAX_RADIUS) => radius,
+            _ => return Err(RuleParseError::InvalidNumber(radius.to_owned())),
+        };
+        let interval = |value: &str| {
+            let bounds = value.split_once('-').and_then(|(low, high)| {
+                let (low, high): (f64, f64) = (low.parse().ok()?, high.parse().ok()?);
+                (0.0 <= low && low <= high && high <= 1.0).then_some((low, high))
+            });
+            bounds.ok_or_else(|| RuleParseError::InvalidNumber(value.to_owned()))
+        };
+        Ok(Self::new(radius, interval(birth)?, interval(death)?))
+    }
+
+    /// Offsets in the inner disk and in the outer annulus.
+    fn neighborhoods(radius: usize) -> (Offsets, Offsets) {
+        let inner_radius = radius as f64 / 3.0;
+        let radius = radius as isize;
+        let (mut inner, mut outer) = (Vec::new(), Vec::new());
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let distance = ((dx * dx + dy * dy) as f64).sqrt();
+                if distance <= inner_radius {
+                    inner.push((dx, dy));
+                } else if distance <= radius as f64 {
+                    outer.push((dx, dy));
+                }
+            }
+        }
+        (inner, outer)
+    }
+
+    /// Average mass at `offsets` from `(pos_x, pos_y)`.
+    fn filling(
+        (pos_x, pos_y): (isize, isize),
+        offsets: &[(isize, isize)],
+        grid: &Grid<Mass>,
+    ) -> f64 {
+        let sum: f64 = offsets
+            .iter()
+            .filter_map(|(dx, dy)| grid.get(pos_x + dx, pos_y + dy))
+            .map(|mass| mass.0)
+            .sum();
+        sum / offsets.len() as f64
+    }
+
+    /// The new mass for outer filling `n` and inner filling `m`.
+    fn transition(&self, n: f64, m: f64) -> f64 {
+        let alive = sigmoid(m, 0.5, ALPHA_M);
+        let blend = |birth: f64, death: f64| birth * (1.0 - alive) + death * alive;
+        let low = blend(self.birth.0, self.death.0);
+        let high = blend(self.birth.1, self.death.1);
+        sigmoid(n, low, ALPHA_N) * (1.0 - sigmoid(n, high, ALPHA_N))
+    }
+}
+
+impl fmt::Display for SmoothLife {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{}/B{}-{}/D{}-{}",
+            self.radius, self.birth.0, self.birth.1, self.death.0, self.death.1
+        )
+    }
+}
+
+impl Automaton for SmoothLife {
+    type State = Mass;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let m = Self::filling(pos, &self.inner, grid);
+        let n = Self::filling(pos, &self.outer, grid);
+        match self.transition(n, m) {
+            mass if mass < EPSILON => Mass(0.0),
+            mass => Mass(mass.min(1.0)),
+        }
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_mass(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_mass(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        curr.0 > 0.0
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        Mass(rng() as f64 / u32::MAX as f64)
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(self.radius)
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let bound = |name, value| Parameter {
+            name,
+            value,
+            min: 0.0,
+            max: 1.0,
+            step: 0.001,
+        };
+        vec![
+            Parameter {
+                name: "Outer radius",
+                value: self.radius as f64,
+                min: 3.0,
+                max: MAX_RADIUS as f64,
+                step: 1.0,
+            },
+            bound("Birth from", self.birth.0),
+            bound("Birth to", self.birth.1),
+            bound("Death from", self.death.0),
+            bound("Death to", self.death.1),
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        let (mut radius, mut birth, mut death) = (self.radius, self.birth, self.death);
+        // Bounds are kept in order, like in parsed rules
+        let bound = value.clamp(0.0, 1.0);
+        match idx {
+            0 => radius = value.round() as usize,
+            1 => birth.0 = bound.min(birth.1),
+            2 => birth.1 = bound.max(birth.0),
+            3 => death.0 = bound.min(death.1),
+            4 => death.1 = bound.max(death.0),
+            _ => return,
+        }
+        *self = Self::new(radius, birth, death);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::tests::step;
+
+    #[test]
+    fn parse_rules() {
+        let rule = SmoothLife::parse("R12/B0.278-0.365/D0.267-0.445").unwrap();
+        assert_eq!(rule, SmoothLife::default());
+        assert_eq!(rule.to_string(), "R12/B0.278-0.365/D0.267-0.445");
+        assert_eq!(
+            SmoothLife::parse("R12/B0.278-0.365"),
+            Err(RuleParseError::MissingSection('D'))
+        );
+        assert_eq!(
+            SmoothLife::parse("R12/B0.3-0.2/D0.267-0.445"),
+            Err(RuleParseError::InvalidNumber("0.3-0.2".to_owned()))
+        );
+        assert_eq!(
+            SmoothLife::parse("R2/B0.278-0.365/D0.267-0.445"),
+            Err(RuleParseError::InvalidNumber("2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn neighborhoods_split_the_disk() {
+        let rule = SmoothLife::default();
+        assert!(rule.inner.contains(&(0, 0)));
+        assert!(rule.inner.contains(&(4, 0)) && !rule.inner.contains(&(5, 0)));
+        assert!(rule.outer.contains(&(12, 0)) && !rule.outer.contains(&(12, 1)));
+        assert!(rule.inner.iter().all(|offset| !rule.outer.contains(offset)));
+        let mut wide = rule.clone();
+        wide.set_parameter(0, 21.0);
+        assert_eq!(wide.to_string(), "R21/B0.278-0.365/D0.267-0.445");
+        assert!(wide.inner.contains(&(7, 0)) && wide.outer.contains(&(21, 0)));
+    }
+
+    #[test]
+    fn bounds_stay_ordered() {
+        let mut rule = SmoothLife::default();
+        rule.set_parameter(1, 0.5);
+        rule.set_parameter(4, 0.1);
+        assert_eq!(rule.to_string(), "R12/B0.365-0.365/D0.267-0.267");
+        rule.set_parameter(2, 2.0);
+        assert_eq!(rule.to_string(), "R12/B0.365-1/D0.267-0.267");
+        assert_eq!(SmoothLife::parse(&rule.to_string()), Ok(rule));
+    }
+
+    #[test]
+    fn cells_follow_birth_and_death_intervals() {
+        let rule = SmoothLife::default();
+        assert!(rule.transition(0.32, 0.0) > 0.9);
+        assert!(rule.transition(0.42, 0.0) < 0.1);
+        assert!(rule.transition(0.42, 1.0) > 0.9);
+        assert!(rule.transition(0.6, 1.0) < 0.1);
+        // Crowded cells die, empty ones stay empty
+        let mut full = Grid::generate(30, 30);
+        for x in 0..30 {
+            for y in 0..30 {
+                full[(x, y)] = Mass(1.0);
+            }
+        }
+        assert_eq!(step(&rule, &full), Grid::generate(30, 30));
+        let empty = Grid::generate(30, 30);
+        assert_eq!(step(&rule, &empty), empty);
+    }
+}
@@ -0,0 +1,608 @@
+use std::{borrow::Cow, collections::HashSet, fmt};
+
+use crate::theme::Theme;
+
+use super::{langtons_ant::Heading, Automaton, Grid, RuleParseError, D2};
+
+/// Turmites, Langton's Ant generalized to multiple colors, internal states and agents.
+///
+/// Every agent has an internal state. Depending on its state and the color
+/// of its cell, it writes a new color, turns, switches to the next state and
+/// moves forward by one cell, as given by the transition table.
+///
+/// Agents are stored as part of the cell they stand on, like
+/// [`LangtonsAnt`](super::LangtonsAnt). All agents move at once in
+/// [`Automaton::step_grid`]. Agents that would move onto the same cell are
+/// resolved by their id and position, see [`Turmite::step`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turmite {
+    /// Transitions indexed by agent state and cell color.
+    table: Vec<Vec<Transition>>,
+}
+
+/// Largest number of agent states and of colors of a table built in the editor.
+pub const MAX_TURMITE_STATES: usize = 8;
+pub const MAX_TURMITE_COLORS: usize = 8;
+
+/// Named tables to start from.
+pub const TURMITE_PRESETS: &[(&str, &str)] = &[
+    ("Langton's Ant", "{{{1,2,0},{0,8,0}}}"),
+    ("Square builder", "{{{1,1,1},{1,8,0}},{{1,2,1},{0,1,0}}}"),
+    ("Fibonacci spiral", "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}"),
+    ("Symmetric LLRR", "{{{1,8,0},{2,8,0},{3,2,0},{0,2,0}}}"),
+    ("Chaotic RLR", "{{{1,2,0},{2,8,0},{0,2,0}}}"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// Color written to the cell.
+    pub write: u8,
+    pub turn: Turn,
+    /// State of the agent afterwards.
+    pub next: u8,
+}
+
+/// Turn relative to the current heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    None,
+    Right,
+    Around,
+    Left,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurmiteCell {
+    pub color: u8,
+    pub agent: Option<Agent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Agent {
+    /// Distinguishes agents when drawing and resolving conflicts.
+    pub id: u8,
+    pub state: u8,
+    pub heading: Heading,
+}
+
+impl Turn {
+    pub const ALL: [Turn; 4] = [Turn::None, Turn::Right, Turn::Around, Turn::Left];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Turn::None => "Straight",
+            Turn::Right => "Right",
+            Turn::Around => "U-turn",
+            Turn::Left => "Left",
+        }
+    }
+
+    /// Code of the turn in the usual table notation.
+    fn code(self) -> u8 {
+        match self {
+            Turn::None => 1,
+            Turn::Right => 2,
+            Turn::Around => 4,
+            Turn::Left => 8,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Turn::None),
+            2 => Some(Turn::Right),
+            4 => Some(Turn::Around),
+            8 => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn apply(self, heading: Heading) -> Heading {
+        match self {
+            Turn::None => heading,
+            Turn::Right => heading.turn_right(),
+            Turn::Around => heading.turn_right().turn_right(),
+            Turn::Left => heading.turn_left(),
+        }
+    }
+}
+
+impl Default for Turmite {
+    /// A two-state turmite filling a growing, roughly square region.
+    fn default() -> Self {
+        Self::parse("{{{1,1,1},{1,8,0}},{{1,2,1},{0,1,0}}}").expect("Valid default table")
+    }
+}
+
+impl Turmite {
+    /// Parse a table in the usual notation, e.g. `{{{1,2,0},{0,8,0}}}` for Langton's Ant.
+    ///
+    /// The table contains a list of transitions for every state, one for
+    /// each color. Each transition is the color to write, the turn (1 for
+    /// none, 2 for right, 4 for around and 8 for left) and the next state.
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let invalid = || RuleParseError::InvalidTable(rule.to_owned());
+        let rule: String = rule.chars().filter(|c| !c.is_whitespace()).collect();
+        let inner = rule
+            .strip_prefix("{{{")
+            .and_then(|rule| rule.strip_suffix("}}}"))
+            .ok_or_else(invalid)?;
+        let table = inner
+            .split("}},{{")
+            .map(|state| {
+                state
+                    .split("},{")
+                    .map(|transition| {
+                        let numbers = transition
+                            .split(',')
+                            .map(|number| number.parse::<u8>().map_err(|_| invalid()))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        match numbers[..] {
+                            [write, turn, next] => Ok(Transition {
+                                write,
+                                turn: Turn::from_code(turn).ok_or_else(invalid)?,
+                                next,
+                            }),
+                            _ => Err(invalid()),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let colors = table[0].len();
+        let valid = table.iter().flatten().all(|transition| {
+            (transition.write as usize) < colors && (transition.next as usize) < table.len()
+        });
+        if !valid || table.iter().any(|transitions| transitions.len() != colors) {
+            return Err(invalid());
+        }
+        Ok(Self { table })
+    }
+
+    pub fn colors(&self) -> usize {
+        self.table[0].len()
+    }
+
+    /// Number of internal states of the agents.
+    pub fn state_count(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Transitions indexed by agent state and cell color.
+    pub fn table(&self) -> &[Vec<Transition>] {
+        &self.table
+    }
+
+    /// Replace a single transition, keeping color and state in range.
+    pub fn set_transition(&mut self, state: usize, color: usize, transition: Transition) {
+        let (colors, states) = (self.colors(), self.state_count());
+        if let Some(slot) = self.table.get_mut(state).and_then(|t| t.get_mut(color)) {
+            *slot = Transition {
+                write: transition.write.min(colors as u8 - 1),
+                next: transition.next.min(states as u8 - 1),
+                ..transition
+            };
+        }
+    }
+
+    /// Change the number of states and colors, each between one and its maximum.
+    ///
+    /// New transitions keep the color, go straight and stay in their state.
+    /// Transitions to removed colors or states are redirected to the last remaining one.
+    pub fn resize(&mut self, states: usize, colors: usize) {
+        let states = states.clamp(1, MAX_TURMITE_STATES);
+        let colors = colors.clamp(1, MAX_TURMITE_COLORS);
+        self.table.truncate(states);
+        for state in self.table.len()..states {
+            self.table.push(Vec::new());
+            self.table[state].push(Transition {
+                write: 0,
+                turn: Turn::None,
+                next: state as u8,
+            });
+        }
+        for (state, transitions) in self.table.iter_mut().enumerate() {
+            transitions.truncate(colors);
+            for color in transitions.len()..colors {
+                transitions.push(Transition {
+                    write: color as u8,
+                    turn: Turn::None,
+                    next: state as u8,
+                });
+            }
+            for transition in transitions {
+                transition.write = transition.write.min(colors as u8 - 1);
+                transition.next = transition.next.min(states as u8 - 1);
+            }
+        }
+    }
+
+    fn transition(&self, agent: &Agent, color: u8) -> Transition {
+        // Cells may hold colors or agents of an earlier, larger table
+        let state = self
+            .table
+            .get(agent.state as usize)
+            .unwrap_or(&self.table[0]);
+        state.get(color as usize).copied().unwrap_or(state[0])
+    }
+
+    /// Move all agents by one step.
+    ///
+    /// Agents are handled in order of their id and position. An agent that
+    /// would move onto a cell claimed by an earlier agent, or by an agent
+    /// that can't move, stays on its cell instead. It still writes, turns
+    /// and changes its state.
+    pub fn step(&self, grid: &Grid<TurmiteCell>) -> Grid<TurmiteCell> {
+        let mut agents: Vec<_> = (0..grid.width() as isize)
+            .flat_map(|x| (0..grid.height() as isize).map(move |y| (x, y)))
+            .filter_map(|pos| grid[pos].agent.map(|agent| (pos, agent)))
+            .collect();
+        agents.sort_by_key(|&((x, y), agent)| (agent.id, y, x));
+        let mut next = grid.clone();
+        let moves: Vec<_> = agents
+            .iter()
+            .map(|&(pos, agent)| {
+                let transition = self.transition(&agent, grid[pos].color);
+                next[pos] = TurmiteCell {
+                    color: transition.write,
+                    agent: None,
+                };
+                let agent = Agent {
+                    state: transition.next,
+                    heading: transition.turn.apply(agent.heading),
+                    ..agent
+                };
+                let (dx, dy) = agent.heading.offset();
+                (pos, agent, grid.normalize(pos.0 + dx, pos.1 + dy))
+            })
+            .collect();
+        // Stopping an agent frees its target, but blocks its current cell
+        let mut stays: Vec<_> = moves
+            .iter()
+            .map(|(_, _, target)| target.is_none())
+            .collect();
+        loop {
+            let mut claimed: HashSet<_> = moves
+                .iter()
+                .zip(&stays)
+                .filter(|(_, &stays)| stays)
+                .map(|((pos, _, _), _)| *pos)
+                .collect();
+            let mut changed = false;
+            for ((_, _, target), stays) in moves.iter().zip(stays.iter_mut()) {
+                if let (Some(target), false) = (target, *stays) {
+                    if !claimed.insert(*target) {
+                        *stays = true;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        for ((pos, agent, target), stays) in moves.into_iter().zip(stays) {
+            let pos = if stays { pos } else { target.unwrap_or(pos) };
+            next[pos].agent = Some(agent);
+        }
+        next
+    }
+}
+
+impl fmt::Display for Turmite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let states: Vec<_> = self
+            .table
+            .iter()
+            .map(|transitions| {
+                let transitions: Vec<_> = transitions
+                    .iter()
+                    .map(|t| format!("{{{},{},{}}}", t.write, t.turn.code(), t.next))
+                    .collect();
+                format!("{{{}}}", transitions.join(","))
+            })
+            .collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+impl Automaton for Turmite {
+    type State = TurmiteCell;
+    type Dimension = D2;
+
+    /// The state of the cell if no agents are blocked.
+    ///
+    /// Of multiple agents moving onto the cell, only the one with the lowest
+    /// id arrives. [`Automaton::step_grid`] resolves conflicts properly.
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        let curr = &grid[(pos_x, pos_y)];
+        let color = match &curr.agent {
+            Some(agent) => self.transition(agent, curr.color).write,
+            None => curr.color,
+        };
+        let agent = Heading::ALL
+            .iter()
+            .filter_map(|&towards| {
+                let (dx, dy) = towards.offset();
+                let neighbor = grid.get(pos_x + dx, pos_y + dy)?;
+                let agent = neighbor.agent?;
+                let transition = self.transition(&agent, neighbor.color);
+                let heading = transition.turn.apply(agent.heading);
+                (heading.offset() == (-dx, -dy)).then_some(Agent {
+                    state: transition.next,
+                    heading,
+                    ..agent
+                })
+            })
+            .min_by_key(|agent| agent.id);
+        TurmiteCell { color, agent }
+    }
+
+    /// Cycle through the colors, keeping the agent.
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        TurmiteCell {
+            color: ((curr.color as usize + 1) % self.colors()) as u8,
+            ..curr
+        }
+    }
+
+    fn toggle_agent(&self, curr: &Self::State, id: u8) -> Option<Self::State> {
+        let agent = match curr.agent {
+            Some(_) => None,
+            None => Some(Agent {
+                id,
+                state: 0,
+                heading: Heading::North,
+            }),
+        };
+        Some(TurmiteCell { agent, ..*curr })
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        curr.color != 0
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            // Golden angle steps keep the hues of consecutive ids apart
+            TurmiteCell {
+                agent: Some(agent), ..
+            } => format!("hsl({}, 90%, 55%)", agent.id as u32 * 137 % 360).into(),
+            TurmiteCell { color: 0, .. } => theme.palette.dead.into(),
+            TurmiteCell { color: 1, .. } => theme.palette.alive.into(),
+            TurmiteCell { color, .. } => {
+                let t = (*color - 1) as f64 / (self.colors().saturating_sub(2).max(1)) as f64;
+                format!(
+                    "hsl({:.0}, 50%, {:.0}%)",
+                    200.0 - 160.0 * t,
+                    65.0 - 15.0 * t
+                )
+                .into()
+            }
+        }
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self::parse(rule)?;
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, _generation: u64) -> Option<Grid<Self::State>> {
+        Some(self.step(grid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LANGTONS_ANT: &str = "{{{1,2,0},{0,8,0}}}";
+
+    fn agent(id: u8, heading: Heading) -> Option<Agent> {
+        Some(Agent {
+            id,
+            state: 0,
+            heading,
+        })
+    }
+
+    fn find_agents(grid: &Grid<TurmiteCell>) -> Vec<((isize, isize), Agent)> {
+        (0..grid.height() as isize)
+            .flat_map(|y| (0..grid.width() as isize).map(move |x| (x, y)))
+            .filter_map(|pos| grid[pos].agent.map(|agent| (pos, agent)))
+            .collect()
+    }
+
+    #[test]
+    fn parse_and_display_table() {
+        let turmite = Turmite::parse(" {{{1, 2, 0}, {0, 8, 0}}} ").unwrap();
+        assert_eq!(turmite.to_string(), LANGTONS_ANT);
+        assert_eq!(turmite.colors(), 2);
+        let default = Turmite::default();
+        assert_eq!(Turmite::parse(&default.to_string()), Ok(default));
+        // Unknown turn, color out of range, state out of range, ragged table
+        for rule in [
+            "{{{1,3,0},{0,8,0}}}",
+            "{{{2,2,0},{0,8,0}}}",
+            "{{{1,2,1},{0,8,0}}}",
+            "{{{1,2,1},{0,8,0}},{{1,2,0}}}",
+            "B3/S23",
+        ] {
+            assert!(Turmite::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn single_agent_matches_langtons_ant() {
+        let turmite = Turmite::parse(LANGTONS_ANT).unwrap();
+        let mut grid: Grid<TurmiteCell> = Grid::generate(8, 8);
+        grid[(5, 5)].agent = agent(0, Heading::North);
+        let expected = [
+            ((6, 5), Heading::East),
+            ((6, 6), Heading::South),
+            ((5, 6), Heading::West),
+            ((5, 5), Heading::North),
+            ((4, 5), Heading::West),
+        ];
+        for (pos, heading) in expected {
+            grid = turmite.step(&grid);
+            assert_eq!(find_agents(&grid), vec![(pos, agent(0, heading).unwrap())]);
+            // Without conflicts, the local rule agrees
+            assert_eq!(
+                crate::automaton::tests::step(&turmite, &grid),
+                turmite.step(&grid)
+            );
+        }
+    }
+
+    #[test]
+    fn two_state_turmite_grows() {
+        let turmite = Turmite::default();
+        let mut grid: Grid<TurmiteCell> = Grid::generate(40, 40);
+        grid[(20, 20)].agent = agent(0, Heading::North);
+        // The first steps walk two small loops, switching states on the way
+        let expected = [
+            ((20, 19), 1, Heading::North),
+            ((21, 19), 1, Heading::East),
+            ((21, 20), 1, Heading::South),
+            ((20, 20), 1, Heading::West),
+            ((19, 20), 0, Heading::West),
+            ((18, 20), 1, Heading::West),
+            ((18, 19), 1, Heading::North),
+            ((19, 19), 1, Heading::East),
+        ];
+        for (pos, state, heading) in expected {
+            grid = turmite.step(&grid);
+            let agent = Agent {
+                id: 0,
+                state,
+                heading,
+            };
+            assert_eq!(find_agents(&grid), vec![(pos, agent)]);
+        }
+        // Afterwards, the colored region keeps growing
+        let colored = |grid: &Grid<TurmiteCell>| {
+            (0..40)
+                .flat_map(|x| (0..40).map(move |y| (x, y)))
+                .filter(|&pos| grid[pos].color != 0)
+                .count()
+        };
+        let mut counts = vec![];
+        for _ in 0..3 {
+            for _ in 0..100 {
+                grid = turmite.step(&grid);
+            }
+            counts.push(colored(&grid));
+        }
+        assert!(
+            counts.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn conflicts_resolve_by_id() {
+        // Moving straight onto the same cell from the left and the right
+        let turmite = Turmite::parse("{{{0,1,0}}}").unwrap();
+        let mut grid: Grid<TurmiteCell> = Grid::generate(5, 3);
+        grid[(1, 1)].agent = agent(1, Heading::East);
+        grid[(3, 1)].agent = agent(0, Heading::West);
+        let next = turmite.step(&grid);
+        assert_eq!(
+            find_agents(&next),
+            vec![
+                ((1, 1), agent(1, Heading::East).unwrap()),
+                ((2, 1), agent(0, Heading::West).unwrap()),
+            ]
+        );
+        // An agent blocked behind a blocked agent stays as well
+        grid[(0, 1)].agent = agent(2, Heading::East);
+        let next = turmite.step(&grid);
+        assert_eq!(next[(0, 1)].agent, agent(2, Heading::East));
+        assert_eq!(next[(1, 1)].agent, agent(1, Heading::East));
+        assert_eq!(next[(2, 1)].agent, agent(0, Heading::West));
+        // The outcome does not depend on the ids' positions
+        let mut swapped: Grid<TurmiteCell> = Grid::generate(5, 3);
+        swapped[(1, 1)].agent = agent(0, Heading::East);
+        swapped[(3, 1)].agent = agent(1, Heading::West);
+        let next = turmite.step(&swapped);
+        assert_eq!(next[(2, 1)].agent, agent(0, Heading::East));
+        assert_eq!(next[(3, 1)].agent, agent(1, Heading::West));
+    }
+
+    #[test]
+    fn swapping_agents_pass_each_other() {
+        let turmite = Turmite::parse("{{{0,1,0}}}").unwrap();
+        let mut grid: Grid<TurmiteCell> = Grid::generate(4, 1);
+        grid[(1, 0)].agent = agent(0, Heading::East);
+        grid[(2, 0)].agent = agent(1, Heading::West);
+        let next = turmite.step(&grid);
+        assert_eq!(next[(2, 0)].agent, agent(0, Heading::East));
+        assert_eq!(next[(1, 0)].agent, agent(1, Heading::West));
+    }
+
+    #[test]
+    fn toggle_agent_adds_and_removes() {
+        let turmite = Turmite::default();
+        let cell = TurmiteCell {
+            color: 1,
+            agent: None,
+        };
+        let with_agent = turmite.toggle_agent(&cell, 3).unwrap();
+        assert_eq!(with_agent.agent, agent(3, Heading::North));
+        assert_eq!(with_agent.color, 1);
+        assert_eq!(turmite.toggle_agent(&with_agent, 4), Some(cell));
+    }
+
+    #[test]
+    fn presets_are_valid() {
+        for (name, table) in TURMITE_PRESETS {
+            let turmite = Turmite::parse(table).unwrap_or_else(|_| panic!("{}", name));
+            assert_eq!(&turmite.to_string(), table);
+        }
+        assert!(TURMITE_PRESETS.contains(&("Langton's Ant", LANGTONS_ANT)));
+    }
+
+    #[test]
+    fn edited_tables_stay_valid() {
+        let mut turmite = Turmite::parse(LANGTONS_ANT).unwrap();
+        turmite.resize(2, 3);
+        assert_eq!(
+            turmite.to_string(),
+            "{{{1,2,0},{0,8,0},{2,1,0}},{{0,1,1},{1,1,1},{2,1,1}}}"
+        );
+        turmite.set_transition(
+            1,
+            2,
+            Transition {
+                write: 7,
+                turn: Turn::Left,
+                next: 5,
+            },
+        );
+        assert_eq!(
+            turmite.table()[1][2],
+            Transition {
+                write: 2,
+                turn: Turn::Left,
+                next: 1,
+            }
+        );
+        // Shrinking redirects to the remaining colors and states
+        turmite.resize(1, 2);
+        assert_eq!(turmite.to_string(), "{{{1,2,0},{0,8,0}}}");
+        turmite.resize(0, 100);
+        assert_eq!(
+            (turmite.state_count(), turmite.colors()),
+            (1, MAX_TURMITE_COLORS)
+        );
+        assert_eq!(Turmite::parse(&turmite.to_string()), Ok(turmite));
+    }
+}
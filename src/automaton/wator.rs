@@ -0,0 +1,342 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    random::{CellNoise, XorShift},
+    theme::Theme,
+};
+
+use super::{move_pass, Automaton, Grid, Parameter, RuleParseError, D2};
+
+/// Largest breeding time and energy offered by the sliders.
+const MAX_COUNTER: u8 = 30;
+
+/// Wa-Tor, fish and sharks on a toroidal ocean.
+///
+/// In every generation, each fish moves to a random free neighbor. Sharks
+/// move onto a neighboring fish and eat it if there is one, otherwise they
+/// move like fish. Creatures that survived `fish_breed` or `shark_breed`
+/// generations since they last bred leave offspring on the cell they left.
+/// Sharks lose one energy per generation, gain `shark_energy` by eating and
+/// starve without energy.
+///
+/// Creatures move one after another, so they use [`move_pass`] in
+/// [`Automaton::step_grid`], shuffled by a [`CellNoise`] for the generation.
+/// [`Automaton::update`] alone leaves cells unchanged. Rules are written as
+/// `F3/S10/E3`, breeding times and energy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaTor {
+    pub fish_breed: u8,
+    pub shark_breed: u8,
+    /// Energy of a new shark, and gained for every fish eaten.
+    pub shark_energy: u8,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Creature {
+    #[default]
+    Water,
+    /// `age` counts the generations since the fish last bred.
+    Fish {
+        age: u8,
+    },
+    Shark {
+        age: u8,
+        energy: u8,
+    },
+}
+
+impl Default for WaTor {
+    fn default() -> Self {
+        Self {
+            fish_breed: 3,
+            shark_breed: 10,
+            shark_energy: 3,
+            seed: 0,
+        }
+    }
+}
+
+impl WaTor {
+    pub fn parse(rule: &str) -> Result<Self, RuleParseError> {
+        let mut sections = rule.trim().split('/');
+        let mut next = |section: char| {
+            let value = sections
+                .next()
+                .and_then(|part| part.strip_prefix(section))
+                .ok_or(RuleParseError::MissingSection(section))?;
+            match value.parse() {
+                Ok(count @ 1..=MAX_COUNTER) => Ok(count),
+                _ => Err(RuleParseError::InvalidNumber(value.to_owned())),
+            }
+        };
+        let (fish_breed, shark_breed, shark_energy) = (next('F')?, next('S')?, next('E')?);
+        if let Some(rest) = sections.next() {
+            return Err(RuleParseError::Trailing(format!("/{}", rest)));
+        }
+        Ok(Self {
+            fish_breed,
+            shark_breed,
+            shark_energy,
+            ..Self::default()
+        })
+    }
+
+    /// A random von Neumann neighbor of `(x, y)` for which `pred` holds.
+    fn random_neighbor(
+        grid: &Grid<Creature>,
+        (x, y): (isize, isize),
+        rng: &mut XorShift,
+        pred: impl Fn(&Creature) -> bool,
+    ) -> Option<(isize, isize)> {
+        let candidates: Vec<_> = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .filter_map(|(dx, dy)| grid.normalize(x + dx, y + dy))
+            .filter(|&pos| pred(&grid[pos]))
+            .collect();
+        match candidates.len() {
+            0 => None,
+            len => Some(candidates[rng.next_u32() as usize % len]),
+        }
+    }
+
+    /// Move the creature at `pos`, returning where it went.
+    fn visit(
+        &self,
+        grid: &mut Grid<Creature>,
+        pos: (isize, isize),
+        rng: &mut XorShift,
+    ) -> Option<(isize, isize)> {
+        let water = |c: &Creature| *c == Creature::Water;
+        let (moved, offspring, target) = match grid[pos] {
+            Creature::Water => return None,
+            Creature::Fish { age } => {
+                let breeds = age.saturating_add(1) >= self.fish_breed;
+                let target = Self::random_neighbor(grid, pos, rng, water);
+                let age = if breeds && target.is_some() {
+                    0
+                } else {
+                    age.saturating_add(1)
+                };
+                let offspring = breeds.then_some(Creature::Fish { age: 0 });
+                (Creature::Fish { age }, offspring, target)
+            }
+            Creature::Shark { age, energy } => {
+                let fish = |c: &Creature| matches!(c, Creature::Fish { .. });
+                let (target, energy) = match Self::random_neighbor(grid, pos, rng, fish) {
+                    Some(prey) => (Some(prey), energy.saturating_add(self.shark_energy)),
+                    None => (Self::random_neighbor(grid, pos, rng, water), energy),
+                };
+                if energy <= 1 {
+                    grid[pos] = Creature::Water;
+                    return None;
+                }
+                let breeds = age.saturating_add(1) >= self.shark_breed;
+                let age = if breeds && target.is_some() {
+                    0
+                } else {
+                    age.saturating_add(1)
+                };
+                let offspring = breeds.then_some(Creature::Shark {
+                    age: 0,
+                    energy: self.shark_energy,
+                });
+                let shark = Creature::Shark {
+                    age,
+                    energy: energy - 1,
+                };
+                (shark, offspring, target)
+            }
+        };
+        match target {
+            Some(target) => {
+                grid[target] = moved;
+                grid[pos] = offspring.unwrap_or(Creature::Water);
+                Some(target)
+            }
+            None => {
+                grid[pos] = moved;
+                Some(pos)
+            }
+        }
+    }
+}
+
+impl fmt::Display for WaTor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "F{}/S{}/E{}",
+            self.fish_breed, self.shark_breed, self.shark_energy
+        )
+    }
+}
+
+impl Automaton for WaTor {
+    type State = Creature;
+    type Dimension = D2;
+
+    fn update(&self, pos: (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        grid[pos]
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            Creature::Water => Creature::Fish { age: 0 },
+            Creature::Fish { .. } => Creature::Shark {
+                age: 0,
+                energy: self.shark_energy,
+            },
+            Creature::Shark { .. } => Creature::Water,
+        }
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            Creature::Water => theme.palette.dead.into(),
+            Creature::Fish { .. } => theme.palette.yellow.into(),
+            Creature::Shark { .. } => theme.palette.red.into(),
+        }
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr != Creature::Water
+    }
+
+    /// Mostly fish, with ages spread so that they don't all breed at once.
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % 5 {
+            0 => Creature::Shark {
+                age: (rng() % self.shark_breed as u32) as u8,
+                energy: self.shark_energy,
+            },
+            _ => Creature::Fish {
+                age: (rng() % self.fish_breed as u32) as u8,
+            },
+        }
+    }
+
+    fn rule(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn set_rule(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        *self = Self {
+            seed: self.seed,
+            ..Self::parse(rule)?
+        };
+        Ok(())
+    }
+
+    fn step_grid(&self, grid: &Grid<Self::State>, generation: u64) -> Option<Grid<Self::State>> {
+        let mut rng = CellNoise::new(self.seed).rng((0, 0), generation);
+        Some(move_pass(grid, &mut rng, |grid, pos, rng| {
+            self.visit(grid, pos, rng)
+        }))
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let slider = |name, value: u8| Parameter {
+            name,
+            value: value as f64,
+            min: 1.0,
+            max: MAX_COUNTER as f64,
+            step: 1.0,
+        };
+        vec![
+            slider("Fish breed", self.fish_breed),
+            slider("Shark breed", self.shark_breed),
+            slider("Shark energy", self.shark_energy),
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        let value = value.round().clamp(1.0, MAX_COUNTER as f64) as u8;
+        match idx {
+            0 => self.fish_breed = value,
+            1 => self.shark_breed = value,
+            2 => self.shark_energy = value,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creatures(grid: &Grid<Creature>) -> (usize, usize) {
+        let fish = grid
+            .grid
+            .iter()
+            .filter(|c| matches!(c, Creature::Fish { .. }))
+            .count();
+        let sharks = grid
+            .grid
+            .iter()
+            .filter(|c| matches!(c, Creature::Shark { .. }))
+            .count();
+        (fish, sharks)
+    }
+
+    #[test]
+    fn parse_rules() {
+        let wator = WaTor::parse("F3/S10/E3").unwrap();
+        assert_eq!(wator, WaTor::default());
+        assert_eq!(wator.to_string(), "F3/S10/E3");
+        assert_eq!(
+            WaTor::parse("F3/S10"),
+            Err(RuleParseError::MissingSection('E'))
+        );
+        assert_eq!(
+            WaTor::parse("F0/S10/E3"),
+            Err(RuleParseError::InvalidNumber("0".to_owned()))
+        );
+    }
+
+    #[test]
+    fn fish_move_and_breed() {
+        let wator = WaTor::default();
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = Creature::Fish { age: 0 };
+        let next = wator.step_grid(&grid, 0).unwrap();
+        assert_eq!(next[(2, 2)], Creature::Water);
+        assert_eq!(creatures(&next), (1, 0));
+        // The third move leaves offspring behind
+        let next = wator.step_grid(&next, 1).unwrap();
+        let next = wator.step_grid(&next, 2).unwrap();
+        assert_eq!(creatures(&next), (2, 0));
+        assert_eq!(
+            next.grid
+                .iter()
+                .filter(|c| **c == Creature::Fish { age: 0 })
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn sharks_eat_or_starve() {
+        let wator = WaTor::default();
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = Creature::Shark { age: 0, energy: 2 };
+        grid[(2, 1)] = Creature::Fish { age: 0 };
+        // The fish may move away first, so check both outcomes
+        let next = wator.step_grid(&grid, 0).unwrap();
+        match creatures(&next) {
+            (0, 1) => assert!(next.grid.contains(&Creature::Shark { age: 1, energy: 4 })),
+            (1, 1) => assert!(next.grid.contains(&Creature::Shark { age: 1, energy: 1 })),
+            counts => panic!("unexpected creatures {:?}", counts),
+        }
+        // Alone, a shark starves
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = Creature::Shark { age: 0, energy: 3 };
+        let mut generation = 0;
+        while creatures(&grid).1 > 0 {
+            grid = wator.step_grid(&grid, generation).unwrap();
+            generation += 1;
+        }
+        assert_eq!(generation, 3);
+    }
+}
@@ -0,0 +1,110 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{
+    style_life, toggle_life, weighted_sum, Automaton, Grid, LifeStates, VonNeumannNeighbors, D2,
+};
+
+/// Seeds-like automaton with weighted neighbors on the von Neumann
+/// neighborhood of range two.
+///
+/// The four direct neighbors weigh two, the eight further ones weigh one.
+/// A dead cell is born if the weighted sum of its live neighbors is a bit
+/// set in `born`, live cells always die.
+#[derive(Debug, Clone)]
+pub struct WeightedSeeds {
+    pub born: u32,
+    weights: Vec<((isize, isize), i32)>,
+}
+
+impl Default for WeightedSeeds {
+    /// Born on a sum of exactly four, e.g. two direct neighbors.
+    fn default() -> Self {
+        let weights = VonNeumannNeighbors::<2>::new()
+            .filter(|&pos| pos != (0, 0))
+            .map(|(x, y)| ((x, y), 3 - (x.abs() + y.abs()) as i32))
+            .collect();
+        Self {
+            born: 1 << 4,
+            weights,
+        }
+    }
+}
+
+impl WeightedSeeds {
+    /// Weighted number of alive cells around `(pos_x, pos_y)`.
+    fn sum(&self, pos_x: isize, pos_y: isize, grid: &Grid<LifeStates>) -> i32 {
+        weighted_sum(pos_x, pos_y, grid, &self.weights, |state| {
+            (*state == LifeStates::Alife) as i32
+        })
+    }
+}
+
+impl Automaton for WeightedSeeds {
+    type State = LifeStates;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        if grid[(pos_x, pos_y)] == LifeStates::Alife {
+            return LifeStates::Dead;
+        }
+        if self.born >> self.sum(pos_x, pos_y, grid) & 1 == 1 {
+            LifeStates::Alife
+        } else {
+            LifeStates::Dead
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(self.sum(pos_x, pos_y, grid))
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        toggle_life(curr)
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        style_life(curr, theme)
+    }
+
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == LifeStates::Alife
+    }
+
+    fn sparse_radius(&self) -> Option<usize> {
+        Some(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{tests::step, EdgeBehavior};
+
+    #[test]
+    fn weights_favor_direct_neighbors() {
+        let seeds = WeightedSeeds::default();
+        assert_eq!(seeds.weights.len(), 12);
+        assert_eq!(seeds.weights.iter().map(|(_, w)| w).sum::<i32>(), 4 * 2 + 8);
+    }
+
+    #[test]
+    fn pair_spawns_between_and_dies() {
+        let mut grid = Grid::with_edge_behavior(5, 5, EdgeBehavior::Dead);
+        grid[(1, 2)] = LifeStates::Alife;
+        grid[(3, 2)] = LifeStates::Alife;
+        let next = step(&WeightedSeeds::default(), &grid);
+        // The cell between has two direct live neighbors, a sum of four
+        assert_eq!(next[(2, 2)], LifeStates::Alife);
+        // Cells diagonal to both only sum up to two
+        assert_eq!(next[(2, 1)], LifeStates::Dead);
+        assert_eq!(next[(0, 2)], LifeStates::Dead);
+        assert_eq!(next[(1, 2)], LifeStates::Dead);
+        assert_eq!(next[(3, 2)], LifeStates::Dead);
+    }
+}
@@ -0,0 +1,133 @@
+use std::borrow::Cow;
+
+use crate::theme::Theme;
+
+use super::{count_moore_neighbors, Automaton, Grid, D2};
+
+/// Wireworld, a four-state automaton for simulating electronic circuits.
+///
+/// Electron heads become tails, tails become conductors again and a
+/// conductor becomes a head if one or two of its Moore neighbors are heads.
+#[derive(Debug, Clone, Default)]
+pub struct Wireworld;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WireState {
+    #[default]
+    Empty,
+    ElectronHead,
+    ElectronTail,
+    Conductor,
+}
+
+fn head_neighbors(pos_x: isize, pos_y: isize, grid: &Grid<WireState>) -> u8 {
+    count_moore_neighbors(pos_x, pos_y, grid, |s| *s == WireState::ElectronHead)
+}
+
+impl Automaton for Wireworld {
+    type State = WireState;
+    type Dimension = D2;
+
+    fn update(&self, (pos_x, pos_y): (isize, isize), grid: &Grid<Self::State>) -> Self::State {
+        match grid[(pos_x, pos_y)] {
+            WireState::Empty => WireState::Empty,
+            WireState::ElectronHead => WireState::ElectronTail,
+            WireState::ElectronTail => WireState::Conductor,
+            WireState::Conductor => match head_neighbors(pos_x, pos_y, grid) {
+                1..=2 => WireState::ElectronHead,
+                _ => WireState::Conductor,
+            },
+        }
+    }
+
+    fn neighbor_count(
+        &self,
+        (pos_x, pos_y): (isize, isize),
+        grid: &Grid<Self::State>,
+    ) -> Option<i32> {
+        Some(head_neighbors(pos_x, pos_y, grid) as i32)
+    }
+
+    fn toggle(&self, curr: Self::State) -> Self::State {
+        match curr {
+            WireState::Empty => WireState::Conductor,
+            WireState::Conductor => WireState::ElectronHead,
+            WireState::ElectronHead => WireState::ElectronTail,
+            WireState::ElectronTail => WireState::Empty,
+        }
+    }
+
+    /// Counts the electrons, i.e. the electron heads.
+    fn is_alive(&self, curr: &Self::State) -> bool {
+        *curr == WireState::ElectronHead
+    }
+
+    fn random_state(&self, rng: &mut impl FnMut() -> u32) -> Self::State {
+        match rng() % 3 {
+            0 => WireState::ElectronHead,
+            1 => WireState::ElectronTail,
+            _ => WireState::Conductor,
+        }
+    }
+
+    fn style(&self, curr: &Self::State, theme: &Theme) -> Cow<'static, str> {
+        match curr {
+            WireState::Empty => theme.palette.dead.into(),
+            WireState::ElectronHead => theme.palette.blue.into(),
+            WireState::ElectronTail => theme.palette.red.into(),
+            WireState::Conductor => theme.palette.yellow.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{tests::step, Life, LifeStates};
+
+    /// A conductor at (2, 2) surrounded by `heads` electron heads.
+    fn conductor_with_heads(heads: usize) -> Grid<WireState> {
+        let mut grid = Grid::generate(5, 5);
+        grid[(2, 2)] = WireState::Conductor;
+        for &pos in [(1, 1), (2, 1), (3, 1)].iter().take(heads) {
+            grid[pos] = WireState::ElectronHead;
+        }
+        grid
+    }
+
+    #[test]
+    fn conductor_fires_with_one_or_two_heads() {
+        for heads in 1..=2 {
+            let next = step(&Wireworld, &conductor_with_heads(heads));
+            assert_eq!(next[(2, 2)], WireState::ElectronHead);
+        }
+        let next = step(&Wireworld, &conductor_with_heads(3));
+        assert_eq!(next[(2, 2)], WireState::Conductor);
+        let next = step(&Wireworld, &conductor_with_heads(0));
+        assert_eq!(next[(2, 2)], WireState::Conductor);
+    }
+
+    #[test]
+    fn electrons_decay() {
+        let next = step(&Wireworld, &conductor_with_heads(1));
+        assert_eq!(next[(1, 1)], WireState::ElectronTail);
+        let next = step(&Wireworld, &next);
+        assert_eq!(next[(1, 1)], WireState::Conductor);
+        assert_eq!(next[(0, 0)], WireState::Empty);
+    }
+
+    #[test]
+    fn all_states_can_be_painted() {
+        assert_eq!(
+            Wireworld.states(),
+            [
+                WireState::Empty,
+                WireState::Conductor,
+                WireState::ElectronHead,
+                WireState::ElectronTail
+            ]
+        );
+        assert_eq!(Life.states(), [LifeStates::Dead, LifeStates::Alife]);
+    }
+}
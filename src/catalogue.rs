@@ -0,0 +1,118 @@
+//! Browsing RLE patterns of an online catalogue.
+//!
+//! A catalogue is an index page at a configurable URL, like a directory
+//! listing of a web server or a plain list, linking to the `.rle` files of
+//! the patterns. Links are resolved relative to the index, so the files may
+//! live next to it or anywhere else.
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{Response, Url};
+
+use crate::describe_error;
+
+/// Index of the pattern collection of the LifeWiki.
+pub const DEFAULT_CATALOGUE_URL: &str = "https://conwaylife.com/patterns/";
+/// Largest number of search results shown at once.
+pub const MAX_RESULTS: usize = 50;
+
+/// Links to RLE files in the index page `listing`, in order of first appearance.
+///
+/// Links are taken from the `href` attributes of an HTML page, or from the
+/// words of a plain list if there are none.
+pub fn entries(listing: &str) -> Vec<String> {
+    let hrefs: Vec<&str> = listing
+        .split("href=")
+        .skip(1)
+        .filter_map(|rest| match rest.chars().next()? {
+            quote @ ('"' | '\'') => rest[1..].split(quote).next(),
+            _ => rest.split(|c: char| c.is_whitespace() || c == '>').next(),
+        })
+        .collect();
+    let links = if hrefs.is_empty() {
+        listing.split_whitespace().collect()
+    } else {
+        hrefs
+    };
+    let mut entries: Vec<String> = Vec::new();
+    for link in links {
+        if link.to_ascii_lowercase().ends_with(".rle") && !entries.iter().any(|e| e == link) {
+            entries.push(link.to_owned());
+        }
+    }
+    entries
+}
+
+/// The name of the pattern linked by `entry`, its file name without extension.
+pub fn name(entry: &str) -> &str {
+    let file = entry.rsplit('/').next().unwrap_or(entry);
+    if file.to_ascii_lowercase().ends_with(".rle") {
+        &file[..file.len() - ".rle".len()]
+    } else {
+        file
+    }
+}
+
+/// The entries whose name contains `query`, ignoring case, at most [`MAX_RESULTS`].
+pub fn search<'a>(entries: &'a [String], query: &str) -> impl Iterator<Item = &'a String> {
+    let query = query.trim().to_lowercase();
+    entries
+        .iter()
+        .filter(move |entry| name(entry).to_lowercase().contains(&query))
+        .take(MAX_RESULTS)
+}
+
+/// The URL of `entry`, relative to the index at `base`.
+pub fn resolve(entry: &str, base: &str) -> Result<String, String> {
+    Url::new_with_base(entry, base)
+        .map(|url| url.href())
+        .map_err(describe_error)
+}
+
+/// Download the text at `url`.
+pub fn fetch_text(url: String, done: impl FnOnce(Result<String, String>) + 'static) {
+    spawn_local(async move { done(fetch(&url).await.map_err(describe_error)) })
+}
+
+async fn fetch(url: &str) -> Result<String, JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let response: Response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        let status = format!("{} returned {}", url, response.status());
+        return Err(status.into());
+    }
+    let text = JsFuture::from(response.text()?).await?;
+    text.as_string()
+        .ok_or_else(|| format!("{} returned no text", url).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_found_in_listings() {
+        let html = r#"<a href="../">Parent</a>
+            <a href="glider.rle">glider.rle</a> 2021-01-01
+            <a href='/patterns/Gosper-gun.RLE'>Gosper-gun.RLE</a>
+            <a href=blinker.rle>blinker.rle</a>
+            <a href="readme.txt">readme.txt</a>"#;
+        assert_eq!(
+            entries(html),
+            ["glider.rle", "/patterns/Gosper-gun.RLE", "blinker.rle"]
+        );
+        assert_eq!(entries("a.rle\nb.rle\na.rle\n"), ["a.rle", "b.rle"]);
+        assert!(entries("").is_empty());
+    }
+
+    #[test]
+    fn search_matches_names() {
+        let entries = entries("glider.rle\n/patterns/Gosper-gun.RLE\nblinker.rle\n");
+        assert_eq!(name(&entries[1]), "Gosper-gun");
+        let found: Vec<_> = search(&entries, " GLI ").collect();
+        assert_eq!(found, ["glider.rle"]);
+        assert_eq!(search(&entries, "").count(), 3);
+        assert_eq!(search(&entries, "patterns").count(), 0);
+    }
+}
@@ -0,0 +1,41 @@
+//! Copying and pasting text with the asynchronous Clipboard API.
+//!
+//! Browsers only grant access to the clipboard in response to user input,
+//! like the keyboard shortcuts calling these functions.
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::Clipboard;
+
+use crate::describe_error;
+
+/// Replace the contents of the clipboard with `text`.
+pub fn write_text(text: String, done: impl FnOnce(Result<(), String>) + 'static) {
+    let clipboard = match clipboard() {
+        Ok(clipboard) => clipboard,
+        Err(e) => return done(Err(e)),
+    };
+    spawn_local(async move {
+        let result = JsFuture::from(clipboard.write_text(&text)).await;
+        done(result.map(drop).map_err(describe_error))
+    })
+}
+
+/// Read the text in the clipboard.
+pub fn read_text(done: impl FnOnce(Result<String, String>) + 'static) {
+    let clipboard = match clipboard() {
+        Ok(clipboard) => clipboard,
+        Err(e) => return done(Err(e)),
+    };
+    spawn_local(async move {
+        let result = JsFuture::from(clipboard.read_text()).await;
+        done(result.map_err(describe_error).and_then(|text| {
+            text.as_string()
+                .ok_or_else(|| "the clipboard contains no text".to_owned())
+        }))
+    })
+}
+
+fn clipboard() -> Result<Clipboard, String> {
+    web_sys::window()
+        .map(|window| window.navigator().clipboard())
+        .ok_or_else(|| "No window".to_owned())
+}
@@ -0,0 +1,568 @@
+use gif::{Encoder, Frame, Repeat};
+use gloo_timers::callback::Timeout;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    Blob, BlobEvent, BlobPropertyBag, CanvasRenderingContext2d, HtmlAnchorElement,
+    HtmlCanvasElement, MediaRecorder, MediaRecorderOptions, Url,
+};
+use weblog::console_log;
+
+use crate::{
+    automaton::{Automaton, Grid, Life, LifeStates},
+    supervisor::{RenderOptions, Supervisor},
+};
+
+/// Pixels per cell in exported images without a chosen width.
+pub const EXPORT_CELL_PX: u32 = 10;
+/// Largest width or height of exported images, browsers refuse larger canvases.
+pub const MAX_IMAGE_PX: u32 = 8192;
+
+/// Default number of generations and pixels per cell of GIF recordings.
+pub const DEFAULT_RECORD_FRAMES: usize = 50;
+pub const DEFAULT_RECORD_CELL_PX: u32 = 4;
+/// Largest number of generations in a GIF recording.
+pub const MAX_RECORD_FRAMES: usize = 1000;
+/// Range of pixels per cell in GIF recordings.
+pub const MIN_RECORD_CELL_PX: u32 = 1;
+pub const MAX_RECORD_CELL_PX: u32 = 20;
+/// Largest size of all RGBA frames of a GIF recording together.
+pub const MAX_RECORD_BYTES: usize = 1 << 29;
+
+/// File format of recordings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordFormat {
+    #[default]
+    Gif,
+    /// A video recorded in real time by the browser's `MediaRecorder`.
+    WebM,
+}
+
+impl RecordFormat {
+    pub const ALL: [RecordFormat; 2] = [RecordFormat::Gif, RecordFormat::WebM];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "GIF",
+            RecordFormat::WebM => "WebM",
+        }
+    }
+}
+
+/// MIME type of WebM recordings.
+const WEBM_MIME: &str = "video/webm";
+
+/// Generations collected while the automaton runs, downloaded as an animation.
+///
+/// Frames show the whole grid, rendered offscreen, so the recording does not
+/// depend on the viewport. GIF frames are RGBA pixel data from
+/// [`capture_frame`], WebM frames are drawn to a canvas whose stream is
+/// recorded by the browser.
+pub struct Recording {
+    width: u32,
+    height: u32,
+    cell_px: u32,
+    frame_count: usize,
+    len: usize,
+    sink: Sink,
+}
+
+enum Sink {
+    Gif(Vec<Vec<u8>>),
+    WebM(Video),
+}
+
+/// A canvas recorded by a `MediaRecorder`, collecting the encoded chunks.
+struct Video {
+    ctx: CanvasRenderingContext2d,
+    recorder: MediaRecorder,
+    chunks: Array,
+    /// Called by the recorder, must live until it stopped.
+    on_data: Closure<dyn FnMut(BlobEvent)>,
+}
+
+impl Recording {
+    /// Record `frame_count` frames as a GIF showing the grid of `supervisor` at `cell_px` pixels per cell.
+    ///
+    /// Fails if the frames are too large for a GIF or would take more than
+    /// [`MAX_RECORD_BYTES`] together.
+    pub fn new<A: Automaton>(
+        supervisor: &Supervisor<A>,
+        frame_count: usize,
+        cell_px: u32,
+    ) -> Result<Self, String> {
+        let (width, height) = image_size(supervisor.world_size(), supervisor.cell_width(), cell_px);
+        if u16::try_from(width).is_err() || u16::try_from(height).is_err() {
+            return Err(format!(
+                "{}×{} pixels are too large for a GIF",
+                width, height
+            ));
+        }
+        let bytes = frame_count
+            .checked_mul(width as usize * height as usize * 4)
+            .filter(|&bytes| bytes <= MAX_RECORD_BYTES);
+        if bytes.is_none() {
+            return Err(format!(
+                "{} frames of {}×{} pixels take too much memory",
+                frame_count, width, height
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            cell_px,
+            frame_count,
+            len: 0,
+            sink: Sink::Gif(Vec::with_capacity(frame_count)),
+        })
+    }
+
+    /// Like [`Recording::new`], but record a WebM video.
+    ///
+    /// Fails if the browser can't record WebM videos.
+    pub fn webm<A: Automaton>(
+        supervisor: &Supervisor<A>,
+        frame_count: usize,
+        cell_px: u32,
+    ) -> Result<Self, JsValue> {
+        if !MediaRecorder::is_type_supported(WEBM_MIME) {
+            return Err("This browser can't record WebM videos".into());
+        }
+        let (width, height) = image_size(supervisor.world_size(), supervisor.cell_width(), cell_px);
+        let canvas = create_canvas(width, height)?;
+        let ctx: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or("Canvas has no 2d context")?
+            .dyn_into()?;
+        let options = MediaRecorderOptions::new();
+        options.set_mime_type(WEBM_MIME);
+        let recorder = MediaRecorder::new_with_media_stream_and_media_recorder_options(
+            &canvas.capture_stream()?,
+            &options,
+        )?;
+        let chunks = Array::new();
+        let on_data = {
+            let chunks = chunks.clone();
+            Closure::wrap(Box::new(move |ev: BlobEvent| {
+                if let Some(data) = ev.data() {
+                    chunks.push(&data);
+                }
+            }) as Box<dyn FnMut(BlobEvent)>)
+        };
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        recorder.start()?;
+        Ok(Self {
+            width,
+            height,
+            cell_px,
+            frame_count,
+            len: 0,
+            sink: Sink::WebM(Video {
+                ctx,
+                recorder,
+                chunks,
+                on_data,
+            }),
+        })
+    }
+
+    /// Width and height of every frame in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Number of frames recorded so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.len >= self.frame_count
+    }
+
+    /// Add the current grid of `supervisor` as the next frame.
+    pub fn capture<A: Automaton>(
+        &mut self,
+        supervisor: &Supervisor<A>,
+        options: &RenderOptions,
+    ) -> Result<(), String> {
+        match &self.sink {
+            Sink::Gif(_) => {
+                let rgba = capture_frame(supervisor, options, self.cell_px)
+                    .map_err(|e| format!("{:?}", e))?;
+                self.push(rgba)
+            }
+            Sink::WebM(video) => {
+                let size = image_size(
+                    supervisor.world_size(),
+                    supervisor.cell_width(),
+                    self.cell_px,
+                );
+                if size != self.dimensions() {
+                    return Err("the grid was resized".to_owned());
+                }
+                if !self.is_complete() {
+                    fill_background(
+                        &video.ctx,
+                        options.theme.background,
+                        self.width,
+                        self.height,
+                    );
+                    supervisor.draw_unscaled(&video.ctx, options, self.cell_px as f64);
+                    self.len += 1;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Append a frame of RGBA pixel data to a GIF recording.
+    ///
+    /// Fails if the frame does not match [`Recording::dimensions`], e.g.
+    /// because the grid was resized during the recording.
+    pub fn push(&mut self, rgba: Vec<u8>) -> Result<(), String> {
+        let (width, height) = self.dimensions();
+        let expected = width as usize * height as usize * 4;
+        if rgba.len() != expected {
+            return Err(format!(
+                "frame has {} bytes instead of {}, was the grid resized?",
+                rgba.len(),
+                expected
+            ));
+        }
+        let complete = self.is_complete();
+        match &mut self.sink {
+            Sink::Gif(frames) if !complete => {
+                frames.push(rgba);
+                self.len += 1;
+                Ok(())
+            }
+            Sink::Gif(_) => Ok(()),
+            Sink::WebM(_) => Err("a video has no RGBA frames".to_owned()),
+        }
+    }
+
+    /// Stop recording and download the frames so far, each shown for `delay_ms`.
+    ///
+    /// Videos are recorded in real time, so the download starts once the
+    /// last frame was shown for `delay_ms`.
+    pub fn finish(self, delay_ms: u32) -> Result<(), String> {
+        match self.sink {
+            Sink::Gif(_) => {
+                let bytes = self.encode(delay_ms)?;
+                download_bytes(&bytes, "image/gif", "cellular-automaton.gif")
+                    .map_err(|e| format!("{:?}", e))
+            }
+            Sink::WebM(video) => {
+                video.finish(delay_ms);
+                Ok(())
+            }
+        }
+    }
+
+    /// Encode the frames of a GIF recording as a looping GIF, showing each frame for `delay_ms`.
+    pub fn encode(self, delay_ms: u32) -> Result<Vec<u8>, String> {
+        let too_large = |_| {
+            format!(
+                "{}×{} pixels are too large for a GIF",
+                self.width, self.height
+            )
+        };
+        let width = u16::try_from(self.width).map_err(too_large)?;
+        let height = u16::try_from(self.height).map_err(too_large)?;
+        let frames = match self.sink {
+            Sink::Gif(frames) => frames,
+            Sink::WebM(_) => return Err("a video can't be encoded as GIF".to_owned()),
+        };
+        // GIF delays are in hundredths of a second, browsers ignore the smallest ones
+        let delay = (delay_ms / 10).clamp(2, u16::MAX as u32) as u16;
+        let mut bytes = Vec::new();
+        {
+            let mut encoder =
+                Encoder::new(&mut bytes, width, height, &[]).map_err(|e| e.to_string())?;
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(|e| e.to_string())?;
+            for mut rgba in frames {
+                let mut frame = Frame::from_rgba_speed(width, height, &mut rgba, 10);
+                frame.delay = delay;
+                encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl Video {
+    /// Stop the recorder after `delay_ms` and download the video once it stopped.
+    fn finish(self, delay_ms: u32) {
+        let Video {
+            recorder,
+            chunks,
+            on_data,
+            ..
+        } = self;
+        let on_stop = Closure::once_into_js(move || {
+            // The last chunk arrives before the recorder stops
+            drop(on_data);
+            let properties = BlobPropertyBag::new();
+            properties.set_type(WEBM_MIME);
+            let video = Blob::new_with_blob_sequence_and_options(&chunks, &properties)
+                .and_then(|blob| download_blob(&blob, "cellular-automaton.webm"));
+            if let Err(e) = video {
+                console_log!("Failed to download the video", e);
+            }
+        });
+        recorder.set_onstop(Some(on_stop.unchecked_ref()));
+        Timeout::new(delay_ms, move || {
+            if let Err(e) = recorder.stop() {
+                console_log!("Failed to stop the video recording", e);
+            }
+        })
+        .forget();
+    }
+}
+
+/// Download the whole grid as a PNG image.
+///
+/// The grid is rendered to an offscreen canvas, so the image does not depend
+/// on the current pan and zoom and is never clipped by the viewport. Its size
+/// is chosen by [`export_size`].
+pub fn export_png<A: Automaton>(
+    supervisor: &Supervisor<A>,
+    options: &RenderOptions,
+    image_width: Option<u32>,
+) -> Result<(), JsValue> {
+    let ((width, height), cell_px) = export_size(
+        supervisor.world_size(),
+        supervisor.cell_width(),
+        image_width,
+    );
+    let canvas = create_canvas(width, height)?;
+    let ctx: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or("Canvas has no 2d context")?
+        .dyn_into()?;
+    fill_background(&ctx, options.theme.background, width, height);
+    supervisor.draw_unscaled(&ctx, options, cell_px);
+    download(
+        &canvas.to_data_url_with_type("image/png")?,
+        "cellular-automaton.png",
+    )
+}
+
+/// Download the whole grid as an SVG image, see [`Supervisor::to_svg`].
+///
+/// The image has the same size as [`export_png`] would produce.
+pub fn export_svg<A: Automaton>(
+    supervisor: &Supervisor<A>,
+    options: &RenderOptions,
+    image_width: Option<u32>,
+) -> Result<(), JsValue> {
+    let (size, _) = export_size(
+        supervisor.world_size(),
+        supervisor.cell_width(),
+        image_width,
+    );
+    let svg = supervisor.to_svg(options, size);
+    download_bytes(svg.as_bytes(), "image/svg+xml", "cellular-automaton.svg")
+}
+
+/// A canvas showing `cells` at most `max_px` pixels wide and high, with cells
+/// no larger than [`EXPORT_CELL_PX`].
+pub fn thumbnail(
+    cells: &Grid<LifeStates>,
+    options: &RenderOptions,
+    max_px: u32,
+) -> Result<HtmlCanvasElement, JsValue> {
+    let mut supervisor = Supervisor::new(Life, cells.width(), cells.height());
+    supervisor.load_alive(cells);
+    let side = cells.width().max(cells.height()) as f64;
+    let cell_px = (max_px as f64 / side).min(EXPORT_CELL_PX as f64);
+    let width = (cells.width() as f64 * cell_px).ceil() as u32;
+    let height = (cells.height() as f64 * cell_px).ceil() as u32;
+    let canvas = create_canvas(width.max(1), height.max(1))?;
+    let ctx: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or("Canvas has no 2d context")?
+        .dyn_into()?;
+    fill_background(&ctx, options.theme.background, width, height);
+    supervisor.draw_unscaled(&ctx, options, cell_px);
+    Ok(canvas)
+}
+
+/// Render the whole grid offscreen at `cell_px` pixels per cell and return its RGBA pixel data.
+pub fn capture_frame<A: Automaton>(
+    supervisor: &Supervisor<A>,
+    options: &RenderOptions,
+    cell_px: u32,
+) -> Result<Vec<u8>, JsValue> {
+    let (width, height) = image_size(supervisor.world_size(), supervisor.cell_width(), cell_px);
+    let canvas = create_canvas(width, height)?;
+    let ctx: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or("Canvas has no 2d context")?
+        .dyn_into()?;
+    fill_background(&ctx, options.theme.background, width, height);
+    supervisor.draw_unscaled(&ctx, options, cell_px as f64);
+    let image = ctx.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+    Ok(image.data().0)
+}
+
+/// Let the browser download `bytes` of type `mime` as `filename`.
+pub fn download_bytes(bytes: &[u8], mime: &str, filename: &str) -> Result<(), JsValue> {
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let properties = BlobPropertyBag::new();
+    properties.set_type(mime);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &properties)?;
+    download_blob(&blob, filename)
+}
+
+/// Let the browser download `blob` as `filename`.
+fn download_blob(blob: &Blob, filename: &str) -> Result<(), JsValue> {
+    let url = Url::create_object_url_with_blob(blob)?;
+    let result = download(&url, filename);
+    Url::revoke_object_url(&url)?;
+    result
+}
+
+/// Size in pixels of an image showing a grid covering `world_size`, see [`Supervisor::world_size`].
+pub fn image_size((width, height): (f64, f64), cell_width: usize, cell_px: u32) -> (u32, u32) {
+    let scale = cell_px as f64 / cell_width as f64;
+    (
+        (width * scale).ceil() as u32,
+        (height * scale).ceil() as u32,
+    )
+}
+
+/// Size in pixels and pixels per cell of an exported image.
+///
+/// Without an `image_width`, cells are [`EXPORT_CELL_PX`] wide. Otherwise
+/// the image is scaled to the given width, keeping the aspect ratio. Neither
+/// side exceeds [`MAX_IMAGE_PX`].
+pub fn export_size(
+    world_size: (f64, f64),
+    cell_width: usize,
+    image_width: Option<u32>,
+) -> ((u32, u32), f64) {
+    let (width, height) = world_size;
+    let cell_px = match image_width {
+        Some(image_width) => image_width.max(1) as f64 * cell_width as f64 / width,
+        None => EXPORT_CELL_PX as f64,
+    };
+    let largest = MAX_IMAGE_PX as f64 * cell_width as f64 / width.max(height);
+    let cell_px = cell_px.min(largest);
+    let scale = cell_px / cell_width as f64;
+    let size = (
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    );
+    (size, cell_px)
+}
+
+fn fill_background(ctx: &CanvasRenderingContext2d, color: &str, width: u32, height: u32) {
+    ctx.set_fill_style_str(color);
+    ctx.fill_rect(0.0, 0.0, width as f64, height as f64);
+}
+
+fn create_canvas(width: u32, height: u32) -> Result<HtmlCanvasElement, JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("No document")?;
+    let canvas: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    Ok(canvas)
+}
+
+/// Let the browser download `url` as `filename`.
+pub fn download(url: &str, filename: &str) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("No document")?;
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(url);
+    anchor.set_download(filename);
+    anchor.click();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_covers_whole_grid() {
+        assert_eq!(image_size((1000.0, 1500.0), 50, EXPORT_CELL_PX), (200, 300));
+        assert_eq!(image_size((80.0, 120.0), 4, EXPORT_CELL_PX), (200, 300));
+    }
+
+    #[test]
+    fn export_scales_to_chosen_width() {
+        // 20 x 10 cells, 5 units wide
+        let world = (100.0, 50.0);
+        assert_eq!(export_size(world, 5, None), ((200, 100), 10.0));
+        assert_eq!(export_size(world, 5, Some(1000)), ((1000, 500), 50.0));
+        assert_eq!(export_size(world, 5, Some(30)), ((30, 15), 1.5));
+        let ((width, height), _) = export_size((50.0, 100.0), 5, Some(MAX_IMAGE_PX));
+        assert_eq!((width, height), (MAX_IMAGE_PX / 2, MAX_IMAGE_PX));
+    }
+
+    #[test]
+    fn recording_collects_frames_of_grid_size() {
+        let supervisor = Supervisor::new(Life, 6, 4);
+        let mut recording = Recording::new(&supervisor, 3, 2).unwrap();
+        assert_eq!(recording.dimensions(), (12, 8));
+        let frame = vec![0; 12 * 8 * 4];
+        for len in 1..=3 {
+            assert!(!recording.is_complete());
+            recording.push(frame.clone()).unwrap();
+            assert_eq!(recording.len(), len);
+        }
+        assert!(recording.is_complete());
+        // Additional frames are ignored
+        recording.push(frame).unwrap();
+        assert_eq!(recording.len(), 3);
+    }
+
+    #[test]
+    fn recording_rejects_frames_of_other_size() {
+        let supervisor = Supervisor::new(Life, 6, 4);
+        let mut recording = Recording::new(&supervisor, 3, 2).unwrap();
+        assert!(recording.push(vec![0; 14 * 8 * 4]).is_err());
+        assert_eq!(recording.len(), 0);
+    }
+
+    #[test]
+    fn recording_encodes_gif() {
+        let supervisor = Supervisor::new(Life, 2, 2);
+        let mut recording = Recording::new(&supervisor, 2, 1).unwrap();
+        recording.push(vec![255; 16]).unwrap();
+        recording.push(vec![0; 16]).unwrap();
+        assert!(recording.encode(100).unwrap().starts_with(b"GIF89a"));
+    }
+
+    #[test]
+    fn recording_encodes_early_stop() {
+        let supervisor = Supervisor::new(Life, 2, 2);
+        let mut recording = Recording::new(&supervisor, 10, 1).unwrap();
+        recording.push(vec![255; 16]).unwrap();
+        assert!(!recording.is_complete());
+        assert!(recording.encode(100).unwrap().starts_with(b"GIF89a"));
+    }
+
+    #[test]
+    fn recording_rejects_oversized_frames() {
+        let supervisor = Supervisor::new(Life, 6, 4);
+        // Six cells of 20000 pixels are too wide for a GIF
+        assert!(Recording::new(&supervisor, 1, 20_000).is_err());
+        assert!(Recording::new(&supervisor, usize::MAX, 1).is_err());
+        let frames = MAX_RECORD_BYTES / (6 * 64 * 4 * 64 * 4);
+        assert!(Recording::new(&supervisor, frames, 64).is_ok());
+        assert!(Recording::new(&supervisor, frames + 1, 64).is_err());
+    }
+}
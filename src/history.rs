@@ -0,0 +1,148 @@
+use std::{collections::VecDeque, mem};
+
+use crate::automaton::Grid;
+
+/// Maximum number of grids kept in the past of a [`History`].
+pub const HISTORY_CAPACITY: usize = 64;
+/// Maximum number of cells kept in the past of a [`History`].
+pub const HISTORY_MAX_CELLS: usize = 1 << 22;
+
+/// Timeline of grids, allowing to move back and forth between them.
+///
+/// Each grid is stored together with its generation number.
+///
+/// Grids are stored as full copies, so at most [`HISTORY_CAPACITY`] past
+/// grids are kept and, for large grids, only as many as fit into
+/// [`HISTORY_MAX_CELLS`] cells, but always at least one. Older grids are
+/// dropped.
+#[derive(Debug, Clone)]
+pub struct History<State> {
+    past: VecDeque<(Grid<State>, u64)>,
+    future: Vec<(Grid<State>, u64)>,
+}
+
+impl<State> Default for History<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State> History<State> {
+    pub fn new() -> Self {
+        Self {
+            past: VecDeque::new(),
+            future: Vec::new(),
+        }
+    }
+
+    /// Record `grid` at `generation` as the latest past state.
+    pub fn push(&mut self, grid: Grid<State>, generation: u64) {
+        let cells = (grid.width() * grid.height()).max(1);
+        let capacity = HISTORY_CAPACITY.min(HISTORY_MAX_CELLS / cells).max(1);
+        while self.past.len() >= capacity {
+            self.past.pop_front();
+        }
+        self.past.push_back((grid, generation));
+    }
+
+    /// Forget everything.
+    pub fn clear(&mut self) {
+        self.past.clear();
+        self.future.clear();
+    }
+
+    /// Forget everything after the current state.
+    pub fn clear_future(&mut self) {
+        self.future.clear();
+    }
+
+    /// Replace `grid` and `generation` with the previous state.
+    ///
+    /// The current state is remembered, so that [`History::forward`] can
+    /// return to it. Returns `false` if there is no previous state.
+    pub fn back(&mut self, grid: &mut Grid<State>, generation: &mut u64) -> bool {
+        match self.past.pop_back() {
+            Some((prev, prev_generation)) => {
+                let curr = mem::replace(grid, prev);
+                let curr_generation = mem::replace(generation, prev_generation);
+                self.future.push((curr, curr_generation));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace `grid` and `generation` with the next state.
+    ///
+    /// Returns `false` if there is no next state.
+    pub fn forward(&mut self, grid: &mut Grid<State>, generation: &mut u64) -> bool {
+        match self.future.pop() {
+            Some((next, next_generation)) => {
+                let prev = mem::replace(grid, next);
+                let prev_generation = mem::replace(generation, next_generation);
+                self.push(prev, prev_generation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.future.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(width: usize) -> Grid<u8> {
+        Grid::generate(width, 1)
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = History::new();
+        assert!(!history.can_go_back());
+        history.push(grid(1), 1);
+        history.push(grid(2), 2);
+        let mut current = grid(3);
+        let mut generation = 3;
+        assert!(history.back(&mut current, &mut generation));
+        assert_eq!((current.width(), generation), (2, 2));
+        assert!(history.can_go_forward());
+        assert!(history.forward(&mut current, &mut generation));
+        assert_eq!((current.width(), generation), (3, 3));
+        assert!(!history.can_go_forward());
+        assert!(!history.forward(&mut current, &mut generation));
+    }
+
+    #[test]
+    fn capacity_is_enforced() {
+        let mut history = History::new();
+        for width in 0..HISTORY_CAPACITY + 10 {
+            history.push(grid(width), 0);
+        }
+        let mut current = grid(0);
+        let mut steps = 0;
+        while history.back(&mut current, &mut 0) {
+            steps += 1;
+        }
+        assert_eq!(steps, HISTORY_CAPACITY);
+        assert_eq!(current.width(), 10);
+    }
+
+    #[test]
+    fn large_grids_are_limited_by_cells() {
+        let mut history = History::new();
+        let side = 1 << 10;
+        for _ in 0..8 {
+            history.push(Grid::<u8>::generate(side, side), 0);
+        }
+        assert_eq!(history.past.len(), HISTORY_MAX_CELLS / (side * side));
+    }
+}
@@ -0,0 +1,22 @@
+//! Simulation core of the cellular automata: grids, rules and the
+//! [`Supervisor`](supervisor::Supervisor) stepping them.
+//!
+//! Drawing onto a canvas requires the default `web` feature, everything
+//! else also compiles and runs on the host without it.
+
+pub mod animation;
+#[cfg(feature = "web")]
+pub mod api;
+pub mod automaton;
+pub mod history;
+pub mod macrocell;
+pub mod pattern;
+pub mod random;
+pub mod stats;
+pub mod supervisor;
+pub mod theme;
+
+/// Width of a cell in screen pixels at scale one, see [`Supervisor::set_cell_width`].
+///
+/// [`Supervisor::set_cell_width`]: supervisor::Supervisor::set_cell_width
+pub const DEFAULT_CELL_WIDTH: usize = 50;
@@ -0,0 +1,295 @@
+//! A library of named patterns saved in the browser's IndexedDB.
+//!
+//! Every automaton has its own patterns, stored in the object store
+//! [`STORE`] under the key `[automaton, name]` as text: the rule of the
+//! automaton on the first line, empty if it has none, followed by the grid
+//! with all cell states as a JSON snapshot, see [`Grid::to_json`].
+//!
+//! Older versions stored patterns for all automata under just their name:
+//! the grid width and height separated by a space, followed by the live
+//! cells in Life 1.06 format on the next lines. These are still listed and
+//! read for the default automaton, see [`Loaded::Legacy`].
+//!
+//! IndexedDB is asynchronous, so all operations report their result to a
+//! callback, which usually sends a message to the model.
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::Array;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+use cellular_automaton::{
+    automaton::{Grid, LifeStates, SnapshotError},
+    pattern::{life106_cells, place_centered},
+};
+
+use crate::{describe_error, DEFAULT_AUTOMATON};
+
+pub const DB_NAME: &str = "cellular-automaton";
+const DB_VERSION: u32 = 1;
+pub const STORE: &str = "patterns";
+
+/// A pattern read from the library.
+#[derive(Debug, PartialEq)]
+pub enum Loaded<State> {
+    Pattern {
+        cells: Grid<State>,
+        rule: Option<String>,
+    },
+    /// The live cells of a pattern saved by an older version.
+    Legacy(Grid<LifeStates>),
+}
+
+/// Serialize a pattern for the library, see the [module documentation](self).
+pub fn serialize<State: Serialize + DeserializeOwned>(
+    cells: &Grid<State>,
+    rule: Option<&str>,
+) -> Result<String, SnapshotError> {
+    Ok(format!("{}\n{}", rule.unwrap_or(""), cells.to_json()?))
+}
+
+/// Read a pattern written by [`serialize`] or an older version, `None` if it is corrupt.
+pub fn deserialize<State: Serialize + DeserializeOwned>(input: &str) -> Option<Loaded<State>> {
+    let (first, rest) = input.split_once('\n')?;
+    if rest.starts_with('{') {
+        return Some(Loaded::Pattern {
+            cells: Grid::from_json(rest).ok()?,
+            rule: Some(first.to_owned()).filter(|rule| !rule.is_empty()),
+        });
+    }
+    let (width, height) = first.split_once(' ')?;
+    let (width, height): (usize, usize) = (width.parse().ok()?, height.parse().ok()?);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let cells = life106_cells(rest).ok()?;
+    Some(Loaded::Legacy(place_centered(&cells, width, height).ok()?))
+}
+
+/// Key of the pattern `name` of the automaton called `automaton`.
+fn key(automaton: &str, name: &str) -> JsValue {
+    Array::of2(&JsValue::from_str(automaton), &JsValue::from_str(name)).into()
+}
+
+/// Key of the pattern `name` saved by an older version, if `automaton` reads those.
+fn legacy_key(automaton: &str, name: &str) -> Option<JsValue> {
+    (automaton == DEFAULT_AUTOMATON).then(|| JsValue::from_str(name))
+}
+
+/// The names of all patterns of `automaton` in alphabetical order.
+pub fn list(automaton: &str, done: impl FnOnce(Result<Vec<String>, String>) + 'static) {
+    let automaton = automaton.to_owned();
+    request(
+        IdbTransactionMode::Readonly,
+        |store| store.get_all_keys(),
+        move |result| {
+            done(result.map(|keys| {
+                let mut names: Vec<_> = Array::from(&keys)
+                    .iter()
+                    .filter_map(|key| match key.as_string() {
+                        Some(name) => legacy_key(&automaton, &name).map(|_| name),
+                        None => {
+                            let key = Array::from(&key);
+                            let owner = key.get(0).as_string()?;
+                            (owner == automaton).then(|| key.get(1).as_string())?
+                        }
+                    })
+                    .collect();
+                names.sort();
+                names.dedup();
+                names
+            }))
+        },
+    )
+}
+
+/// Save a pattern serialized by [`serialize`] as `name` of `automaton`,
+/// replacing any pattern of the same name.
+pub fn save(
+    automaton: &str,
+    name: &str,
+    pattern: &str,
+    done: impl FnOnce(Result<(), String>) + 'static,
+) {
+    let (key, value) = (key(automaton, name), JsValue::from_str(pattern));
+    request(
+        IdbTransactionMode::Readwrite,
+        move |store| store.put_with_key(&value, &key),
+        move |result| done(result.map(drop)),
+    )
+}
+
+/// Load the text of the pattern saved as `name` of `automaton`, see [`deserialize`].
+pub fn load(automaton: &str, name: &str, done: impl FnOnce(Result<String, String>) + 'static) {
+    let key = key(automaton, name);
+    let legacy = legacy_key(automaton, name);
+    let name = name.to_owned();
+    let text = move |result: Result<JsValue, String>| {
+        result.and_then(|value| {
+            value
+                .as_string()
+                .ok_or_else(|| format!("pattern {:?} is missing or corrupt", name))
+        })
+    };
+    request(
+        IdbTransactionMode::Readonly,
+        move |store| store.get(&key),
+        move |result| match (result, legacy) {
+            (Ok(value), Some(legacy)) if value.is_undefined() => request(
+                IdbTransactionMode::Readonly,
+                move |store| store.get(&legacy),
+                move |result| done(text(result)),
+            ),
+            (result, _) => done(text(result)),
+        },
+    )
+}
+
+/// Delete the pattern saved as `name` of `automaton`.
+pub fn delete(automaton: &str, name: &str, done: impl FnOnce(Result<(), String>) + 'static) {
+    let key = key(automaton, name);
+    let legacy = legacy_key(automaton, name);
+    request(
+        IdbTransactionMode::Readwrite,
+        move |store| {
+            let deleted = store.delete(&key)?;
+            match legacy {
+                Some(legacy) => store.delete(&legacy),
+                None => Ok(deleted),
+            }
+        },
+        move |result| done(result.map(drop)),
+    )
+}
+
+type Done = Rc<RefCell<Option<Box<dyn FnOnce(Result<JsValue, String>)>>>>;
+
+/// Call `done` with `result`, unless it was already called.
+fn finish(done: &Done, result: Result<JsValue, String>) {
+    if let Some(done) = done.borrow_mut().take() {
+        done(result)
+    }
+}
+
+/// Report the result of `request` or its error to `done`.
+fn on_request(request: &IdbRequest, done: Done) {
+    let on_success = {
+        let (request, done) = (request.clone(), done.clone());
+        Closure::once_into_js(move || finish(&done, request.result().map_err(describe_error)))
+    };
+    let on_error = {
+        let request = request.clone();
+        Closure::once_into_js(move || {
+            let error = request.error().ok().flatten().map(|error| error.message());
+            finish(
+                &done,
+                Err(error.unwrap_or_else(|| "request failed".to_owned())),
+            )
+        })
+    };
+    request.set_onsuccess(Some(on_success.unchecked_ref()));
+    request.set_onerror(Some(on_error.unchecked_ref()));
+}
+
+/// Open the database, creating the store if necessary, and run the
+/// request created by `make` in a transaction of the given `mode`.
+fn request(
+    mode: IdbTransactionMode,
+    make: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue> + 'static,
+    done: impl FnOnce(Result<JsValue, String>) + 'static,
+) {
+    let done: Done = Rc::new(RefCell::new(Some(Box::new(done))));
+    let open = || {
+        let factory = web_sys::window()
+            .ok_or("No window")?
+            .indexed_db()?
+            .ok_or("No IndexedDB")?;
+        factory.open_with_u32(DB_NAME, DB_VERSION)
+    };
+    let open = match open() {
+        Ok(open) => open,
+        Err(e) => return finish(&done, Err(describe_error(e))),
+    };
+    let on_upgrade = {
+        let open = open.clone();
+        Closure::once_into_js(move || {
+            if let Ok(db) = open.result() {
+                let db: IdbDatabase = db.unchecked_into();
+                if !db.object_store_names().contains(STORE) {
+                    if let Err(e) = db.create_object_store(STORE) {
+                        weblog::console_log!("Failed to create the pattern store", e);
+                    }
+                }
+            }
+        })
+    };
+    open.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    let on_open = Rc::new(RefCell::new(Some(Box::new({
+        let done = done.clone();
+        move |result: Result<JsValue, String>| {
+            let run = |db: JsValue| {
+                let db: IdbDatabase = db.unchecked_into();
+                let store = db
+                    .transaction_with_str_and_mode(STORE, mode)?
+                    .object_store(STORE)?;
+                make(&store)
+            };
+            match result.and_then(|db| run(db).map_err(describe_error)) {
+                Ok(request) => on_request(&request, done),
+                Err(e) => finish(&done, Err(e)),
+            }
+        }
+    }) as Box<dyn FnOnce(_)>)));
+    on_request(&open, on_open);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cellular_automaton::{automaton::WireState, pattern::to_life106};
+
+    #[test]
+    fn round_trip() {
+        let mut cells = Grid::generate(6, 3);
+        cells[(0, 0)] = WireState::Conductor;
+        cells[(5, 2)] = WireState::ElectronHead;
+        let text = serialize(&cells, Some("B3/S23")).unwrap();
+        let rule = Some("B3/S23".to_owned());
+        assert_eq!(deserialize(&text), Some(Loaded::Pattern { cells, rule }));
+        let text = serialize(&Grid::<WireState>::generate(2, 2), None).unwrap();
+        assert!(matches!(
+            deserialize::<WireState>(&text),
+            Some(Loaded::Pattern { rule: None, .. })
+        ));
+        assert!(deserialize::<LifeStates>(&text).is_none());
+    }
+
+    #[test]
+    fn legacy_patterns_are_read() {
+        let mut cells = Grid::generate(6, 3);
+        cells[(0, 0)] = LifeStates::Alife;
+        cells[(5, 2)] = LifeStates::Alife;
+        let legacy = format!("6 3\n{}", to_life106(&cells));
+        assert_eq!(
+            deserialize::<WireState>(&legacy),
+            Some(Loaded::Legacy(cells))
+        );
+    }
+
+    #[test]
+    fn corrupt_patterns_are_rejected() {
+        let valid = serialize(&Grid::<LifeStates>::generate(3, 3), None).unwrap();
+        assert!(deserialize::<LifeStates>(&valid).is_some());
+        assert!(deserialize::<LifeStates>("").is_none());
+        assert!(
+            deserialize::<LifeStates>(&valid.replacen("\"width\":3", "\"width\":2", 1)).is_none()
+        );
+        let legacy = "3 3\n#Life 1.06\n";
+        assert!(deserialize::<LifeStates>(legacy).is_some());
+        assert!(deserialize::<LifeStates>(&legacy.replacen("3 3", "3", 1)).is_none());
+        assert!(deserialize::<LifeStates>(&legacy.replacen("3 3", "0 3", 1)).is_none());
+        assert!(deserialize::<LifeStates>(&legacy.replacen("3 3", "100000 100000", 1)).is_none());
+        assert!(deserialize::<LifeStates>("3 3\n1 2\n").is_none());
+    }
+}
@@ -0,0 +1,435 @@
+//! Golly's macrocell (`.mc`) format.
+//!
+//! A pattern is a quadtree, where every line after the `[M2]` header defines
+//! one node, numbered from one, and the last node is the root:
+//!
+//! - An 8×8 leaf is written as rows of `.` (dead) and `*` (alive), each ended
+//!   by `$`. Trailing dead cells and rows are omitted.
+//! - `k nw ne sw se` is a node of size 2^k with the given quadrants, where
+//!   `0` is an empty quadrant. For `k = 1` the quadrants are cell states
+//!   instead, any state other than zero counts as alive.
+//!
+//! Identical subtrees are shared, so huge but regular patterns stay small.
+//! Patterns are read into a dense [`Grid`] though, so the bounding box of
+//! the live cells must not exceed [`MAX_CELLS`]. Larger patterns, like
+//! most metapixel constructions, are rejected with
+//! [`MacrocellError::TooLarge`] before the tree is expanded.
+
+use std::{collections::HashMap, fmt};
+
+use crate::automaton::{Grid, LifeStates, MAX_CELLS};
+
+/// First line of every macrocell file.
+pub const MACROCELL_HEADER: &str = "[M2]";
+
+/// Level of the 8×8 leaves.
+const LEAF_LEVEL: u32 = 3;
+
+/// Deepest supported level, so that coordinates fit into an `i64`.
+const MAX_LEVEL: u32 = 62;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacrocellError {
+    /// The file does not start with [`MACROCELL_HEADER`].
+    MissingHeader,
+    /// The given (1-based) line could not be parsed.
+    InvalidLine(usize, String),
+    /// The node in the given (1-based) line refers to an undefined node or
+    /// one of the wrong size.
+    InvalidNode(usize),
+    /// The bounding box of the live cells, `width`×`height`, has more than
+    /// [`MAX_CELLS`] cells.
+    TooLarge { width: u64, height: u64 },
+}
+
+impl fmt::Display for MacrocellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "expected '{}' header", MACROCELL_HEADER),
+            Self::InvalidLine(line, content) => write!(f, "invalid line {}: {:?}", line, content),
+            Self::InvalidNode(line) => write!(f, "node in line {} refers to an invalid node", line),
+            Self::TooLarge { width, height } => write!(
+                f,
+                "the pattern spans {}×{} cells, but only patterns of up to {} cells can be loaded",
+                width, height, MAX_CELLS
+            ),
+        }
+    }
+}
+
+/// The smallest and largest coordinates of live cells, as `(min_x, min_y, max_x, max_y)`.
+type Bounds = (i64, i64, i64, i64);
+
+#[derive(Debug, Clone)]
+enum Kind {
+    /// Rows of an 8×8 leaf, bit `x` of row `y` is the cell `(x, y)`.
+    Leaf([u8; 8]),
+    /// Cell states of a 2×2 node.
+    States([u32; 4]),
+    /// Indices of the four quadrants, zero if empty.
+    Inner([usize; 4]),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    level: u32,
+    kind: Kind,
+    /// Bounds of the live cells relative to the top-left corner of the node.
+    bounds: Option<Bounds>,
+}
+
+/// Offsets of the quadrants of a node of the given level, in `nw ne sw se` order.
+fn quadrant_offsets(level: u32) -> [(i64, i64); 4] {
+    let half = 1 << (level - 1);
+    [(0, 0), (half, 0), (0, half), (half, half)]
+}
+
+fn union(a: Option<Bounds>, b: Option<Bounds>) -> Option<Bounds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+impl Node {
+    fn new(level: u32, kind: Kind, nodes: &[Node]) -> Self {
+        let bounds = match &kind {
+            Kind::Leaf(rows) => (0..8)
+                .flat_map(|y| (0..8).map(move |x| (x, y)))
+                .filter(|&(x, y)| rows[y as usize] >> x & 1 == 1)
+                .fold(None, |bounds, (x, y)| union(bounds, Some((x, y, x, y)))),
+            Kind::States(states) => quadrant_offsets(1)
+                .iter()
+                .zip(states)
+                .filter(|(_, state)| **state != 0)
+                .fold(None, |bounds, (&(x, y), _)| {
+                    union(bounds, Some((x, y, x, y)))
+                }),
+            Kind::Inner(children) => quadrant_offsets(level)
+                .iter()
+                .zip(children)
+                .filter_map(|(&(dx, dy), &child)| {
+                    let (x0, y0, x1, y1) = nodes[child].bounds?;
+                    Some((x0 + dx, y0 + dy, x1 + dx, y1 + dy))
+                })
+                .fold(None, |bounds, child| union(bounds, Some(child))),
+        };
+        Self {
+            level,
+            kind,
+            bounds,
+        }
+    }
+
+    /// Push the live cells of the node with its top-left corner at `(x, y)`.
+    fn cells(&self, nodes: &[Node], (x, y): (i64, i64), out: &mut Vec<(i64, i64)>) {
+        if self.bounds.is_none() {
+            return;
+        }
+        match &self.kind {
+            Kind::Leaf(rows) => {
+                for (dy, row) in (0..).zip(rows) {
+                    out.extend(
+                        (0..8)
+                            .filter(|dx| row >> dx & 1 == 1)
+                            .map(|dx| (x + dx, y + dy)),
+                    );
+                }
+            }
+            Kind::States(states) => {
+                for (&(dx, dy), state) in quadrant_offsets(1).iter().zip(states) {
+                    if *state != 0 {
+                        out.push((x + dx, y + dy));
+                    }
+                }
+            }
+            Kind::Inner(children) => {
+                for (&(dx, dy), &child) in quadrant_offsets(self.level).iter().zip(children) {
+                    nodes[child].cells(nodes, (x + dx, y + dy), out);
+                }
+            }
+        }
+    }
+}
+
+/// Parse a leaf line like `.*$..*$***$`.
+fn parse_leaf(line: &str) -> Option<[u8; 8]> {
+    let mut rows = [0; 8];
+    let (mut x, mut y) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' | '*' if x < 8 && y < 8 => {
+                if c == '*' {
+                    rows[y] |= 1 << x;
+                }
+                x += 1;
+            }
+            '$' if y < 8 => (x, y) = (0, y + 1),
+            _ => return None,
+        }
+    }
+    Some(rows)
+}
+
+/// Parse the node `k nw ne sw se` in the (1-based) line `line_no`,
+/// checking the quadrants against `nodes`.
+fn parse_inner(line: &str, line_no: usize, nodes: &[Node]) -> Result<Node, MacrocellError> {
+    let fields: Result<Vec<u64>, _> = line.split_whitespace().map(str::parse).collect();
+    let (level, quadrants) = match fields.as_deref() {
+        Ok(&[level, nw, ne, sw, se]) if (1..=MAX_LEVEL as u64).contains(&level) => {
+            (level as u32, [nw, ne, sw, se])
+        }
+        _ => return Err(MacrocellError::InvalidLine(line_no, line.to_owned())),
+    };
+    if level == 1 {
+        let states = quadrants.map(|state| state as u32);
+        return Ok(Node::new(level, Kind::States(states), nodes));
+    }
+    let children = quadrants.map(|idx| idx as usize);
+    let valid = children
+        .iter()
+        .all(|&idx| idx == 0 || nodes.get(idx).is_some_and(|child| child.level == level - 1));
+    if !valid {
+        return Err(MacrocellError::InvalidNode(line_no));
+    }
+    Ok(Node::new(level, Kind::Inner(children), nodes))
+}
+
+/// Read a pattern in macrocell format.
+///
+/// The grid is just large enough to contain all live cells. Any rule is
+/// read by [`macrocell_rule`].
+pub fn from_macrocell(input: &str) -> Result<Grid<LifeStates>, MacrocellError> {
+    let mut lines = input.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim().starts_with(MACROCELL_HEADER) => {}
+        _ => return Err(MacrocellError::MissingHeader),
+    }
+    // Index zero is the empty node
+    let mut nodes = vec![Node {
+        level: 0,
+        kind: Kind::Inner([0; 4]),
+        bounds: None,
+    }];
+    for (idx, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let node = if line.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_inner(line, idx + 1, &nodes)?
+        } else {
+            let rows = parse_leaf(line)
+                .ok_or_else(|| MacrocellError::InvalidLine(idx + 1, line.to_owned()))?;
+            Node::new(LEAF_LEVEL, Kind::Leaf(rows), &nodes)
+        };
+        nodes.push(node);
+    }
+    let root = nodes.last().filter(|_| nodes.len() > 1);
+    let (root, (x0, y0, x1, y1)) = match root.and_then(|root| Some((root, root.bounds?))) {
+        Some(root) => root,
+        None => return Ok(Grid::generate(1, 1)),
+    };
+    let (width, height) = ((x1 - x0 + 1) as u64, (y1 - y0 + 1) as u64);
+    let too_large = MacrocellError::TooLarge { width, height };
+    if width.saturating_mul(height) > MAX_CELLS as u64 {
+        // Don't even try to allocate it, or to collect the live cells
+        return Err(too_large);
+    }
+    let mut grid = Grid::try_generate(width as usize, height as usize).map_err(|_| too_large)?;
+    let mut cells = Vec::new();
+    root.cells(&nodes, (-x0, -y0), &mut cells);
+    for (x, y) in cells {
+        grid[(x as isize, y as isize)] = LifeStates::Alife;
+    }
+    Ok(grid)
+}
+
+/// The rule from the `#R` line of a macrocell pattern, if it has one.
+pub fn macrocell_rule(input: &str) -> Option<String> {
+    input
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("#R"))
+        .map(|rule| rule.trim().to_owned())
+        .filter(|rule| !rule.is_empty())
+}
+
+/// Serialize the live cells of `grid` in macrocell format.
+///
+/// The `rule` is added as `#R` line if given. The tree covers the bounding
+/// box of the live cells, identical subtrees are written once.
+pub fn to_macrocell(grid: &Grid<LifeStates>, rule: Option<&str>) -> String {
+    let mut out = format!("{}\n", MACROCELL_HEADER);
+    if let Some(rule) = rule {
+        out += &format!("#R {}\n", rule);
+    }
+    let alive: Vec<_> = (0..grid.height() as isize)
+        .flat_map(|y| (0..grid.width() as isize).map(move |x| (x, y)))
+        .filter(|&pos| grid[pos] == LifeStates::Alife)
+        .collect();
+    let x0 = alive.iter().map(|pos| pos.0).min().unwrap_or(0);
+    let y0 = alive.iter().map(|pos| pos.1).min().unwrap_or(0);
+    let width = alive.iter().map(|pos| pos.0 - x0 + 1).max().unwrap_or(0);
+    let height = alive.iter().map(|pos| pos.1 - y0 + 1).max().unwrap_or(0);
+    let mut level = LEAF_LEVEL;
+    while 1 << level < width.max(height) {
+        level += 1;
+    }
+    let mut writer = Writer {
+        is_alive: |x, y| x < width && y < height && grid[(x + x0, y + y0)] == LifeStates::Alife,
+        lines: Vec::new(),
+        known: HashMap::new(),
+    };
+    if writer.node(level, 0, 0) == 0 {
+        // Even an empty pattern needs a root
+        writer.lines.push("$".to_owned());
+    }
+    for line in writer.lines {
+        out += &line;
+        out.push('\n');
+    }
+    out
+}
+
+/// Builds the node lines of [`to_macrocell`], sharing identical subtrees.
+struct Writer<F> {
+    is_alive: F,
+    lines: Vec<String>,
+    /// Index of every line written so far.
+    known: HashMap<String, usize>,
+}
+
+impl<F: Fn(isize, isize) -> bool> Writer<F> {
+    /// Write the node of `level` with its top-left corner at `(x, y)` and
+    /// all of its descendants, returning its index or zero if it is empty.
+    fn node(&mut self, level: u32, x: isize, y: isize) -> usize {
+        let line = if level == LEAF_LEVEL {
+            let rows: Vec<String> = (0..8)
+                .map(|dy| {
+                    let row: String = (0..8)
+                        .map(|dx| {
+                            if (self.is_alive)(x + dx, y + dy) {
+                                '*'
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect();
+                    row.trim_end_matches('.').to_owned()
+                })
+                .collect();
+            let rows = rows.join("$");
+            let rows = rows.trim_end_matches('$');
+            if rows.is_empty() {
+                return 0;
+            }
+            format!("{}$", rows)
+        } else {
+            let half = 1 << (level - 1);
+            let children = [(0, 0), (half, 0), (0, half), (half, half)]
+                .map(|(dx, dy)| self.node(level - 1, x + dx, y + dy));
+            if children == [0; 4] {
+                return 0;
+            }
+            let [nw, ne, sw, se] = children;
+            format!("{} {} {} {} {}", level, nw, ne, sw, se)
+        };
+        if let Some(&idx) = self.known.get(&line) {
+            return idx;
+        }
+        self.lines.push(line.clone());
+        self.known.insert(line, self.lines.len());
+        self.lines.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::{from_rle, to_rle};
+
+    const GLIDER: &str = "[M2] (golly 4.2)\n#R B3/S23\n.*$..*$***$\n";
+
+    #[test]
+    fn read_glider_leaf() {
+        let grid = from_macrocell(GLIDER).unwrap();
+        assert_eq!(grid, from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap());
+        assert_eq!(macrocell_rule(GLIDER), Some("B3/S23".to_owned()));
+        assert_eq!(macrocell_rule("[M2]\n$\n"), None);
+    }
+
+    #[test]
+    fn empty_quadrants_are_not_expanded() {
+        // Two gliders in opposite corners of a 2^40 wide node
+        let mut input = String::from("[M2]\n.*$..*$***$\n");
+        for level in 4..40 {
+            input += &format!("{} {} 0 0 {}\n", level, level - 3, level - 3);
+        }
+        // Only the bounding box counts, which is far too large
+        let error = from_macrocell(&input).unwrap_err();
+        let span = (1 << 39) - 5;
+        assert_eq!(
+            error,
+            MacrocellError::TooLarge {
+                width: span,
+                height: span
+            }
+        );
+        assert!(error.to_string().contains(&format!("{}×{}", span, span)));
+        // A single glider deep down the tree is found without expanding the rest
+        let mut input = String::from("[M2]\n.*$..*$***$\n");
+        for level in 4..40 {
+            input += &format!("{} 0 0 0 {}\n", level, level - 3);
+        }
+        assert_eq!(from_macrocell(&input), from_macrocell(GLIDER));
+    }
+
+    #[test]
+    fn two_state_nodes() {
+        // A blinker from level-one nodes: `1 nw ne sw se` holds states
+        let input = "[M2]\n1 0 0 1 1\n1 0 0 1 0\n2 0 0 1 2\n";
+        let grid = from_macrocell(input).unwrap();
+        assert_eq!(to_rle(&grid, None), "x = 3, y = 1\n3o!\n");
+    }
+
+    #[test]
+    fn malformed_macrocell_is_rejected() {
+        assert_eq!(from_macrocell(".*$\n"), Err(MacrocellError::MissingHeader));
+        assert_eq!(
+            from_macrocell("[M2]\n.x$\n"),
+            Err(MacrocellError::InvalidLine(2, ".x$".to_owned()))
+        );
+        assert_eq!(
+            from_macrocell("[M2]\n.........*$\n"),
+            Err(MacrocellError::InvalidLine(2, ".........*$".to_owned()))
+        );
+        // Node 2 is not defined yet
+        assert_eq!(
+            from_macrocell("[M2]\n*$\n4 1 2 0 0\n"),
+            Err(MacrocellError::InvalidNode(3))
+        );
+        // A level 5 node needs level 4 quadrants
+        assert_eq!(
+            from_macrocell("[M2]\n*$\n5 1 0 0 0\n"),
+            Err(MacrocellError::InvalidNode(3))
+        );
+        assert!(from_macrocell("[M2]\n4 1 2 3\n").is_err());
+    }
+
+    #[test]
+    fn macrocell_round_trip() {
+        let gun = "x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!";
+        let grid = from_rle(gun).unwrap();
+        let text = to_macrocell(&grid, Some("B3/S23"));
+        assert!(text.starts_with("[M2]\n#R B3/S23\n"));
+        assert_eq!(from_macrocell(&text).unwrap(), grid);
+        // Identical leaves are written once
+        let blocks = from_rle("x = 18, y = 2\n2o14b2o$2o14b2o!").unwrap();
+        let text = to_macrocell(&blocks, None);
+        assert_eq!(text.lines().filter(|line| *line == "**$**$").count(), 1);
+        assert_eq!(from_macrocell(&text).unwrap(), blocks);
+        let empty = to_macrocell(&Grid::generate(4, 4), None);
+        assert_eq!(from_macrocell(&empty).unwrap(), Grid::generate(1, 1));
+    }
+}
@@ -1,32 +1,84 @@
-use gloo_timers::callback::Interval;
-use lazy_static::lazy_static;
+use gloo_timers::callback::{Interval, Timeout};
 use nalgebra::{Point2, Translation2};
 use supervisor::Supervisor;
 use wasm_bindgen::{
     prelude::{wasm_bindgen, Closure},
     JsCast, JsValue,
 };
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use web_sys::{CanvasRenderingContext2d, File, FileReader, HtmlCanvasElement};
 use weblog::console_log;
 use yew::prelude::*;
 
-use std::f64;
+use std::{f64, mem};
 
-mod automaton;
+mod catalogue;
+mod clipboard;
+mod export;
+mod library;
 mod settings;
-mod supervisor;
+mod share;
+mod storage;
+mod transport;
 
-use automaton::{Automaton, Life};
+use cellular_automaton::{animation, automaton, pattern, stats, supervisor, theme};
 
-use crate::{settings::Settings, supervisor::Scale};
+use automaton::{
+    Automaton, BriansBrain, Cyclic, DayAndNight, Elementary, ForestFire, Generations,
+    GreenbergHastings, HexLife, HighLife, Immigration, LangtonsAnt, LargerThanLife, Lenia, Life,
+    LifeLike, Margolus, RockPaperScissors, Sandpile, Seeds, SmoothLife, Topology, TotalisticRule,
+    Turmite, WaTor, WeightedSeeds, Wireworld,
+};
+
+use crate::{
+    animation::ViewAnimation,
+    export::{RecordFormat, Recording},
+    library::Loaded,
+    pattern::{Orientation, PatternMeta},
+    settings::{CataloguePreview, Settings},
+    supervisor::{line_cells, pinch_delta, Region, Scale},
+};
 
 const CANVAS_ID: &str = "canvas";
-const CELL_WIDTH: usize = 50;
+/// Width and height of the initial grid.
+const DEFAULT_GRID_SIZE: usize = 20;
 const TIME_BETWEEN_RENDERS_MS: u32 = 100;
+/// Delay before persisting changes, see [`Model::schedule_save`].
+const SAVE_DELAY_MS: u32 = 1000;
+/// Largest width and height of catalogue previews.
+const THUMBNAIL_PX: u32 = 120;
+/// Screen pixels moved per arrow key press.
+const PAN_STEP_PX: f64 = 50.0;
+/// Screen pixels a finger may move before a tap becomes a drag.
+const TAP_RADIUS_PX: f64 = 10.0;
 
-lazy_static! {
-    static ref MIN_DRAG: Point2<i32> = Point2::new(5, 5);
-}
+/// Keyboard and mouse controls as shown in the settings, see [`shortcut`].
+const SHORTCUT_HELP: &[(&str, &str)] = &[
+    ("Space", "Step"),
+    ("Enter / P", "Play / pause"),
+    ("C", "Clear"),
+    ("R", "Randomize"),
+    ("← / →", "Step back / forward"),
+    ("Shift+arrows, ↑ / ↓", "Pan"),
+    ("Left drag", "Paint"),
+    ("Right drag", "Erase"),
+    ("Ctrl+click", "Cycle cell state"),
+    ("Shift drag", "Pan"),
+    ("S", "Select region"),
+    (
+        "T / Shift+T",
+        "Rotate selection or pattern clockwise / counterclockwise",
+    ),
+    (
+        "H / V",
+        "Flip selection or pattern horizontally / vertically",
+    ),
+    ("Ctrl+C", "Copy selection or grid as RLE"),
+    ("Ctrl+V", "Paste, then click to place"),
+    ("Esc", "Clear selection or stop pasting"),
+    ("F", "Toggle fullscreen"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Y", "Redo"),
+];
 
 #[wasm_bindgen(module = "/js/resize-canvas.js")]
 extern "C" {
@@ -35,17 +87,145 @@ extern "C" {
 
 pub enum Msg {
     MouseDown(MouseEvent),
+    MouseMove(MouseEvent),
     MouseUp(MouseEvent),
+    /// The cursor left the canvas.
+    MouseLeave(MouseEvent),
+    TouchStart(TouchEvent),
+    TouchMove(TouchEvent),
+    /// Fingers were lifted or the touch was canceled.
+    TouchEnd(TouchEvent),
+    /// Request drawing the grid with the next animation frame.
     Redraw,
+    /// Draw the grid, triggered by the browser's `requestAnimationFrame`.
+    AnimationFrame,
     Resized,
     Scroll(WheelEvent),
     Update,
+    StepForward,
+    StepBack,
+    Undo,
+    Redo,
+    KeyDown(KeyboardEvent),
+    Rewind,
+    FastForward,
     ToggleSettings,
+    /// Show the canvas fullscreen, or leave fullscreen.
+    ToggleFullscreen,
     ToggleAutoRun,
+    Play,
+    Pause,
+    ToggleNaturalPan,
+    /// Switch between free panning and keeping part of the grid in view.
+    ToggleClampPan,
+    ToggleGrid,
+    ToggleHeatmap,
+    ToggleNeighborCounts,
+    ToggleCellShape,
+    TogglePauseWhenStable,
+    /// Switch between placing agents and editing cells by clicking.
+    TogglePlaceAgents,
+    /// Switch between dropping sand and editing cells by clicking.
+    ToggleDropSand,
+    /// Switch between selecting regions and editing cells by dragging.
+    ToggleSelect,
+    /// Transform the cells in the selected region, or the pattern to stamp
+    /// if nothing is selected.
+    Transform(Transform),
+    ClearSelection,
+    /// Switch between a wrapping and an expanding grid.
+    ToggleExpanding,
+    /// Glue the edges of a wrapping grid differently.
+    SetTopology(Topology),
+    SetTheme(usize),
+    /// Save the state and reload with the automaton of the given name, see [`AUTOMATA`].
+    SelectAutomaton(String),
+    SetCellWidth(usize),
+    SetSpeed(u32),
     ResetZoom,
+    /// Zoom to the alive cells, see [`Supervisor::fit_to_pattern`].
+    FitToPattern,
+    /// Move the view by the given amount of screen pixels.
+    Pan(f64, f64),
+    Clear,
+    /// Download the whole grid as a PNG image, see [`export::export_png`].
+    ExportPng,
+    /// Download the whole grid as an SVG image, see [`export::export_svg`].
+    ExportSvg,
+    /// Choose the width of exported images, `None` to derive it from the grid.
+    SetImageWidth(Option<u32>),
+    /// Record the given number of generations as an animation.
+    StartRecording(usize),
+    /// Finish the recording early and download it.
+    StopRecording,
+    SetRecordFormat(RecordFormat),
+    SetRecordFrames(usize),
+    SetRecordCellPx(u32),
+    /// Persist the grid and settings to local storage and the URL.
+    Save,
+    /// Forget the persisted state and restore the defaults.
+    ResetDefaults,
+    ExportPattern,
+    /// Download the live cells in the given format and show them in the pattern box.
+    DownloadPattern(pattern::Format),
+    ImportPattern,
+    /// A file was dropped onto the canvas, see [`Msg::StampPatternFile`].
+    DropFile(DragEvent),
+    /// Load the pattern file chosen in the file picker.
+    OpenFile(File),
+    /// Load the pattern from the file with the given name and content.
+    LoadPatternFile(String, String),
+    /// Stamp the pattern from the file with the given name and content,
+    /// centered on the given cell.
+    StampPatternFile((isize, isize), String, String),
+    DismissError,
+    /// Hide the name and description of the imported pattern.
+    DismissPatternMeta,
+    SetPatternText(String),
+    SetLibraryName(String),
+    /// Save the grid to the pattern library under the entered name.
+    SaveToLibrary,
+    LoadFromLibrary(String),
+    DeleteFromLibrary(String),
+    /// The names in the pattern library were read, see [`Model::refresh_library`].
+    LibraryListed(Result<Vec<String>, String>),
+    /// A pattern was read from the library, see [`library::deserialize`].
+    LibraryLoaded(Result<String, String>),
+    /// Copy the selected region, or the whole grid, to the clipboard as RLE.
+    Copy,
+    /// Read a pattern from the clipboard, see [`Msg::Pasted`].
+    Paste,
+    /// Text was read from the clipboard, place the pattern in it by clicking.
+    Pasted(Result<String, String>),
+    SetCatalogueUrl(String),
+    /// Fetch the index of the online catalogue, see [`Msg::CatalogueLoaded`].
+    LoadCatalogue,
+    /// The links to the patterns of the catalogue were read.
+    CatalogueLoaded(Result<Vec<String>, String>),
+    SetCatalogueQuery(String),
+    /// Fetch the catalogue pattern behind the link, see [`Msg::CataloguePatternLoaded`].
+    PreviewCataloguePattern(String),
+    /// The RLE of the catalogue pattern with the given name was fetched.
+    CataloguePatternLoaded(String, Result<String, String>),
+    /// Place the previewed catalogue pattern by clicking, like a pasted one.
+    StampCataloguePattern,
+    /// Select the built-in pattern to stamp on click, by index into [`pattern::PATTERNS`].
+    SelectPattern(Option<usize>),
+    /// Select the state painted by dragging, by index into [`Automaton::states`].
+    SetBrush(usize),
+    Resize(usize, usize),
+    Randomize(f64),
+    SetDensity(f64),
+    /// Change the text of the seed field, see [`Settings::seed`].
+    SetSeedText(String),
+    SetRule(String),
+    /// Set a rule in B/S notation, built with the rule editor.
+    SetTotalistic(TotalisticRule),
+    /// Set a parameter of the rule, by index into [`Automaton::parameters`].
+    SetParameter(usize, f64),
 }
 
-pub struct Model<A: Automaton + 'static> {
+pub struct Model<A: Automaton + Default + 'static> {
     // `ComponentLink` is like a reference to a component.
     // It can be used to send messages to the component
     link: ComponentLink<Self>,
@@ -53,39 +233,320 @@ pub struct Model<A: Automaton + 'static> {
     canvas: Option<HtmlCanvasElement>,
     context: Option<CanvasRenderingContext2d>,
     resize_callback: Closure<dyn Fn()>,
+    keydown_callback: Closure<dyn Fn(KeyboardEvent)>,
+    frame_callback: Closure<dyn Fn()>,
+    /// Handle of the requested animation frame, if a redraw is pending.
+    frame_request: Option<i32>,
+    /// Running zoom animation, advanced with every animation frame.
+    animation: Option<ViewAnimation>,
     automaton: Supervisor<A>,
-    last_mouse_click: Option<Point2<i32>>,
+    drag: Option<Drag<A::State>>,
+    gesture: Option<Gesture>,
+    /// The cell under the cursor, if any.
+    hover: Option<(isize, isize)>,
     settings: Settings,
     render_timer: Option<Interval>,
+    save_timer: Option<Timeout>,
+    /// Message shown above the canvas until dismissed.
+    error: Option<String>,
+    /// Recording in progress, extended by every generation.
+    recording: Option<Recording>,
+    /// Built-in pattern stamped by clicking, instead of toggling cells.
+    selected_pattern: Option<usize>,
+    /// Offsets of the pasted pattern, stamped by clicking like the selected pattern.
+    paste: Option<Vec<(isize, isize)>>,
+    /// Orientation of the selected or pasted pattern, changed by
+    /// [`Msg::Transform`] while no region is selected.
+    stamp_orientation: Orientation,
+    /// Region selected by dragging in selection mode.
+    selection: Option<Region>,
+    /// Name, author and comments of the imported pattern, kept when exporting.
+    pattern_meta: PatternMeta,
+}
+
+/// An ongoing mouse drag.
+enum Drag<State> {
+    /// Moving the view, started at the given screen position.
+    Pan(Point2<i32>),
+    /// Painting cells with `state`, `last` is the most recently painted cell.
+    Paint { state: State, last: (isize, isize) },
+    /// Selecting the region between `start` and the cell under the cursor.
+    Select { start: (isize, isize) },
+    /// Dropping sand onto `cell` in every generation while the mouse is held.
+    Sand { cell: (isize, isize) },
+}
+
+/// Operations on the selected region or the pattern to stamp, see [`Msg::Transform`].
+#[derive(Debug, Clone, Copy)]
+pub enum Transform {
+    RotateCw,
+    RotateCcw,
+    FlipH,
+    FlipV,
+}
+
+/// Ongoing touch gesture on the canvas.
+enum Gesture {
+    /// A single finger, that only pans once it moved further than
+    /// [`TAP_RADIUS_PX`] from `start` and toggles the cell there otherwise.
+    Drag {
+        start: Point2<f64>,
+        last: Point2<f64>,
+        moved: bool,
+    },
+    /// Two fingers, zooming with the distance between them and panning
+    /// with their center.
+    Pinch { distance: f64, center: Point2<f64> },
+}
+
+impl Gesture {
+    /// The gesture for the fingers currently on the screen.
+    fn from_touches(touches: &[Point2<f64>]) -> Option<Self> {
+        match *touches {
+            [pos] => Some(Gesture::Drag {
+                start: pos,
+                last: pos,
+                moved: false,
+            }),
+            [a, b, ..] => Some(Gesture::Pinch {
+                distance: (a - b).norm(),
+                center: nalgebra::center(&a, &b),
+            }),
+            [] => None,
+        }
+    }
 }
 
-impl<A: Automaton> Model<A> {
+impl<A: Automaton + Default> Model<A> {
     fn draw(&mut self) {
+        let stamp = self.stamp_offsets();
         if let (Some(ctx), Some(canvas)) = (self.context.as_mut(), self.canvas.as_mut()) {
-            // Clear the background
-            ctx.set_fill_style(&JsValue::from("rgb(40,40,40)"));
+            let options = self.settings.render_options();
+            // Clear the canvas, the grid draws its own background on top
+            ctx.set_fill_style_str(options.theme.outside);
             ctx.fill_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
             // Draw the current automaton
-            self.automaton.draw(ctx);
+            self.automaton.draw(ctx, &options);
+            if let Some(region) = self.selection {
+                self.automaton
+                    .draw_region(ctx, region, options.theme.palette.yellow);
+            }
+            if let (Some(offsets), Some(cell)) = (stamp, self.hover) {
+                self.automaton
+                    .draw_stamp_preview(ctx, &options, cell, &offsets);
+            }
+        }
+    }
+
+    /// The state painted by dragging, the toggled default state if the brush is out of range.
+    fn brush_state(&self) -> A::State {
+        let automaton = self.automaton.automaton();
+        automaton
+            .states()
+            .into_iter()
+            .nth(self.settings.brush())
+            .unwrap_or_else(|| automaton.toggle(A::State::default()))
+    }
+
+    /// Offsets of the pasted or selected pattern in its orientation, stamped by clicking.
+    fn stamp_offsets(&self) -> Option<Vec<(isize, isize)>> {
+        let offsets = match (&self.paste, self.selected_pattern) {
+            (Some(paste), _) => paste.as_slice(),
+            (None, Some(idx)) => pattern::PATTERNS[idx].1,
+            (None, None) => return None,
+        };
+        Some(self.stamp_orientation.apply(offsets))
+    }
+
+    /// Replace the grid with the pattern in `text` and fit it into the view,
+    /// or show the parse error.
+    ///
+    /// The rule stored in the pattern is applied as well, if the automaton has one.
+    fn import_pattern(&mut self, format: pattern::Format, text: &str) {
+        match pattern::parse_as(format, text) {
+            Ok(cells) => {
+                self.error = None;
+                self.automaton.load_alive(&cells);
+                if let Some(canvas) = &self.canvas {
+                    self.automaton.reset_zoom(canvas.width(), canvas.height());
+                }
+                let rule = pattern::rule_of(format, text);
+                if let (Some(rule), Some(_)) = (rule, self.automaton.automaton().rule()) {
+                    self.update(Msg::SetRule(rule));
+                }
+                self.pattern_meta = pattern::meta_of(format, text);
+                self.link.send_message(Msg::Redraw);
+            }
+            Err(e) => self.error = Some(format!("Invalid pattern: {}", e)),
+        }
+    }
+
+    /// Read the names in the pattern library, reported by [`Msg::LibraryListed`].
+    fn refresh_library(link: ComponentLink<Self>) {
+        library::list(automaton_name(), move |names| {
+            link.send_message(Msg::LibraryListed(names))
+        });
+    }
+
+    /// Refresh the library list after a change, or report why it failed.
+    fn library_changed(link: ComponentLink<Self>) -> impl FnOnce(Result<(), String>) {
+        move |result| match result {
+            Ok(()) => Self::refresh_library(link),
+            Err(e) => link.send_message(Msg::LibraryListed(Err(e))),
+        }
+    }
+
+    /// Add the current grid to the recording, and download it once complete.
+    fn record_frame(&mut self) {
+        let recording = match &mut self.recording {
+            Some(recording) => recording,
+            None => return,
+        };
+        let options = self.settings.render_options();
+        if let Err(e) = recording.capture(&self.automaton, &options) {
+            self.recording = None;
+            self.error = Some(format!("Recording stopped: {}", e));
+            return;
+        }
+        if recording.is_complete() {
+            self.finish_recording();
+        }
+    }
+
+    /// Stop recording and download the frames recorded so far.
+    fn finish_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            if let Err(e) = recording.finish(self.settings.tick_ms()) {
+                self.error = Some(format!("Failed to save the recording: {}", e));
+            }
+        }
+    }
+
+    /// Animate the view towards the result of `change`, called with the canvas size.
+    ///
+    /// During an animation, `change` applies to its target instead of the
+    /// current view, so that quick successive zooms add up, and the new
+    /// animation starts at the current view.
+    fn animate_view(&mut self, change: impl FnOnce(&mut Supervisor<A>, u32, u32)) {
+        let (width, height) = match &self.canvas {
+            Some(canvas) => (canvas.width(), canvas.height()),
+            None => return,
+        };
+        let current = (self.automaton.trans, self.automaton.scale);
+        if let Some(animation) = &self.animation {
+            (self.automaton.trans, self.automaton.scale) = animation.target();
+        }
+        change(&mut self.automaton, width, height);
+        let target = (self.automaton.trans, self.automaton.scale);
+        (self.automaton.trans, self.automaton.scale) = current;
+        self.animation = Some(ViewAnimation::new(current, target, js_sys::Date::now()));
+        self.link.send_message(Msg::Redraw);
+    }
+
+    /// Keep part of the grid on the canvas, if enabled in the settings.
+    fn clamp_pan(&mut self) {
+        if let (true, Some(canvas)) = (self.settings.clamp_pan(), &self.canvas) {
+            self.automaton.clamp_pan(canvas.width(), canvas.height());
+        }
+    }
+
+    /// Persist the state after [`SAVE_DELAY_MS`], unless a save is already pending.
+    ///
+    /// The pending save is not postponed, so that the state is saved
+    /// regularly even while auto-running.
+    fn schedule_save(&mut self) {
+        if self.save_timer.is_none() {
+            let link = self.link.clone();
+            self.save_timer = Some(Timeout::new(SAVE_DELAY_MS, move || {
+                link.send_message(Msg::Save)
+            }));
+        }
+    }
+
+    /// (Re-)create the render timer according to the current settings.
+    ///
+    /// Any previous timer is cancelled, no timer is created while not auto-running.
+    fn restart_timer(&mut self) {
+        if let Some(interval) = self.render_timer.take() {
+            interval.cancel();
+        }
+        if self.settings.auto_run() {
+            let link = self.link.clone();
+            self.render_timer = Some(Interval::new(self.settings.tick_ms(), move || {
+                link.send_message(Msg::Update)
+            }));
         }
     }
 }
 
-impl<A: Automaton + 'static> Component for Model<A> {
+impl<A: Automaton + Default + 'static> Component for Model<A> {
     type Message = Msg;
     type Properties = ();
 
     fn create(_props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut automaton = Supervisor::new(A::default(), DEFAULT_GRID_SIZE, DEFAULT_GRID_SIZE);
+        let keydown_link = link.clone();
+        let frame_link = link.clone();
+        let name = automaton_name();
+        let (settings, view) = if let Some(saved) = storage::load(name) {
+            if let Some(rule) = saved.rule {
+                if let Err(e) = automaton.automaton_mut().set_rule(&rule) {
+                    console_log!("Ignoring the saved rule", rule, e.to_string());
+                }
+            }
+            automaton.load_grid(saved.cells);
+            (saved.settings, saved.view)
+        } else if let Some(saved) = storage::load_legacy().filter(|_| name == DEFAULT_AUTOMATON) {
+            // Older versions only stored the live cells for all automata
+            automaton.load_alive(&saved.cells);
+            (saved.settings, saved.view)
+        } else {
+            (Settings::default(), None)
+        };
+        if let Some((trans, scale)) = view {
+            automaton.trans = trans;
+            automaton.scale = Scale::Manual(scale);
+        }
+        // A shared grid takes precedence over the saved one
+        if let Some(shared) = share::from_location() {
+            automaton.load_alive(&shared.cells);
+            automaton.set_seed(shared.seed);
+            // The saved view belongs to the saved grid
+            automaton.scale = Scale::Auto(1.0);
+            if let Some(rule) = shared.rule {
+                if let Err(e) = automaton.automaton_mut().set_rule(&rule) {
+                    console_log!("Ignoring the shared rule", rule, e.to_string());
+                }
+            }
+        }
         Self {
             link: link.clone(),
             canvas_ref: NodeRef::default(),
             canvas: None,
             context: None,
             resize_callback: Closure::wrap(Box::from(move || link.send_message(Msg::Resized))),
-            automaton: Supervisor::new(20, 20),
-            last_mouse_click: None,
-            settings: Settings::default(),
+            keydown_callback: Closure::wrap(Box::from(move |ev| {
+                keydown_link.send_message(Msg::KeyDown(ev))
+            })),
+            frame_callback: Closure::wrap(Box::from(move || {
+                frame_link.send_message(Msg::AnimationFrame)
+            })),
+            frame_request: None,
+            animation: None,
+            automaton,
+            drag: None,
+            gesture: None,
+            hover: None,
+            settings,
             render_timer: None,
+            save_timer: None,
+            selected_pattern: None,
+            paste: None,
+            stamp_orientation: Orientation::default(),
+            selection: None,
+            pattern_meta: PatternMeta::default(),
+            error: None,
+            recording: None,
         }
     }
 
@@ -100,8 +561,16 @@ impl<A: Automaton + 'static> Component for Model<A> {
                 .unwrap();
             // Add resize handler to document
             setResizeHandler(CANVAS_ID, &self.resize_callback, 1500);
+            // Add keyboard shortcuts
+            if let Some(window) = web_sys::window() {
+                let callback = self.keydown_callback.as_ref().unchecked_ref();
+                if let Err(e) = window.add_event_listener_with_callback("keydown", callback) {
+                    console_log!("Failed to add keyboard shortcuts", e);
+                }
+            }
             // Initial resize
             self.link.send_message(Msg::Resized);
+            Self::refresh_library(self.link.clone());
 
             self.canvas = Some(canvas);
             self.context = Some(context);
@@ -111,55 +580,271 @@ impl<A: Automaton + 'static> Component for Model<A> {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::Redraw => {
-                self.draw();
+                // Coalesce all redraws until the next frame into one
+                if self.frame_request.is_none() {
+                    let callback = self.frame_callback.as_ref().unchecked_ref();
+                    match web_sys::window().map(|w| w.request_animation_frame(callback)) {
+                        Some(Ok(handle)) => self.frame_request = Some(handle),
+                        _ => console_log!("Failed to request an animation frame"),
+                    }
+                }
                 false
             }
+            Msg::AnimationFrame => {
+                self.frame_request = None;
+                if let Some(animation) = self.animation {
+                    let now = js_sys::Date::now();
+                    let (trans, scale) = animation.view_at(now);
+                    self.automaton.trans = trans;
+                    self.automaton.scale = scale;
+                    if animation.is_finished(now) {
+                        self.animation = None;
+                    } else {
+                        self.link.send_message(Msg::Redraw);
+                    }
+                }
+                self.draw();
+                self.schedule_save();
+                // Refresh the statistics overlay
+                true
+            }
             Msg::MouseDown(ev) => {
-                self.last_mouse_click = Some(Point2::from([ev.client_x(), ev.client_y()]));
+                if ev.button() == 1 || ev.shift_key() {
+                    // Middle or shift drag pans
+                    ev.prevent_default();
+                    self.drag = Some(Drag::Pan(Point2::from([ev.client_x(), ev.client_y()])));
+                    false
+                } else if ev.button() == 0 && self.settings.select() {
+                    let cell = self.automaton.cell_at(mouse_position(&ev));
+                    self.selection = self.automaton.clip_region((cell.0, cell.1, cell.0, cell.1));
+                    self.drag = Some(Drag::Select { start: cell });
+                    self.link.send_message(Msg::Redraw);
+                    false
+                } else if ev.button() == 0 && self.settings.place_agents() {
+                    let (x, y) = self.automaton.cell_at(mouse_position(&ev));
+                    self.automaton.toggle_agent(x, y);
+                    self.link.send_message(Msg::Redraw);
+                    true
+                } else if ev.button() == 0 && self.settings.drop_sand() {
+                    let (x, y) = self.automaton.cell_at(mouse_position(&ev));
+                    // All grains dropped while the button is held are undone together
+                    self.automaton.checkpoint();
+                    self.automaton.drop_sand(x, y);
+                    self.drag = Some(Drag::Sand { cell: (x, y) });
+                    self.link.send_message(Msg::Redraw);
+                    true
+                } else if let (0, Some(offsets)) = (ev.button(), self.stamp_offsets()) {
+                    let origin = self.automaton.cell_at(mouse_position(&ev));
+                    self.automaton.stamp(origin, &offsets);
+                    self.link.send_message(Msg::Redraw);
+                    true
+                } else if ev.button() == 0 || ev.button() == 2 {
+                    let (x, y) = self.automaton.cell_at(mouse_position(&ev));
+                    if ev.button() == 0 && ev.ctrl_key() {
+                        // Cycle through the states of multi-state automata
+                        self.automaton.toggle(x, y);
+                    } else {
+                        // Left paints the selected state, right erases
+                        let state = match ev.button() {
+                            0 => self.brush_state(),
+                            _ => A::State::default(),
+                        };
+                        self.automaton.checkpoint();
+                        self.automaton.set(x, y, state);
+                    }
+                    self.drag = self.automaton.get(x, y).map(|state| Drag::Paint {
+                        state: state.clone(),
+                        last: (x, y),
+                    });
+                    self.link.send_message(Msg::Redraw);
+                    true
+                } else {
+                    false
+                }
+            }
+            Msg::MouseMove(ev) => {
+                if let Some(Drag::Select { start }) = self.drag {
+                    let cell = self.automaton.cell_at(mouse_position(&ev));
+                    let selection = self
+                        .automaton
+                        .clip_region((start.0, start.1, cell.0, cell.1));
+                    if mem::replace(&mut self.selection, selection) != selection {
+                        self.link.send_message(Msg::Redraw);
+                    }
+                }
+                if let Some(Drag::Sand { cell }) = &mut self.drag {
+                    *cell = self.automaton.cell_at(mouse_position(&ev));
+                }
+                if let Some(Drag::Paint { state, last }) = &mut self.drag {
+                    let cell = self.automaton.cell_at(mouse_position(&ev));
+                    if cell != *last {
+                        for (x, y) in line_cells(*last, cell).into_iter().skip(1) {
+                            self.automaton.set(x, y, state.clone());
+                        }
+                        *last = cell;
+                        self.link.send_message(Msg::Redraw);
+                    }
+                }
+                // Only the overlay changes, the canvas is redrawn separately
+                let hover = self.automaton.cell_under(mouse_position(&ev));
+                let moved = mem::replace(&mut self.hover, hover) != hover;
+                if moved && (self.selected_pattern.is_some() || self.paste.is_some()) {
+                    // Move the pattern preview along
+                    self.link.send_message(Msg::Redraw);
+                }
+                moved
+            }
+            Msg::MouseLeave(ev) => {
+                self.hover = None;
+                self.link.send_message(Msg::Redraw);
+                self.update(Msg::MouseUp(ev));
+                true
+            }
+            Msg::TouchStart(ev) => {
+                ev.prevent_default();
+                self.gesture = Gesture::from_touches(&touch_positions(&ev));
+                false
+            }
+            Msg::TouchMove(ev) => {
+                ev.prevent_default();
+                match (&mut self.gesture, touch_positions(&ev).as_slice()) {
+                    (Some(Gesture::Drag { start, last, moved }), &[pos]) => {
+                        *moved |= (pos - *start).norm() > TAP_RADIUS_PX;
+                        if *moved {
+                            let delta = pos - *last;
+                            self.link.send_message(Msg::Pan(delta.x, delta.y));
+                        }
+                        *last = pos;
+                    }
+                    (Some(Gesture::Pinch { distance, center }), &[a, b, ..]) => {
+                        let (new_distance, new_center) = ((a - b).norm(), nalgebra::center(&a, &b));
+                        let delta = new_center - *center;
+                        let zoom = pinch_delta(self.automaton.scale.raw(), *distance, new_distance);
+                        *distance = new_distance;
+                        *center = new_center;
+                        self.automaton.zoom_at(new_center, zoom);
+                        self.link.send_message(Msg::Pan(delta.x, delta.y));
+                    }
+                    _ => {}
+                }
+                false
+            }
+            Msg::TouchEnd(ev) => {
+                ev.prevent_default();
+                let touches = touch_positions(&ev);
+                match self.gesture.take() {
+                    Some(Gesture::Drag {
+                        start,
+                        moved: false,
+                        ..
+                    }) if touches.is_empty() => {
+                        let (x, y) = self.automaton.cell_at(start);
+                        if !(self.settings.place_agents() && self.automaton.toggle_agent(x, y)) {
+                            self.automaton.toggle(x, y);
+                        }
+                        self.link.send_message(Msg::Redraw);
+                    }
+                    // A finger lifted mid-pinch continues as a drag without toggling
+                    Some(Gesture::Pinch { .. }) => {
+                        self.gesture =
+                            Gesture::from_touches(&touches).map(|gesture| match gesture {
+                                Gesture::Drag { start, last, .. } => Gesture::Drag {
+                                    start,
+                                    last,
+                                    moved: true,
+                                },
+                                pinch => pinch,
+                            });
+                    }
+                    _ => self.gesture = Gesture::from_touches(&touches),
+                }
                 false
             }
             Msg::Update => {
+                if let Some(Drag::Sand { cell: (x, y) }) = self.drag {
+                    self.automaton.drop_sand(x, y);
+                }
                 self.automaton.update();
+                self.record_frame();
+                if self.settings.pause_when_stable() && self.automaton.stability().is_some() {
+                    self.link.send_message(Msg::Pause);
+                }
+                // The statistics are refreshed with the next frame
                 self.link.send_message(Msg::Redraw);
                 false
             }
+            Msg::StepForward => {
+                self.automaton.step_forward();
+                self.record_frame();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::StepBack => {
+                self.automaton.step_back();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::Undo => {
+                self.automaton.undo();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::Redo => {
+                self.automaton.redo();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::KeyDown(ev) => {
+                if is_typing(&ev) {
+                    return false;
+                }
+                let ctrl = ev.ctrl_key() || ev.meta_key();
+                if let Some(msg) =
+                    shortcut(&ev.key(), ctrl, ev.shift_key(), self.settings.density())
+                {
+                    ev.prevent_default();
+                    self.link.send_message(msg);
+                }
+                false
+            }
+            Msg::Rewind => {
+                self.automaton.rewind();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::FastForward => {
+                self.automaton.fast_forward();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
             Msg::MouseUp(ev) => {
-                if let Some(from) = self.last_mouse_click {
+                if let Some(Drag::Pan(from)) = self.drag.take() {
                     let to = Point2::from([ev.client_x(), ev.client_y()]);
-                    let diff = to - from;
-                    if diff.x.abs() <= MIN_DRAG.x && diff.y.abs() <= MIN_DRAG.y {
-                        // Not a drag, just a click
-                        let pos = self.automaton.from_screen_coordinates(Point2::from([
-                            ev.client_x() as f64,
-                            ev.client_y() as f64,
-                        ]));
-                        let x = pos.x as isize / CELL_WIDTH as isize;
-                        let y = pos.y as isize / CELL_WIDTH as isize;
-                        self.automaton.toggle(x, y);
-                        self.link.send_message(Msg::Redraw);
-                        false
-                    } else {
-                        self.automaton.trans = Translation2::from([
-                            diff.x as f64 + self.automaton.trans.x,
-                            diff.y as f64 + self.automaton.trans.y,
-                        ]);
-                        self.link.send_message(Msg::Redraw);
-                        false
-                    }
-                } else {
-                    false
+                    let delta = self.settings.pan_delta((to - from).cast());
+                    self.animation = None;
+                    self.automaton.trans = Translation2::from([
+                        delta.x + self.automaton.trans.x,
+                        delta.y + self.automaton.trans.y,
+                    ]);
+                    self.clamp_pan();
+                    self.link.send_message(Msg::Redraw);
                 }
+                false
             }
             Msg::Scroll(ev) => {
-                let mouse = Point2::from([ev.client_x() as f64, ev.client_y() as f64]);
-                let orig_pos = self.automaton.from_screen_coordinates(mouse);
-                self.automaton.scale =
-                    Scale::Manual(self.automaton.scale.raw() + 0.001 * ev.delta_y());
-                self.automaton.scale = Scale::Manual(self.automaton.scale.raw().max(0.0));
-                let trans_adj = mouse / self.automaton.scale.raw() - orig_pos;
-                self.automaton.trans.x = trans_adj.x;
-                self.automaton.trans.y = trans_adj.y;
-                console_log!(&ev, self.automaton.scale.raw());
+                self.animate_view(|supervisor, width, height| {
+                    supervisor.zoom_at(mouse_position(&ev), 0.001 * ev.delta_y());
+                    console_log!(&ev, supervisor.scale.raw());
+                    supervisor.clamp_pan(width, height);
+                });
+                false
+            }
+            Msg::Pan(dx, dy) => {
+                self.animation = None;
+                let scale = self.automaton.scale.raw();
+                self.automaton.trans.x += dx / scale;
+                self.automaton.trans.y += dy / scale;
+                self.clamp_pan();
                 self.link.send_message(Msg::Redraw);
                 false
             }
@@ -167,6 +852,7 @@ impl<A: Automaton + 'static> Component for Model<A> {
                 if let (Scale::Auto(_), Some(canvas)) = (&self.automaton.scale, &self.canvas) {
                     self.automaton.reset_zoom(canvas.width(), canvas.height());
                 }
+                self.clamp_pan();
                 self.link.send_message(Msg::Redraw);
                 false
             }
@@ -174,27 +860,567 @@ impl<A: Automaton + 'static> Component for Model<A> {
                 self.settings.toggle();
                 true
             }
+            Msg::ToggleFullscreen => {
+                // The canvas is resized by the `fullscreenchange` handler
+                // registered with `setResizeHandler`
+                let document = web_sys::window().and_then(|window| window.document());
+                match (document, &self.canvas) {
+                    (Some(document), _) if document.fullscreen_element().is_some() => {
+                        document.exit_fullscreen()
+                    }
+                    (Some(document), _) if !document.fullscreen_enabled() => {
+                        console_log!("Fullscreen is not available")
+                    }
+                    (_, Some(canvas)) => {
+                        if let Err(e) = canvas.request_fullscreen() {
+                            console_log!("Failed to enter fullscreen", e);
+                        }
+                    }
+                    _ => {}
+                }
+                false
+            }
             Msg::ToggleAutoRun => {
-                self.settings.toggle_auto_run();
-                if self.settings.auto_run() {
-                    let link = self.link.clone();
-                    self.render_timer = Some(Interval::new(TIME_BETWEEN_RENDERS_MS, move || {
-                        link.send_message(Msg::Update)
-                    }));
+                let msg = if self.settings.auto_run() {
+                    Msg::Pause
                 } else {
-                    if let Some(interval) = self.render_timer.take() {
-                        interval.cancel();
-                    }
+                    Msg::Play
+                };
+                self.update(msg)
+            }
+            Msg::Play => {
+                // Only restart on change, so that there is never more than one timer
+                if self.settings.set_auto_run(true) {
+                    self.restart_timer();
+                }
+                true
+            }
+            Msg::Pause => {
+                if self.settings.set_auto_run(false) {
+                    self.restart_timer();
+                }
+                true
+            }
+            Msg::SetSpeed(tick_ms) => {
+                self.settings.set_tick_ms(tick_ms);
+                self.restart_timer();
+                self.schedule_save();
+                true
+            }
+            Msg::ToggleGrid => {
+                self.settings.toggle_grid();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::ToggleHeatmap => {
+                self.settings.toggle_heatmap();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::ToggleNeighborCounts => {
+                self.settings.toggle_neighbor_counts();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::SetCellWidth(cell_width) => {
+                self.automaton.set_cell_width(cell_width);
+                if let Some(canvas) = &self.canvas {
+                    self.automaton.reset_zoom(canvas.width(), canvas.height());
+                }
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::SetTheme(idx) => {
+                self.settings.set_theme(idx);
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::SelectAutomaton(name) => {
+                self.update(Msg::Save);
+                let url = format!("?automaton={}", name);
+                if let Err(e) = web_sys::window().ok_or("No window").and_then(|window| {
+                    window
+                        .location()
+                        .assign(&url)
+                        .map_err(|_| "Navigation failed")
+                }) {
+                    console_log!("Failed to switch the automaton", e);
+                }
+                false
+            }
+            Msg::ToggleExpanding => {
+                self.automaton.set_expanding(!self.automaton.expanding());
+                true
+            }
+            Msg::SetTopology(topology) => {
+                self.automaton.set_topology(topology);
+                true
+            }
+            Msg::TogglePauseWhenStable => {
+                self.settings.toggle_pause_when_stable();
+                true
+            }
+            Msg::ToggleSelect => {
+                self.settings.toggle_select();
+                if !self.settings.select() {
+                    self.update(Msg::ClearSelection);
+                }
+                true
+            }
+            Msg::Transform(transform) => {
+                if self.selection.is_none()
+                    && (self.selected_pattern.is_some() || self.paste.is_some())
+                {
+                    let orientation = self.stamp_orientation;
+                    self.stamp_orientation = match transform {
+                        Transform::RotateCw => orientation.rotated_cw(),
+                        Transform::RotateCcw => orientation.rotated_cw().rotated_cw().rotated_cw(),
+                        Transform::FlipH => orientation.flipped_h(),
+                        Transform::FlipV => orientation.flipped_h().rotated_cw().rotated_cw(),
+                    };
+                    self.link.send_message(Msg::Redraw);
+                } else if let Some(region) = self.selection {
+                    self.selection = match transform {
+                        Transform::RotateCw => self.automaton.rotate_cw(region),
+                        Transform::RotateCcw => self.automaton.rotate_ccw(region),
+                        Transform::FlipH => self.automaton.flip_h(region),
+                        Transform::FlipV => self.automaton.flip_v(region),
+                    };
+                    self.link.send_message(Msg::Redraw);
                 }
+                false
+            }
+            Msg::ClearSelection => {
+                self.selection = None;
+                self.paste = None;
+                self.drag = None;
+                self.link.send_message(Msg::Redraw);
+                false
+            }
+            Msg::TogglePlaceAgents => {
+                self.settings.toggle_place_agents();
+                true
+            }
+            Msg::ToggleDropSand => {
+                self.settings.toggle_drop_sand();
+                true
+            }
+            Msg::ToggleCellShape => {
+                self.settings.toggle_cell_shape();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::ToggleNaturalPan => {
+                self.settings.toggle_natural_pan();
+                self.schedule_save();
+                true
+            }
+            Msg::ToggleClampPan => {
+                self.settings.toggle_clamp_pan();
+                self.clamp_pan();
+                self.link.send_message(Msg::Redraw);
                 true
             }
             Msg::ResetZoom => {
+                self.animate_view(Supervisor::reset_zoom);
+                false
+            }
+            Msg::FitToPattern => {
+                self.animate_view(Supervisor::fit_to_pattern);
+                false
+            }
+            Msg::Clear => {
+                self.automaton.clear();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::ExportPng => {
+                let options = self.settings.render_options();
+                let width = self.settings.image_width();
+                if let Err(e) = export::export_png(&self.automaton, &options, width) {
+                    console_log!("Failed to export image", e);
+                }
+                false
+            }
+            Msg::ExportSvg => {
+                let options = self.settings.render_options();
+                let width = self.settings.image_width();
+                if let Err(e) = export::export_svg(&self.automaton, &options, width) {
+                    console_log!("Failed to export SVG", e);
+                }
+                false
+            }
+            Msg::SetImageWidth(width) => {
+                self.settings.set_image_width(width);
+                true
+            }
+            Msg::StartRecording(frames) => {
+                let cell_px = self.settings.record_cell_px();
+                let recording = match self.settings.record_format() {
+                    RecordFormat::Gif => Recording::new(&self.automaton, frames, cell_px),
+                    RecordFormat::WebM => Recording::webm(&self.automaton, frames, cell_px)
+                        .map_err(|e| format!("{:?}", e)),
+                };
+                match recording {
+                    Ok(recording) => {
+                        self.recording = Some(recording);
+                        // Start with the current generation
+                        self.record_frame();
+                    }
+                    Err(e) => self.error = Some(format!("Can't record: {}", e)),
+                }
+                true
+            }
+            Msg::StopRecording => {
+                self.finish_recording();
+                true
+            }
+            Msg::SetRecordFormat(format) => {
+                self.settings.set_record_format(format);
+                true
+            }
+            Msg::SetRecordFrames(frames) => {
+                self.settings.set_record_frames(frames);
+                true
+            }
+            Msg::SetRecordCellPx(cell_px) => {
+                self.settings.set_record_cell_px(cell_px);
+                true
+            }
+            Msg::ExportPattern => {
+                let text = pattern::to_life106(&self.automaton.alive_grid());
+                self.settings.set_pattern_text(text);
+                true
+            }
+            Msg::DownloadPattern(format) => {
+                let rule = self.automaton.automaton().rule();
+                let grid = self.automaton.alive_grid();
+                let text =
+                    pattern::serialize_as(format, &grid, rule.as_deref(), &self.pattern_meta);
+                let filename = format!("pattern.{}", format.extension());
+                if let Err(e) = export::download_bytes(text.as_bytes(), "text/plain", &filename) {
+                    console_log!("Failed to export the pattern", e);
+                }
+                self.settings.set_pattern_text(text);
+                true
+            }
+            Msg::ImportPattern => {
+                let text = self.settings.pattern_text().to_owned();
+                self.import_pattern(pattern::Format::detect(None, &text), &text);
+                true
+            }
+            Msg::DropFile(ev) => {
+                ev.prevent_default();
+                let file = ev
+                    .data_transfer()
+                    .and_then(|data| data.files())
+                    .and_then(|files| files.get(0));
+                if let Some(file) = file {
+                    let cell = self.automaton.cell_at(mouse_position(&ev));
+                    let load = move |name, text| Msg::StampPatternFile(cell, name, text);
+                    if let Err(e) = read_file(&file, self.link.clone(), load) {
+                        console_log!("Failed to read the dropped file", e);
+                    }
+                }
+                false
+            }
+            Msg::OpenFile(file) => {
+                if let Err(e) = read_file(&file, self.link.clone(), Msg::LoadPatternFile) {
+                    console_log!("Failed to read the chosen file", e);
+                }
+                false
+            }
+            Msg::LoadPatternFile(name, text) => {
+                let format = pattern::Format::detect(Some(&name), &text);
+                self.import_pattern(format, &text);
+                true
+            }
+            Msg::StampPatternFile(cell, name, text) => {
+                let format = pattern::Format::detect(Some(&name), &text);
+                match pattern::parse_as(format, &text) {
+                    Ok(cells) => {
+                        self.error = None;
+                        self.automaton.stamp(cell, &pattern::alive_offsets(&cells));
+                        self.link.send_message(Msg::Redraw);
+                    }
+                    Err(e) => self.error = Some(format!("Invalid pattern: {}", e)),
+                }
+                true
+            }
+            Msg::DismissError => {
+                self.error = None;
+                true
+            }
+            Msg::DismissPatternMeta => {
+                self.pattern_meta = PatternMeta::default();
+                true
+            }
+            Msg::SetPatternText(text) => {
+                self.settings.set_pattern_text(text);
+                false
+            }
+            Msg::SetLibraryName(name) => {
+                self.settings.set_library_name(name);
+                true
+            }
+            Msg::SaveToLibrary => {
+                let name = self.settings.library_name().trim().to_owned();
+                if name.is_empty() {
+                    return false;
+                }
+                let rule = self.automaton.automaton().rule();
+                match library::serialize(self.automaton.grid(), rule.as_deref()) {
+                    Ok(pattern) => library::save(
+                        automaton_name(),
+                        &name,
+                        &pattern,
+                        Self::library_changed(self.link.clone()),
+                    ),
+                    Err(e) => {
+                        self.error = Some(format!("Pattern library: {}", e));
+                        return true;
+                    }
+                }
+                false
+            }
+            Msg::LoadFromLibrary(name) => {
+                let link = self.link.clone();
+                library::load(automaton_name(), &name, move |text| {
+                    link.send_message(Msg::LibraryLoaded(text))
+                });
+                false
+            }
+            Msg::DeleteFromLibrary(name) => {
+                library::delete(
+                    automaton_name(),
+                    &name,
+                    Self::library_changed(self.link.clone()),
+                );
+                false
+            }
+            Msg::LibraryListed(Ok(names)) => {
+                self.settings.set_library(names);
+                true
+            }
+            Msg::LibraryLoaded(Ok(text)) => {
+                match library::deserialize(&text) {
+                    Some(Loaded::Pattern { cells, rule }) => {
+                        if let Some(rule) = rule {
+                            if let Err(e) = self.automaton.automaton_mut().set_rule(&rule) {
+                                console_log!("Ignoring the pattern's rule", rule, e.to_string());
+                            }
+                        }
+                        self.automaton.load_grid(cells);
+                    }
+                    Some(Loaded::Legacy(cells)) => self.automaton.load_alive(&cells),
+                    None => {
+                        self.error = Some("Pattern library: the pattern is corrupt".to_owned());
+                        return true;
+                    }
+                }
+                self.error = None;
                 if let Some(canvas) = &self.canvas {
                     self.automaton.reset_zoom(canvas.width(), canvas.height());
+                }
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::LibraryListed(Err(e)) | Msg::LibraryLoaded(Err(e)) => {
+                self.error = Some(format!("Pattern library: {}", e));
+                true
+            }
+            Msg::Copy => {
+                let cells = match self.selection {
+                    Some(region) => self.automaton.alive_region(region),
+                    None => self.automaton.alive_grid(),
+                };
+                let rle = pattern::to_rle(&cells, self.automaton.automaton().rule().as_deref());
+                let link = self.link.clone();
+                clipboard::write_text(rle, move |result| {
+                    if let Err(e) = result {
+                        link.send_message(Msg::Pasted(Err(e)));
+                    }
+                });
+                false
+            }
+            Msg::Paste => {
+                let link = self.link.clone();
+                clipboard::read_text(move |text| link.send_message(Msg::Pasted(text)));
+                false
+            }
+            Msg::Pasted(text) => {
+                match text.and_then(|text| pattern::parse(&text)) {
+                    Ok(cells) => {
+                        self.error = None;
+                        self.paste = Some(pattern::alive_offsets(&cells));
+                        self.selected_pattern = None;
+                        self.stamp_orientation = Orientation::default();
+                        self.link.send_message(Msg::Redraw);
+                    }
+                    Err(e) => self.error = Some(format!("Clipboard: {}", e)),
+                }
+                true
+            }
+            Msg::SetCatalogueUrl(url) => {
+                self.settings.set_catalogue_url(url);
+                false
+            }
+            Msg::LoadCatalogue => {
+                let link = self.link.clone();
+                let url = self.settings.catalogue_url().trim().to_owned();
+                catalogue::fetch_text(url, move |listing| {
+                    let entries = listing.map(|listing| catalogue::entries(&listing));
+                    link.send_message(Msg::CatalogueLoaded(entries))
+                });
+                false
+            }
+            Msg::CatalogueLoaded(Ok(entries)) => {
+                if entries.is_empty() {
+                    self.error = Some("Catalogue: no RLE files found".to_owned());
+                }
+                self.settings.set_catalogue(entries);
+                true
+            }
+            Msg::SetCatalogueQuery(query) => {
+                self.settings.set_catalogue_query(query);
+                true
+            }
+            Msg::PreviewCataloguePattern(entry) => {
+                let link = self.link.clone();
+                let name = catalogue::name(&entry).to_owned();
+                match catalogue::resolve(&entry, self.settings.catalogue_url().trim()) {
+                    Ok(url) => catalogue::fetch_text(url, move |text| {
+                        link.send_message(Msg::CataloguePatternLoaded(name, text))
+                    }),
+                    Err(e) => link.send_message(Msg::CatalogueLoaded(Err(e))),
+                }
+                false
+            }
+            Msg::CataloguePatternLoaded(name, text) => {
+                let cells =
+                    text.and_then(|text| pattern::from_rle(&text).map_err(|e| e.to_string()));
+                let preview = cells.and_then(|cells| {
+                    let options = self.settings.render_options();
+                    let thumbnail = export::thumbnail(&cells, &options, THUMBNAIL_PX)
+                        .map_err(describe_error)?;
+                    Ok(CataloguePreview {
+                        name,
+                        cells,
+                        thumbnail,
+                    })
+                });
+                match preview {
+                    Ok(preview) => self.settings.set_catalogue_preview(Some(preview)),
+                    Err(e) => self.error = Some(format!("Catalogue: {}", e)),
+                }
+                true
+            }
+            Msg::CatalogueLoaded(Err(e)) => {
+                self.error = Some(format!("Catalogue: {}", e));
+                true
+            }
+            Msg::StampCataloguePattern => {
+                if let Some(preview) = self.settings.catalogue_preview() {
+                    self.paste = Some(pattern::alive_offsets(&preview.cells));
+                    self.selected_pattern = None;
+                    self.stamp_orientation = Orientation::default();
                     self.link.send_message(Msg::Redraw);
                 }
+                true
+            }
+            Msg::SetBrush(brush) => {
+                self.settings.set_brush(brush);
+                true
+            }
+            Msg::SelectPattern(selected) => {
+                self.selected_pattern = selected;
+                self.paste = None;
+                self.stamp_orientation = Orientation::default();
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::Save => {
+                self.save_timer = None;
+                let cells = self.automaton.alive_grid();
+                let view = match self.automaton.scale {
+                    Scale::Manual(scale) => Some((self.automaton.trans, scale)),
+                    Scale::Auto(_) => None,
+                };
+                let rule = self.automaton.automaton().rule();
+                let saved = storage::save(
+                    automaton_name(),
+                    &self.settings,
+                    self.automaton.grid(),
+                    rule.as_deref(),
+                    view,
+                );
+                if let Err(e) = saved {
+                    console_log!("Failed to save state", e);
+                }
+                let seed = self.automaton.seed();
+                if let Err(e) = share::to_location(&cells, rule.as_deref(), seed) {
+                    console_log!("Failed to update the URL", e);
+                }
                 false
             }
+            Msg::ResetDefaults => {
+                if let Err(e) = storage::clear(automaton_name()) {
+                    console_log!("Failed to clear saved state", e);
+                }
+                // Keep the menu open
+                self.settings = Settings::default();
+                self.settings.toggle();
+                self.automaton =
+                    Supervisor::new(A::default(), DEFAULT_GRID_SIZE, DEFAULT_GRID_SIZE);
+                self.restart_timer();
+                self.link.send_message(Msg::ResetZoom);
+                true
+            }
+            Msg::Resize(width, height) => {
+                if let Err(e) = self.automaton.resize(width.max(1), height.max(1)) {
+                    self.error = Some(format!("Can't resize: {}", e));
+                    return true;
+                }
+                self.error = None;
+                if let Some(canvas) = &self.canvas {
+                    self.automaton.reset_zoom(canvas.width(), canvas.height());
+                }
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::Randomize(density) => {
+                let seed = match self.settings.seed() {
+                    Ok(seed) => seed.unwrap_or_else(|| js_sys::Date::now() as u64),
+                    Err(e) => {
+                        self.error = Some(format!("Invalid seed: {}", e));
+                        return true;
+                    }
+                };
+                self.automaton.randomize(density, seed);
+                self.link.send_message(Msg::Redraw);
+                true
+            }
+            Msg::SetDensity(density) => {
+                self.settings.set_density(density);
+                self.schedule_save();
+                true
+            }
+            Msg::SetSeedText(text) => {
+                self.settings.set_seed_text(text);
+                true
+            }
+            Msg::SetRule(rule) => {
+                let result = self.automaton.automaton_mut().set_rule(&rule);
+                self.settings
+                    .set_invalid_rule(result.err().map(|e| (rule, e.to_string())));
+                true
+            }
+            Msg::SetTotalistic(rule) => self.update(Msg::SetRule(rule.to_string())),
+            Msg::SetParameter(idx, value) => {
+                self.automaton.automaton_mut().set_parameter(idx, value);
+                self.settings.set_invalid_rule(None);
+                true
+            }
         }
     }
 
@@ -205,22 +1431,315 @@ impl<A: Automaton + 'static> Component for Model<A> {
         false
     }
 
+    fn destroy(&mut self) {
+        self.animation = None;
+        if let (Some(handle), Some(window)) = (self.frame_request.take(), web_sys::window()) {
+            if let Err(e) = window.cancel_animation_frame(handle) {
+                console_log!("Failed to cancel the animation frame", e);
+            }
+        }
+    }
+
     fn view(&self) -> Html {
         let onmousedown = self.link.callback(Msg::MouseDown);
+        let onmousemove = self.link.callback(Msg::MouseMove);
         let onmouseup = self.link.callback(Msg::MouseUp);
+        let onmouseleave = self.link.callback(Msg::MouseLeave);
         let onwheel = self.link.callback(Msg::Scroll);
+        // Right click erases, so don't show the context menu
+        let oncontextmenu = self.link.batch_callback(|ev: MouseEvent| {
+            ev.prevent_default();
+            None
+        });
+        // Accept dropped files instead of letting the browser open them
+        let ondragover = self.link.batch_callback(|ev: DragEvent| {
+            ev.prevent_default();
+            None
+        });
+        let ondrop = self.link.callback(Msg::DropFile);
+        let ontouchstart = self.link.callback(Msg::TouchStart);
+        let ontouchmove = self.link.callback(Msg::TouchMove);
+        let ontouchend = self.link.callback(Msg::TouchEnd);
+        let ontouchcancel = self.link.callback(Msg::TouchEnd);
+        let dismiss_error = self.link.callback(|_| Msg::DismissError);
+        let dismiss_meta = self.link.callback(|_| Msg::DismissPatternMeta);
         html! {
             <>
                 <canvas ref=self.canvas_ref.clone() id="canvas"
                         onmousedown=onmousedown
+                        onmousemove=onmousemove
                         onmouseup=onmouseup
-                        onwheel=onwheel />
-                { self.settings.html(&self.link) }
+                        onmouseleave=onmouseleave
+                        onwheel=onwheel
+                        oncontextmenu=oncontextmenu
+                        ondragover=ondragover
+                        ondrop=ondrop
+                        ontouchstart=ontouchstart
+                        ontouchmove=ontouchmove
+                        ontouchend=ontouchend
+                        ontouchcancel=ontouchcancel />
+                { self.settings.html(&self.link, &self.automaton, self.selected_pattern,
+                                     self.recording.is_some()) }
+                { transport::html(&self.link, &self.automaton, self.settings.auto_run()) }
+                <div id="stats" class="over">
+                    { if self.settings.auto_run() { "Running · " } else { "Paused · " } }
+                    { format!("Generation {} · Population {}",
+                              self.automaton.generation(), self.automaton.population()) }
+                    {
+                        match self.hover {
+                            Some((x, y)) => format!(" · Cell ({}, {})", x, y),
+                            None => String::new(),
+                        }
+                    }
+                    {
+                        match &self.recording {
+                            Some(recording) => format!(" · Recording {}/{}",
+                                                       recording.len(), recording.frame_count()),
+                            None => String::new(),
+                        }
+                    }
+                    {
+                        match self.automaton.stability() {
+                            Some(period) => format!(" · Stabilized (period {})", period),
+                            None => String::new(),
+                        }
+                    }
+                </div>
+                { stats::population_graph(self.automaton.populations()) }
+                {
+                    if self.pattern_meta.is_empty() {
+                        html! {}
+                    } else {
+                        let meta = &self.pattern_meta;
+                        html! {
+                            <div id="pattern-info" class="over" onclick=dismiss_meta
+                                 title="Click to hide">
+                                <b>{ meta.name.as_deref().unwrap_or("Unnamed pattern") }</b>
+                                {
+                                    match &meta.author {
+                                        Some(author) => format!(" by {}", author),
+                                        None => String::new(),
+                                    }
+                                }
+                                { for meta.comments.iter().map(|line| html! { <p>{ line }</p> }) }
+                            </div>
+                        }
+                    }
+                }
+                {
+                    if let Some(error) = &self.error {
+                        html! {
+                            <div id="error" class="over" onclick=dismiss_error
+                                 title="Click to dismiss">{ error }</div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
             </>
         }
     }
 }
 
+/// Read the text of `file` and send the message created by `load` from its name and text.
+fn read_file<A: Automaton + Default>(
+    file: &File,
+    link: ComponentLink<Model<A>>,
+    load: impl FnOnce(String, String) -> Msg + 'static,
+) -> Result<(), JsValue> {
+    let reader = FileReader::new()?;
+    let name = file.name();
+    let onload = {
+        let reader = reader.clone();
+        Closure::once_into_js(move || match reader.result().map(|text| text.as_string()) {
+            Ok(Some(text)) => link.send_message(load(name, text)),
+            _ => console_log!("Failed to read the file as text"),
+        })
+    };
+    reader.set_onload(Some(onload.unchecked_ref()));
+    reader.read_as_text(file)
+}
+
+/// The message for a keyboard shortcut, if any.
+///
+/// Keep [`SHORTCUT_HELP`] in sync when changing this.
+fn shortcut(key: &str, ctrl: bool, shift: bool, density: f64) -> Option<Msg> {
+    let msg = match (key, ctrl) {
+        ("z", true) => Msg::Undo,
+        ("y", true) | ("Z", true) => Msg::Redo,
+        ("c", true) => Msg::Copy,
+        ("v", true) => Msg::Paste,
+        (" ", false) => Msg::StepForward,
+        ("Enter", false) | ("p", false) => Msg::ToggleAutoRun,
+        ("c", false) => Msg::Clear,
+        ("r", false) => Msg::Randomize(density),
+        ("s", false) => Msg::ToggleSelect,
+        ("t", false) => Msg::Transform(Transform::RotateCw),
+        ("T", false) => Msg::Transform(Transform::RotateCcw),
+        ("h", false) => Msg::Transform(Transform::FlipH),
+        ("v", false) => Msg::Transform(Transform::FlipV),
+        ("Escape", false) => Msg::ClearSelection,
+        ("f", false) => Msg::ToggleFullscreen,
+        // Arrows move the view, i.e. the content moves the other way
+        ("ArrowLeft", false) if shift => Msg::Pan(PAN_STEP_PX, 0.0),
+        ("ArrowRight", false) if shift => Msg::Pan(-PAN_STEP_PX, 0.0),
+        ("ArrowLeft", false) => Msg::StepBack,
+        ("ArrowRight", false) => Msg::StepForward,
+        ("ArrowUp", false) => Msg::Pan(0.0, PAN_STEP_PX),
+        ("ArrowDown", false) => Msg::Pan(0.0, -PAN_STEP_PX),
+        _ => return None,
+    };
+    Some(msg)
+}
+
+/// Whether the key event goes to a text field, which should not trigger shortcuts.
+fn is_typing(ev: &KeyboardEvent) -> bool {
+    ev.target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .map(|element| matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"))
+        .unwrap_or(false)
+}
+
+/// A readable message for a JavaScript exception or rejected promise.
+fn describe_error(error: JsValue) -> String {
+    if let Some(error) = error.dyn_ref::<web_sys::DomException>() {
+        return error.message();
+    }
+    error
+        .dyn_ref::<js_sys::Error>()
+        .map(|error| String::from(error.message()))
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{:?}", error))
+}
+
+fn mouse_position(ev: &MouseEvent) -> Point2<f64> {
+    Point2::from([ev.client_x() as f64, ev.client_y() as f64])
+}
+
+/// Positions of the fingers still on the screen.
+fn touch_positions(ev: &TouchEvent) -> Vec<Point2<f64>> {
+    let touches = ev.touches();
+    (0..touches.length())
+        .filter_map(|idx| touches.get(idx))
+        .map(|touch| Point2::from([touch.client_x() as f64, touch.client_y() as f64]))
+        .collect()
+}
+
+/// Read the automaton to run from the `automaton` query parameter.
+fn selected_automaton() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("automaton="))
+        .map(str::to_owned)
+}
+
+/// Name of the automaton opened without `?automaton=`.
+const DEFAULT_AUTOMATON: &str = "life";
+
+/// Name, label and entry point of every automaton, the name being the value
+/// of `?automaton=` that opens it.
+pub const AUTOMATA: &[(&str, &str, fn())] = &[
+    ("life", "Game of Life", yew::start_app::<Model<Life>>),
+    ("life-like", "Life-like", yew::start_app::<Model<LifeLike>>),
+    ("highlife", "HighLife", yew::start_app::<Model<HighLife>>),
+    (
+        "day-and-night",
+        "Day & Night",
+        yew::start_app::<Model<DayAndNight>>,
+    ),
+    ("seeds", "Seeds", yew::start_app::<Model<Seeds>>),
+    (
+        "totalistic",
+        "Totalistic",
+        yew::start_app::<Model<TotalisticRule>>,
+    ),
+    (
+        "generations",
+        "Generations",
+        yew::start_app::<Model<Generations>>,
+    ),
+    (
+        "larger-than-life",
+        "Larger than Life",
+        yew::start_app::<Model<LargerThanLife>>,
+    ),
+    (
+        "hex-life",
+        "Hexagonal Life",
+        yew::start_app::<Model<HexLife>>,
+    ),
+    (
+        "immigration",
+        "Immigration",
+        yew::start_app::<Model<Immigration>>,
+    ),
+    (
+        "weighted-seeds",
+        "Weighted Seeds",
+        yew::start_app::<Model<WeightedSeeds>>,
+    ),
+    (
+        "brians-brain",
+        "Brian's Brain",
+        yew::start_app::<Model<BriansBrain>>,
+    ),
+    ("wireworld", "Wireworld", yew::start_app::<Model<Wireworld>>),
+    ("cyclic", "Cyclic", yew::start_app::<Model<Cyclic>>),
+    (
+        "greenberg-hastings",
+        "Greenberg-Hastings",
+        yew::start_app::<Model<GreenbergHastings>>,
+    ),
+    (
+        "rock-paper-scissors",
+        "Rock Paper Scissors",
+        yew::start_app::<Model<RockPaperScissors>>,
+    ),
+    (
+        "forest-fire",
+        "Forest fire",
+        yew::start_app::<Model<ForestFire>>,
+    ),
+    (
+        "elementary",
+        "Elementary",
+        yew::start_app::<Model<Elementary>>,
+    ),
+    ("margolus", "Margolus", yew::start_app::<Model<Margolus>>),
+    (
+        "langtons-ant",
+        "Langton's ant",
+        yew::start_app::<Model<LangtonsAnt>>,
+    ),
+    ("turmite", "Turmite", yew::start_app::<Model<Turmite>>),
+    ("wa-tor", "Wa-Tor", yew::start_app::<Model<WaTor>>),
+    ("sandpile", "Sandpile", yew::start_app::<Model<Sandpile>>),
+    ("lenia", "Lenia", yew::start_app::<Model<Lenia>>),
+    (
+        "smooth-life",
+        "SmoothLife",
+        yew::start_app::<Model<SmoothLife>>,
+    ),
+];
+
+/// Name of the running automaton, which namespaces its saved state.
+/// Unknown names fall back to [`DEFAULT_AUTOMATON`].
+pub fn automaton_name() -> &'static str {
+    let selected = selected_automaton();
+    AUTOMATA
+        .iter()
+        .map(|(name, ..)| *name)
+        .find(|name| selected.as_deref() == Some(*name))
+        .unwrap_or(DEFAULT_AUTOMATON)
+}
+
 fn main() {
-    yew::start_app::<Model<Life>>();
+    let name = automaton_name();
+    let (_, _, start) = AUTOMATA
+        .iter()
+        .find(|(other, ..)| *other == name)
+        .expect("the default automaton is listed");
+    start();
 }
@@ -0,0 +1,932 @@
+//! Reading and writing patterns in common plaintext formats.
+use std::{fmt, mem};
+
+use crate::{
+    automaton::{Grid, GridError, LifeStates},
+    macrocell::{from_macrocell, macrocell_rule, to_macrocell, MACROCELL_HEADER},
+};
+
+const LIFE106_HEADER: &str = "#Life 1.06";
+
+/// Maximum length of the lines written by [`to_rle`].
+pub const RLE_LINE_WIDTH: usize = 70;
+
+/// Built-in patterns that can be stamped onto the grid, as offsets of
+/// their live cells from the top-left corner.
+#[rustfmt::skip]
+pub const PATTERNS: &[(&str, &[(isize, isize)])] = &[
+    ("Glider", &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]),
+    ("Blinker", &[(0, 0), (1, 0), (2, 0)]),
+    ("Block", &[(0, 0), (1, 0), (0, 1), (1, 1)]),
+    ("Gosper gun", &[
+        (24, 0), (22, 1), (24, 1), (12, 2), (13, 2), (20, 2), (21, 2), (34, 2), (35, 2),
+        (11, 3), (15, 3), (20, 3), (21, 3), (34, 3), (35, 3), (0, 4), (1, 4), (10, 4),
+        (16, 4), (20, 4), (21, 4), (0, 5), (1, 5), (10, 5), (14, 5), (16, 5), (17, 5),
+        (22, 5), (24, 5), (10, 6), (16, 6), (24, 6), (11, 7), (15, 7), (12, 8), (13, 8),
+    ]),
+];
+
+/// Orientation of a stamped pattern, a horizontal flip followed by
+/// `quarter_turns` clockwise rotations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    pub quarter_turns: u8,
+    pub flipped: bool,
+}
+
+impl Orientation {
+    /// This orientation rotated by another quarter turn clockwise.
+    pub fn rotated_cw(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 1) % 4,
+            ..self
+        }
+    }
+
+    /// This orientation mirrored horizontally.
+    pub fn flipped_h(self) -> Self {
+        // Flipping reverses the direction of all previous rotations
+        Self {
+            quarter_turns: (4 - self.quarter_turns % 4) % 4,
+            flipped: !self.flipped,
+        }
+    }
+
+    /// The `offsets` in this orientation, moved so that the smallest
+    /// coordinates are zero again.
+    pub fn apply(self, offsets: &[(isize, isize)]) -> Vec<(isize, isize)> {
+        let mut offsets = if self.flipped {
+            flip_offsets_h(offsets)
+        } else {
+            offsets.to_vec()
+        };
+        for _ in 0..self.quarter_turns % 4 {
+            offsets = rotate_offsets_cw(&offsets);
+        }
+        offsets
+    }
+}
+
+/// Rotate offsets by a quarter turn clockwise, keeping them at the origin.
+pub fn rotate_offsets_cw(offsets: &[(isize, isize)]) -> Vec<(isize, isize)> {
+    // With y pointing down, clockwise maps (x, y) to (-y, x)
+    let max_y = offsets.iter().map(|&(_, y)| y).max().unwrap_or_default();
+    let min_x = offsets.iter().map(|&(x, _)| x).min().unwrap_or_default();
+    offsets
+        .iter()
+        .map(|&(x, y)| (max_y - y, x - min_x))
+        .collect()
+}
+
+/// Mirror offsets horizontally, keeping them at the origin.
+pub fn flip_offsets_h(offsets: &[(isize, isize)]) -> Vec<(isize, isize)> {
+    let max_x = offsets.iter().map(|&(x, _)| x).max().unwrap_or_default();
+    let min_y = offsets.iter().map(|&(_, y)| y).min().unwrap_or_default();
+    offsets
+        .iter()
+        .map(|&(x, y)| (max_x - x, y - min_y))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The file does not start with the expected header.
+    MissingHeader,
+    /// The given (1-based) line could not be parsed.
+    InvalidLine(usize, String),
+    /// The cells are spread too far apart.
+    TooLarge(GridError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingHeader => write!(f, "expected '{}' header", LIFE106_HEADER),
+            ParseError::InvalidLine(line, content) => {
+                write!(f, "invalid line {}: {:?}", line, content)
+            }
+            ParseError::TooLarge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RleError {
+    /// No `x = .., y = ..` header line before the pattern.
+    MissingHeader,
+    /// The header line could not be parsed.
+    InvalidHeader(String),
+    /// A character that is not part of the run-length encoding.
+    InvalidToken(char),
+    /// A live cell at the given position lies outside the size from the header.
+    OutOfBounds(usize, usize),
+    /// The size from the header is too large.
+    TooLarge(GridError),
+    /// A run count, or the position after a run, does not fit into a `usize`.
+    RunTooLong,
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::MissingHeader => write!(f, "expected 'x = .., y = ..' header"),
+            RleError::InvalidHeader(header) => write!(f, "invalid header {:?}", header),
+            RleError::InvalidToken(token) => write!(f, "unexpected character {:?}", token),
+            RleError::OutOfBounds(x, y) => {
+                write!(f, "cell ({}, {}) lies outside of the pattern size", x, y)
+            }
+            RleError::TooLarge(e) => write!(f, "{}", e),
+            RleError::RunTooLong => write!(f, "run count too large"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaintextError {
+    /// The given (1-based) line contains a character other than `.` and `O`.
+    InvalidCell(usize, char),
+    /// The longest row times the number of rows is too large.
+    TooLarge(GridError),
+}
+
+impl fmt::Display for PlaintextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaintextError::InvalidCell(line, cell) => {
+                write!(f, "unexpected character {:?} in line {}", cell, line)
+            }
+            PlaintextError::TooLarge(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// The supported pattern formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Life106,
+    Rle,
+    /// The `.cells` format, see [`from_plaintext`].
+    Plaintext,
+    /// Golly's quadtree format, see [`macrocell`](crate::macrocell).
+    Macrocell,
+}
+
+impl Format {
+    /// File extension of patterns in this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Life106 => "lif",
+            Format::Rle => "rle",
+            Format::Plaintext => "cells",
+            Format::Macrocell => "mc",
+        }
+    }
+
+    /// Guess the format from the extension of the file `name`, or from the
+    /// content of `input` if the extension is missing or unknown.
+    pub fn detect(name: Option<&str>, input: &str) -> Self {
+        let extension = name
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("rle") => return Format::Rle,
+            Some("cells") => return Format::Plaintext,
+            Some("lif" | "life") => return Format::Life106,
+            Some("mc") => return Format::Macrocell,
+            _ => {}
+        }
+        let first = input.lines().map(str::trim).find(|line| !line.is_empty());
+        match first {
+            Some(line) if line.starts_with(LIFE106_HEADER) => Format::Life106,
+            Some(line) if line.starts_with(MACROCELL_HEADER) => Format::Macrocell,
+            Some(line) if line.starts_with('!') => Format::Plaintext,
+            Some(line) if line.chars().all(|c| c == '.' || c == 'O') => Format::Plaintext,
+            _ => Format::Rle,
+        }
+    }
+}
+
+/// Read a pattern in any of the supported formats, see [`Format::detect`].
+pub fn parse(input: &str) -> Result<Grid<LifeStates>, String> {
+    parse_as(Format::detect(None, input), input)
+}
+
+/// Read a pattern in the given format.
+pub fn parse_as(format: Format, input: &str) -> Result<Grid<LifeStates>, String> {
+    match format {
+        Format::Life106 => from_life106(input).map_err(|e| e.to_string()),
+        Format::Rle => from_rle(input).map_err(|e| e.to_string()),
+        Format::Plaintext => from_plaintext(input).map_err(|e| e.to_string()),
+        Format::Macrocell => from_macrocell(input).map_err(|e| e.to_string()),
+    }
+}
+
+/// The rule stored in a pattern of the given format, if any.
+pub fn rule_of(format: Format, input: &str) -> Option<String> {
+    match format {
+        Format::Rle => rle_rule(input),
+        Format::Macrocell => macrocell_rule(input),
+        Format::Life106 | Format::Plaintext => None,
+    }
+}
+
+/// Name, author and description of a pattern, read from its comments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternMeta {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// Remaining comment lines, usually describing the pattern.
+    pub comments: Vec<String>,
+}
+
+impl PatternMeta {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.author.is_none() && self.comments.is_empty()
+    }
+
+    /// `#N`, `#O` and `#C` lines preceding the header of an RLE pattern.
+    fn to_rle_comments(&self) -> String {
+        let mut out = String::new();
+        for (tag, value) in [('N', &self.name), ('O', &self.author)] {
+            if let Some(value) = value {
+                out += &format!("#{} {}\n", tag, value);
+            }
+        }
+        for comment in &self.comments {
+            out += &format!("#C {}\n", comment);
+        }
+        out
+    }
+
+    /// `!Name:`, `!Author:` and other comment lines preceding a plaintext pattern.
+    fn to_plaintext_comments(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in [("Name", &self.name), ("Author", &self.author)] {
+            if let Some(value) = value {
+                out += &format!("!{}: {}\n", key, value);
+            }
+        }
+        for comment in &self.comments {
+            out += &format!("!{}\n", comment);
+        }
+        out
+    }
+}
+
+/// The metadata in the comments of a pattern of the given format.
+///
+/// RLE patterns use `#N` for the name, `#O` for the author and `#C` for
+/// other comments. Plaintext patterns use `!Name:` and `!Author:` lines,
+/// all other lines starting with `!` are comments. Blank comments are dropped.
+pub fn meta_of(format: Format, input: &str) -> PatternMeta {
+    let mut meta = PatternMeta::default();
+    let mut add = |key: &str, value: &str| {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        match key {
+            "N" | "Name:" => meta.name = Some(value.to_owned()),
+            "O" | "Author:" => meta.author = Some(value.to_owned()),
+            _ => meta.comments.push(value.to_owned()),
+        }
+    };
+    for line in input.lines().map(str::trim) {
+        match format {
+            Format::Rle => match line.strip_prefix('#') {
+                Some(comment) if comment.is_char_boundary(1) => {
+                    let (tag, value) = comment.split_at(1);
+                    if matches!(tag, "N" | "O" | "C" | "c") {
+                        add(tag, value);
+                    }
+                }
+                _ => {}
+            },
+            Format::Plaintext => {
+                if let Some(comment) = line.strip_prefix('!') {
+                    match ["Name:", "Author:"]
+                        .iter()
+                        .find_map(|key| Some((*key, comment.strip_prefix(key)?)))
+                    {
+                        Some((key, value)) => add(key, value),
+                        None => add("", comment),
+                    }
+                }
+            }
+            Format::Life106 | Format::Macrocell => break,
+        }
+    }
+    meta
+}
+
+/// Serialize the live cells of `grid` in the given format.
+///
+/// Only RLE and macrocell include the `rule`, the other formats have no place
+/// for it. The `meta` is kept in the comments of RLE and plaintext patterns.
+pub fn serialize_as(
+    format: Format,
+    grid: &Grid<LifeStates>,
+    rule: Option<&str>,
+    meta: &PatternMeta,
+) -> String {
+    match format {
+        Format::Life106 => to_life106(grid),
+        Format::Rle => meta.to_rle_comments() + &to_rle(grid, rule),
+        Format::Plaintext => meta.to_plaintext_comments() + &to_plaintext(grid),
+        Format::Macrocell => to_macrocell(grid, rule),
+    }
+}
+
+/// Serialize the live cells of `grid` in Life 1.06 format.
+///
+/// Coordinates are relative to the center of the grid.
+pub fn to_life106(grid: &Grid<LifeStates>) -> String {
+    let (center_x, center_y) = center(grid);
+    let mut out = String::from(LIFE106_HEADER);
+    out.push('\n');
+    for y in 0..grid.height() as isize {
+        for x in 0..grid.width() as isize {
+            if grid[(x, y)] == LifeStates::Alife {
+                out += &format!("{} {}\n", x - center_x, y - center_y);
+            }
+        }
+    }
+    out
+}
+
+/// Read a pattern in Life 1.06 format.
+///
+/// The grid is just large enough to contain all cells, while keeping the
+/// origin of the pattern at the center, so that [`to_life106`] reproduces
+/// the same coordinates.
+pub fn from_life106(input: &str) -> Result<Grid<LifeStates>, ParseError> {
+    centered_grid(&life106_cells(input)?).map_err(ParseError::TooLarge)
+}
+
+/// Read the coordinates of the live cells from a pattern in Life 1.06 format.
+pub fn life106_cells(input: &str) -> Result<Vec<(isize, isize)>, ParseError> {
+    let mut lines = input.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == LIFE106_HEADER => {}
+        _ => return Err(ParseError::MissingHeader),
+    }
+    let mut cells = Vec::new();
+    for (idx, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || ParseError::InvalidLine(idx + 1, line.to_owned());
+        let mut coords = line.split_whitespace().map(str::parse::<isize>);
+        match (coords.next(), coords.next(), coords.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((x, y)),
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(cells)
+}
+
+/// Read a pattern in run-length encoded (RLE) format.
+///
+/// The grid has the size given in the header, the rule is read by [`rle_rule`].
+pub fn from_rle(input: &str) -> Result<Grid<LifeStates>, RleError> {
+    let mut lines = rle_lines(input);
+    let (width, height, _) = parse_rle_header(lines.next().ok_or(RleError::MissingHeader)?)?;
+    let mut grid = Grid::try_generate(width, height).map_err(RleError::TooLarge)?;
+    let (mut x, mut y): (usize, usize) = (0, 0);
+    let mut count: Option<usize> = None;
+    for token in lines.flat_map(str::chars) {
+        match token {
+            '0'..='9' => {
+                let digit = token.to_digit(10).unwrap() as usize;
+                let more = count.unwrap_or(0).checked_mul(10);
+                count = Some(
+                    more.and_then(|count| count.checked_add(digit))
+                        .ok_or(RleError::RunTooLong)?,
+                );
+                continue;
+            }
+            'b' => {
+                x = x
+                    .checked_add(count.unwrap_or(1))
+                    .ok_or(RleError::RunTooLong)?
+            }
+            'o' => {
+                for _ in 0..count.unwrap_or(1) {
+                    if x >= width || y >= height {
+                        return Err(RleError::OutOfBounds(x, y));
+                    }
+                    grid[(x as isize, y as isize)] = LifeStates::Alife;
+                    x += 1;
+                }
+            }
+            '$' => {
+                x = 0;
+                y = y
+                    .checked_add(count.unwrap_or(1))
+                    .ok_or(RleError::RunTooLong)?;
+            }
+            '!' => break,
+            token if token.is_whitespace() => {}
+            token => return Err(RleError::InvalidToken(token)),
+        }
+        count = None;
+    }
+    Ok(grid)
+}
+
+/// Serialize the bounding box of the live cells of `grid` in RLE format.
+///
+/// The `rule` is added to the header if given. Lines are wrapped at
+/// [`RLE_LINE_WIDTH`] characters.
+pub fn to_rle(grid: &Grid<LifeStates>, rule: Option<&str>) -> String {
+    let alive = |x, y| grid[(x, y)] == LifeStates::Alife;
+    let (x0, y0, x1, y1) = alive_bounds(grid).unwrap_or((0, 0, -1, -1));
+    let mut out = format!("x = {}, y = {}", x1 - x0 + 1, y1 - y0 + 1);
+    if let Some(rule) = rule {
+        out += &format!(", rule = {}", rule);
+    }
+    // Runs of equal cells, where trailing dead cells of a row are dropped
+    // and row ends accumulate over empty rows
+    let mut runs: Vec<(usize, char)> = Vec::new();
+    let push = |runs: &mut Vec<(usize, char)>, tag| match runs.last_mut() {
+        Some((count, last)) if *last == tag => *count += 1,
+        _ => runs.push((1, tag)),
+    };
+    for y in y0..=y1 {
+        if y > y0 {
+            push(&mut runs, '$');
+        }
+        let row_start = runs.len();
+        for x in x0..=x1 {
+            push(&mut runs, if alive(x, y) { 'o' } else { 'b' });
+        }
+        if runs.len() > row_start && runs.last().map(|(_, tag)| *tag) == Some('b') {
+            runs.pop();
+        }
+    }
+    runs.push((1, '!'));
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let run = match count {
+            1 => tag.to_string(),
+            _ => format!("{}{}", count, tag),
+        };
+        if line.len() + run.len() > RLE_LINE_WIDTH {
+            out += "\n";
+            out += &mem::take(&mut line);
+        }
+        line += &run;
+    }
+    out += "\n";
+    out += &line;
+    out.push('\n');
+    out
+}
+
+/// The smallest and largest coordinates of live cells, as
+/// `(min_x, min_y, max_x, max_y)`, or `None` if no cell is alive.
+fn alive_bounds(grid: &Grid<LifeStates>) -> Option<(isize, isize, isize, isize)> {
+    (0..grid.height() as isize)
+        .flat_map(|y| (0..grid.width() as isize).map(move |x| (x, y)))
+        .filter(|&pos| grid[pos] == LifeStates::Alife)
+        .fold(None, |bounds, (x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((min_x, min_y, max_x, max_y)) => {
+                Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+            }
+        })
+}
+
+/// Read a pattern in plaintext (`.cells`) format.
+///
+/// Every line is a row, where `.` is a dead and `O` an alive cell. Lines
+/// starting with `!` are comments. The grid is as wide as the longest row.
+pub fn from_plaintext(input: &str) -> Result<Grid<LifeStates>, PlaintextError> {
+    let rows: Vec<_> = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim_end()))
+        .filter(|(_, line)| !line.starts_with('!'))
+        .collect();
+    let width = rows.iter().map(|(_, row)| row.len()).max().unwrap_or(0);
+    let mut grid =
+        Grid::try_generate(width.max(1), rows.len().max(1)).map_err(PlaintextError::TooLarge)?;
+    for (y, (idx, row)) in rows.iter().enumerate() {
+        for (x, cell) in row.chars().enumerate() {
+            match cell {
+                '.' => {}
+                'O' => grid[(x as isize, y as isize)] = LifeStates::Alife,
+                cell => return Err(PlaintextError::InvalidCell(idx + 1, cell)),
+            }
+        }
+    }
+    Ok(grid)
+}
+
+/// Serialize the bounding box of the live cells of `grid` in plaintext format.
+///
+/// Trailing dead cells of every row are omitted, as [`from_plaintext`]
+/// takes the width from the longest row.
+pub fn to_plaintext(grid: &Grid<LifeStates>) -> String {
+    let mut out = String::new();
+    if let Some((x0, y0, x1, y1)) = alive_bounds(grid) {
+        for y in y0..=y1 {
+            let row: String = (x0..=x1)
+                .map(|x| match grid[(x, y)] {
+                    LifeStates::Alife => 'O',
+                    LifeStates::Dead => '.',
+                })
+                .collect();
+            out += row.trim_end_matches('.');
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The rule from the header of an RLE pattern, like `B3/S23`, if it has one.
+pub fn rle_rule(input: &str) -> Option<String> {
+    let header = rle_lines(input).next()?;
+    let (_, _, rule) = parse_rle_header(header).ok()?;
+    rule.map(str::to_owned)
+}
+
+/// Lines of an RLE pattern without blank lines and comments.
+fn rle_lines(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parse the `x = .., y = ..[, rule = ..]` line into the pattern size and rule.
+fn parse_rle_header(header: &str) -> Result<(usize, usize, Option<&str>), RleError> {
+    let invalid = || RleError::InvalidHeader(header.to_owned());
+    let (mut width, mut height, mut rule) = (None, None, None);
+    for field in header.split(',') {
+        let (key, value) = field.split_once('=').ok_or_else(invalid)?;
+        let size = || value.trim().parse::<usize>().map_err(|_| invalid());
+        match key.trim() {
+            "x" => width = Some(size()?),
+            "y" => height = Some(size()?),
+            "rule" => rule = Some(value.trim()),
+            _ => {}
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width.max(1), height.max(1), rule)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Create a grid with the given cells alive, relative to its center.
+///
+/// Fails if the grid would have more than [`MAX_CELLS`](crate::automaton::MAX_CELLS) cells.
+fn centered_grid(cells: &[(isize, isize)]) -> Result<Grid<LifeStates>, GridError> {
+    // Half the size has to cover the most negative coordinate to the left
+    // and the most positive one (inclusive) to the right.
+    let half = |coord: fn(&(isize, isize)) -> isize| {
+        cells
+            .iter()
+            .map(|cell| match coord(cell) {
+                coord if coord < 0 => coord.unsigned_abs(),
+                coord => coord as usize + 1,
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    };
+    let size = |coord| 2_usize.saturating_mul(half(coord));
+    place_centered(cells, size(|c| c.0), size(|c| c.1))
+}
+
+/// Create a `width`×`height` grid with the given cells alive, relative to its center.
+///
+/// Cells outside of the grid are dropped. Fails if the grid would have more
+/// than [`MAX_CELLS`](crate::automaton::MAX_CELLS) cells.
+pub fn place_centered(
+    cells: &[(isize, isize)],
+    width: usize,
+    height: usize,
+) -> Result<Grid<LifeStates>, GridError> {
+    let mut grid = Grid::try_generate(width, height)?;
+    let (center_x, center_y) = center(&grid);
+    for (x, y) in cells {
+        let (Some(x), Some(y)) = (x.checked_add(center_x), y.checked_add(center_y)) else {
+            continue;
+        };
+        if (0..width as isize).contains(&x) && (0..height as isize).contains(&y) {
+            grid[(x, y)] = LifeStates::Alife;
+        }
+    }
+    Ok(grid)
+}
+
+/// Offsets of the live cells of `grid` from its center, the inverse of [`place_centered`].
+///
+/// Used to stamp a loaded pattern with [`Supervisor::stamp`](crate::supervisor::Supervisor::stamp).
+pub fn alive_offsets(grid: &Grid<LifeStates>) -> Vec<(isize, isize)> {
+    let (center_x, center_y) = center(grid);
+    (0..grid.height() as isize)
+        .flat_map(|y| (0..grid.width() as isize).map(move |x| (x, y)))
+        .filter(|&pos| grid[pos] == LifeStates::Alife)
+        .map(|(x, y)| (x - center_x, y - center_y))
+        .collect()
+}
+
+fn center<State>(grid: &Grid<State>) -> (isize, isize) {
+    (grid.width() as isize / 2, grid.height() as isize / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const GLIDER: &str = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+
+    fn alive(grid: &Grid<LifeStates>) -> usize {
+        (0..grid.width() as isize)
+            .flat_map(|x| (0..grid.height() as isize).map(move |y| (x, y)))
+            .filter(|&pos| grid[pos] == LifeStates::Alife)
+            .count()
+    }
+
+    #[test]
+    fn offsets_are_relative_to_center() {
+        let cells = [(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)];
+        let offsets = alive_offsets(&place_centered(&cells, 5, 4).unwrap());
+        let as_set = |cells: &[(isize, isize)]| cells.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(as_set(&offsets), as_set(&cells));
+        assert!(alive_offsets(&Grid::generate(3, 3)).is_empty());
+    }
+
+    #[test]
+    fn four_rotations_restore_glider() {
+        let glider = PATTERNS[0].1;
+        let as_set = |offsets: &[(isize, isize)]| offsets.iter().copied().collect::<HashSet<_>>();
+        let mut offsets = glider.to_vec();
+        for turn in 1..=4 {
+            offsets = rotate_offsets_cw(&offsets);
+            assert_eq!(offsets.len(), glider.len());
+            assert_eq!(as_set(&offsets) == as_set(glider), turn == 4);
+        }
+        // A glider heading down right heads down left after a quarter turn
+        assert_eq!(
+            as_set(&rotate_offsets_cw(glider)),
+            as_set(&[(0, 0), (0, 1), (2, 1), (0, 2), (1, 2)]),
+        );
+        assert_eq!(
+            as_set(&flip_offsets_h(&flip_offsets_h(glider))),
+            as_set(glider)
+        );
+    }
+
+    #[test]
+    fn orientations_compose() {
+        let blinker = PATTERNS[1].1;
+        let glider = PATTERNS[0].1;
+        let upright = Orientation::default().rotated_cw();
+        assert_eq!(upright.apply(blinker), [(0, 0), (0, 1), (0, 2)]);
+        // Four quarter turns and two flips are the identity
+        let mut orientation = Orientation::default();
+        for _ in 0..4 {
+            orientation = orientation.rotated_cw();
+        }
+        assert_eq!(orientation, Orientation::default());
+        assert_eq!(orientation.flipped_h().flipped_h(), orientation);
+        // Flipping a rotated pattern mirrors what is shown
+        let rotated = upright.apply(glider);
+        let flipped = upright.flipped_h().apply(glider);
+        assert_eq!(
+            flipped.iter().copied().collect::<HashSet<_>>(),
+            flip_offsets_h(&rotated).into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn glider_round_trip() {
+        let grid = from_life106(GLIDER).unwrap();
+        assert_eq!(alive(&grid), 5);
+        assert_eq!(to_life106(&grid), GLIDER);
+    }
+
+    #[test]
+    fn arbitrary_cells_round_trip() {
+        let input = "#Life 1.06\n#D comment\n-7 -3\n\n4 -3\n0 0\n-1 12\n";
+        let grid = from_life106(input).unwrap();
+        assert_eq!(to_life106(&grid), "#Life 1.06\n-7 -3\n4 -3\n0 0\n-1 12\n");
+        let empty = from_life106("#Life 1.06\n").unwrap();
+        assert_eq!(to_life106(&empty), "#Life 1.06\n");
+    }
+
+    #[test]
+    fn rle_glider() {
+        let glider = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+        assert_eq!(rle_rule(glider), Some("B3/S23".to_owned()));
+        assert_eq!(rle_rule("x = 3, y = 3\nbo$2bo$3o!"), None);
+        let grid = from_rle(glider).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(alive(&grid), 5);
+        assert_eq!(grid[(1, 0)], LifeStates::Alife);
+        assert_eq!(grid[(2, 1)], LifeStates::Alife);
+        assert_eq!(grid[(0, 1)], LifeStates::Dead);
+    }
+
+    #[test]
+    fn rle_gosper_glider_gun() {
+        let gun = "#N Gosper glider gun
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!";
+        let grid = from_rle(gun).unwrap();
+        assert_eq!((grid.width(), grid.height()), (36, 9));
+        assert_eq!(alive(&grid), 36);
+    }
+
+    #[test]
+    fn rle_export() {
+        let glider = "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+        assert_eq!(to_rle(&from_rle(glider).unwrap(), Some("B3/S23")), glider);
+        // Only the bounding box is written, empty rows are merged
+        let grid = place_centered(&[(-2, -2), (0, -2), (1, 1)], 10, 10).unwrap();
+        assert_eq!(to_rle(&grid, None), "x = 4, y = 4\nobo3$3bo!\n");
+        assert_eq!(to_rle(&Grid::generate(3, 3), None), "x = 0, y = 0\n!\n");
+    }
+
+    #[test]
+    fn rle_export_wraps_long_lines() {
+        let gun = "x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!";
+        let exported = to_rle(&from_rle(gun).unwrap(), None);
+        assert!(exported.lines().count() > 2);
+        assert!(exported.lines().all(|line| line.len() <= RLE_LINE_WIDTH));
+        assert_eq!(from_rle(&exported), from_rle(gun));
+    }
+
+    #[test]
+    fn malformed_rle_is_rejected() {
+        assert_eq!(from_rle(""), Err(RleError::MissingHeader));
+        assert_eq!(
+            from_rle("x = 3\nbo!"),
+            Err(RleError::InvalidHeader("x = 3".to_owned()))
+        );
+        assert_eq!(
+            from_rle("x = 3, y = 1\nbq!"),
+            Err(RleError::InvalidToken('q'))
+        );
+        assert_eq!(
+            from_rle("x = 2, y = 1\n3o!"),
+            Err(RleError::OutOfBounds(2, 0))
+        );
+        assert_eq!(
+            from_rle("x = 2, y = 1\no$o!"),
+            Err(RleError::OutOfBounds(0, 1))
+        );
+        assert!(matches!(
+            from_rle("x = 100000, y = 100000\no!"),
+            Err(RleError::TooLarge(_))
+        ));
+        for overflow in [
+            "99999999999999999999999b!",
+            "18446744073709551615b2b!",
+            "18446744073709551615$2$!",
+        ] {
+            assert_eq!(
+                from_rle(&format!("x = 2, y = 2\n{}", overflow)),
+                Err(RleError::RunTooLong)
+            );
+        }
+    }
+
+    #[test]
+    fn gosper_gun_matches_rle() {
+        let (_, gun) = PATTERNS
+            .iter()
+            .find(|(name, _)| *name == "Gosper gun")
+            .unwrap();
+        let grid = from_rle("x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!").unwrap();
+        assert_eq!(gun.len(), alive(&grid));
+        assert!(gun.iter().all(|&pos| grid[pos] == LifeStates::Alife));
+    }
+
+    #[test]
+    fn parse_detects_format() {
+        assert_eq!(alive(&parse(GLIDER).unwrap()), 5);
+        assert_eq!(alive(&parse("x = 3, y = 3\nbo$2bo$3o!").unwrap()), 5);
+    }
+
+    #[test]
+    fn format_detection() {
+        assert_eq!(Format::detect(Some("glider.rle"), GLIDER), Format::Rle);
+        assert_eq!(Format::detect(Some("Glider.CELLS"), ""), Format::Plaintext);
+        assert_eq!(Format::detect(Some("glider.lif"), ""), Format::Life106);
+        assert_eq!(Format::detect(Some("glider.txt"), GLIDER), Format::Life106);
+        assert_eq!(
+            Format::detect(None, "\n!Name: Glider\n.O.\n"),
+            Format::Plaintext
+        );
+        assert_eq!(Format::detect(None, "..O\nOOO\n"), Format::Plaintext);
+        assert_eq!(
+            Format::detect(None, "#N Glider\nx = 3, y = 3\n"),
+            Format::Rle
+        );
+        assert_eq!(Format::detect(None, ""), Format::Rle);
+    }
+
+    #[test]
+    fn plaintext_glider() {
+        let grid = from_plaintext("!Name: Glider\n!\n.O\n..O\nOOO\n").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(alive(&grid), 5);
+        assert_eq!(grid[(1, 0)], LifeStates::Alife);
+        assert_eq!(grid[(2, 0)], LifeStates::Dead);
+        assert_eq!(grid, from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap());
+        assert_eq!(
+            from_plaintext("!Comment\n.O\n.*\n"),
+            Err(PlaintextError::InvalidCell(3, '*'))
+        );
+        assert_eq!(alive(&parse(".O.\n").unwrap()), 1);
+        // A long row and many empty lines span a huge grid
+        let huge = format!("{}\n{}", ".".repeat(100_000), "\n".repeat(100_000));
+        assert!(matches!(
+            from_plaintext(&huge),
+            Err(PlaintextError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn plaintext_export() {
+        let glider = ".O\n..O\nOOO\n";
+        assert_eq!(to_plaintext(&from_plaintext(glider).unwrap()), glider);
+        // Only the bounding box is written, empty rows stay in place
+        let grid = place_centered(&[(-2, -2), (0, -2), (1, 1)], 10, 10).unwrap();
+        let text = to_plaintext(&grid);
+        assert_eq!(text, "O.O\n\n\n...O\n");
+        assert_eq!(Format::detect(None, &text), Format::Plaintext);
+        let parsed = from_plaintext(&text).unwrap();
+        assert_eq!(alive(&parsed), 3);
+        assert_eq!((parsed.width(), parsed.height()), (4, 4));
+        assert_eq!(to_plaintext(&Grid::generate(3, 3)), "");
+    }
+
+    #[test]
+    fn all_formats_round_trip() {
+        let grid = from_life106(GLIDER).unwrap();
+        for format in [
+            Format::Life106,
+            Format::Rle,
+            Format::Plaintext,
+            Format::Macrocell,
+        ] {
+            let text = serialize_as(format, &grid, None, &PatternMeta::default());
+            let name = format!("glider.{}", format.extension());
+            assert_eq!(Format::detect(Some(&name), ""), format);
+            let parsed = parse_as(format, &text).unwrap();
+            assert_eq!(to_rle(&parsed, None), to_rle(&grid, None));
+        }
+    }
+
+    #[test]
+    fn metadata_is_read_and_kept() {
+        let rle = "#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\n#c\n\
+                   x = 3, y = 3\nbo$2bo$3o!\n";
+        let meta = meta_of(Format::Rle, rle);
+        assert_eq!(meta.name.as_deref(), Some("Glider"));
+        assert_eq!(meta.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(meta.comments, ["The smallest spaceship."]);
+        let grid = from_rle(rle).unwrap();
+        for format in [Format::Rle, Format::Plaintext] {
+            let text = serialize_as(format, &grid, None, &meta);
+            assert_eq!(meta_of(format, &text), meta);
+            assert_eq!(alive(&parse_as(format, &text).unwrap()), 5);
+        }
+        let cells = "!Name: Blinker\n!\n!Period 2.\nOOO\n";
+        let meta = meta_of(Format::Plaintext, cells);
+        assert_eq!(meta.name.as_deref(), Some("Blinker"));
+        assert_eq!(meta.author, None);
+        assert_eq!(meta.comments, ["Period 2."]);
+        assert!(meta_of(Format::Life106, GLIDER).is_empty());
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        assert_eq!(from_life106("0 0\n"), Err(ParseError::MissingHeader));
+        assert_eq!(
+            from_life106("#Life 1.06\n1 2\n3 x\n"),
+            Err(ParseError::InvalidLine(3, "3 x".to_owned()))
+        );
+        assert!(from_life106("#Life 1.06\n1 2 3\n").is_err());
+        for far in [
+            "#Life 1.06\n-9223372036854775808 0\n",
+            "#Life 1.06\n0 9223372036854775807\n",
+            "#Life 1.06\n-5000 0\n5000 0\n0 5000\n",
+        ] {
+            assert!(matches!(from_life106(far), Err(ParseError::TooLarge(_))));
+        }
+        let grid = place_centered(&[(isize::MIN, isize::MAX), (0, 0)], 2, 2).unwrap();
+        assert_eq!(grid[(1, 1)], LifeStates::Alife);
+        assert!(place_centered(&[], 100_000, 100_000).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+/// A small xorshift64* pseudo random number generator.
+///
+/// Not suitable for anything cryptographic, but fast, tiny and
+/// deterministic across platforms.
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A uniformly distributed float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Random numbers that only depend on a seed, a cell and a generation.
+///
+/// Unlike a single [`XorShift`], nothing has to be threaded through the
+/// cells, so rules can draw from it while computing cells in any order, and
+/// recomputing a generation gives the same result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellNoise {
+    seed: u64,
+}
+
+impl CellNoise {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// A generator for the cell at `(x, y)` in `generation`.
+    pub fn rng(&self, (x, y): (isize, isize), generation: u64) -> XorShift {
+        let hash = [x as u64, y as u64, generation]
+            .iter()
+            .fold(splitmix(self.seed), |hash, &value| splitmix(hash ^ value));
+        XorShift::new(hash)
+    }
+}
+
+/// The SplitMix64 finalizer, mixing all bits of `value`.
+fn splitmix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_are_in_unit_interval() {
+        let mut rng = XorShift::new(0);
+        for _ in 0..1000 {
+            let f = rng.next_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+
+    #[test]
+    fn cell_noise_is_repeatable() {
+        let noise = CellNoise::new(42);
+        let draw = |pos, generation| noise.rng(pos, generation).next_u64();
+        assert_eq!(draw((3, 4), 7), draw((3, 4), 7));
+        assert_ne!(draw((3, 4), 7), draw((4, 3), 7));
+        assert_ne!(draw((3, 4), 7), draw((3, 4), 8));
+        assert_ne!(
+            draw((3, 4), 7),
+            CellNoise::new(43).rng((3, 4), 7).next_u64()
+        );
+    }
+}
@@ -1,11 +1,131 @@
-use yew::{html, ComponentLink, Html};
+use std::num::ParseIntError;
 
-use crate::{automaton::Automaton, Model, Msg};
+use nalgebra::Vector2;
+use web_sys::HtmlCanvasElement;
+use yew::{html, ChangeData, ComponentLink, Html, InputData};
 
-#[derive(Debug, Clone, Default)]
+use crate::{
+    automaton::{
+        Automaton, Grid, LifeStates, Topology, TotalisticKind, TotalisticRule, Transition, Turmite,
+        Turn, MAX_TURMITE_COLORS, MAX_TURMITE_STATES, TURMITE_PRESETS,
+    },
+    automaton_name,
+    catalogue::{self, DEFAULT_CATALOGUE_URL},
+    export::{
+        RecordFormat, DEFAULT_RECORD_CELL_PX, DEFAULT_RECORD_FRAMES, EXPORT_CELL_PX, MAX_IMAGE_PX,
+        MAX_RECORD_CELL_PX, MAX_RECORD_FRAMES, MIN_RECORD_CELL_PX,
+    },
+    pattern::{Format, PATTERNS},
+    supervisor::{CellShape, RenderOptions, Supervisor, MAX_CELL_WIDTH, MIN_CELL_WIDTH},
+    theme::THEMES,
+    Model, Msg, Transform, AUTOMATA, SHORTCUT_HELP, TIME_BETWEEN_RENDERS_MS,
+};
+
+/// Range of the simulation tick interval in milliseconds.
+pub const MIN_TICK_MS: u32 = 10;
+pub const MAX_TICK_MS: u32 = 2000;
+
+#[derive(Debug, Clone)]
 pub struct Settings {
     visible: bool,
     auto_run: bool,
+    /// Probability of a cell being alive after randomizing.
+    density: f64,
+    /// Seed for randomizing as entered, a time-based seed is used if it is blank.
+    seed_text: String,
+    /// Milliseconds between two generations while auto-running.
+    tick_ms: u32,
+    /// Whether dragging moves the content along with the mouse (like
+    /// grabbing the canvas) or moves the viewport instead.
+    natural_pan: bool,
+    /// Keep part of the grid in view while panning and zooming, see [`Supervisor::clamp_pan`].
+    clamp_pan: bool,
+    /// Draw lines between cells.
+    show_grid: bool,
+    /// Color alive cells by their age.
+    heatmap: bool,
+    /// Print the neighbor count on each cell.
+    neighbor_counts: bool,
+    cell_shape: CellShape,
+    /// Stop auto-running once the pattern is stable, see [`Supervisor::stability`].
+    pause_when_stable: bool,
+    /// Index into [`THEMES`].
+    theme: usize,
+    /// Clicking places agents instead of editing cells, see [`Supervisor::toggle_agent`].
+    place_agents: bool,
+    /// Holding the mouse drops sand, see [`Supervisor::drop_sand`].
+    drop_sand: bool,
+    /// Dragging selects a region instead of painting cells.
+    select: bool,
+    /// Index into [`Automaton::states`] of the state painted by dragging.
+    brush: usize,
+    /// Number of generations in a GIF recording.
+    record_frames: usize,
+    /// Pixels per cell in a recording.
+    record_cell_px: u32,
+    record_format: RecordFormat,
+    /// Width of exported images in pixels, chosen from the grid size if `None`.
+    image_width: Option<u32>,
+    /// Contents of the pattern import/export text area.
+    pattern_text: String,
+    /// Text of the rule field with the reason it was rejected, until a valid rule is entered.
+    invalid_rule: Option<(String, String)>,
+    /// Name to save the grid under in the pattern library.
+    library_name: String,
+    /// Names of the patterns in the library, see [`crate::library`].
+    library: Vec<String>,
+    /// URL of the index of the online catalogue, see [`crate::catalogue`].
+    catalogue_url: String,
+    /// Links to the patterns of the loaded catalogue.
+    catalogue: Vec<String>,
+    /// Search text for the catalogue.
+    catalogue_query: String,
+    /// The catalogue pattern shown in the preview.
+    catalogue_preview: Option<CataloguePreview>,
+}
+
+/// A pattern fetched from the online catalogue, waiting to be stamped.
+#[derive(Debug, Clone)]
+pub struct CataloguePreview {
+    pub name: String,
+    pub cells: Grid<LifeStates>,
+    pub thumbnail: HtmlCanvasElement,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            auto_run: false,
+            density: 0.3,
+            seed_text: String::new(),
+            tick_ms: TIME_BETWEEN_RENDERS_MS,
+            natural_pan: true,
+            clamp_pan: true,
+            show_grid: false,
+            heatmap: false,
+            neighbor_counts: false,
+            cell_shape: CellShape::Square,
+            pause_when_stable: false,
+            theme: 0,
+            place_agents: false,
+            drop_sand: false,
+            select: false,
+            brush: 1,
+            record_frames: DEFAULT_RECORD_FRAMES,
+            record_cell_px: DEFAULT_RECORD_CELL_PX,
+            record_format: RecordFormat::default(),
+            image_width: None,
+            pattern_text: String::new(),
+            invalid_rule: None,
+            library_name: String::new(),
+            library: Vec::new(),
+            catalogue_url: DEFAULT_CATALOGUE_URL.to_owned(),
+            catalogue: Vec::new(),
+            catalogue_query: String::new(),
+            catalogue_preview: None,
+        }
+    }
 }
 
 impl Settings {
@@ -13,26 +133,268 @@ impl Settings {
         self.visible = !self.visible;
     }
 
-    pub fn toggle_auto_run(&mut self) {
-        self.auto_run = !self.auto_run;
+    /// Start or stop auto-running. Returns whether the state changed.
+    pub fn set_auto_run(&mut self, auto_run: bool) -> bool {
+        let changed = self.auto_run != auto_run;
+        self.auto_run = auto_run;
+        changed
     }
 
     pub fn auto_run(&self) -> bool {
         self.auto_run
     }
 
-    pub fn html<A: Automaton>(&self, link: &ComponentLink<Model<A>>) -> Html {
+    pub fn tick_ms(&self) -> u32 {
+        self.tick_ms
+    }
+
+    pub fn set_tick_ms(&mut self, tick_ms: u32) {
+        self.tick_ms = tick_ms.clamp(MIN_TICK_MS, MAX_TICK_MS);
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn set_density(&mut self, density: f64) {
+        self.density = density.clamp(0.0, 1.0);
+    }
+
+    /// The seed entered for randomizing, `None` if the field is blank.
+    pub fn seed(&self) -> Result<Option<u64>, ParseIntError> {
+        match self.seed_text.trim() {
+            "" => Ok(None),
+            seed => seed.parse().map(Some),
+        }
+    }
+
+    pub fn set_seed_text(&mut self, text: String) {
+        self.seed_text = text;
+    }
+
+    pub fn toggle_grid(&mut self) {
+        self.show_grid = !self.show_grid;
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.heatmap = !self.heatmap;
+    }
+
+    pub fn toggle_neighbor_counts(&mut self) {
+        self.neighbor_counts = !self.neighbor_counts;
+    }
+
+    /// Switch between square and round cells.
+    pub fn toggle_cell_shape(&mut self) {
+        self.cell_shape = match self.cell_shape {
+            CellShape::Square => CellShape::Circle,
+            CellShape::Circle => CellShape::Square,
+        };
+    }
+
+    pub fn pause_when_stable(&self) -> bool {
+        self.pause_when_stable
+    }
+
+    pub fn toggle_pause_when_stable(&mut self) {
+        self.pause_when_stable = !self.pause_when_stable;
+    }
+
+    pub fn place_agents(&self) -> bool {
+        self.place_agents
+    }
+
+    pub fn toggle_place_agents(&mut self) {
+        self.place_agents = !self.place_agents;
+    }
+
+    pub fn drop_sand(&self) -> bool {
+        self.drop_sand
+    }
+
+    pub fn toggle_drop_sand(&mut self) {
+        self.drop_sand = !self.drop_sand;
+    }
+
+    pub fn select(&self) -> bool {
+        self.select
+    }
+
+    pub fn toggle_select(&mut self) {
+        self.select = !self.select;
+    }
+
+    /// Select the theme at `idx` in [`THEMES`], unknown indices are ignored.
+    pub fn brush(&self) -> usize {
+        self.brush
+    }
+
+    pub fn set_brush(&mut self, brush: usize) {
+        self.brush = brush;
+    }
+
+    pub fn set_theme(&mut self, idx: usize) {
+        if idx < THEMES.len() {
+            self.theme = idx;
+        }
+    }
+
+    pub fn set_record_frames(&mut self, frames: usize) {
+        self.record_frames = frames.clamp(1, MAX_RECORD_FRAMES);
+    }
+
+    pub fn record_format(&self) -> RecordFormat {
+        self.record_format
+    }
+
+    pub fn set_record_format(&mut self, format: RecordFormat) {
+        self.record_format = format;
+    }
+
+    pub fn image_width(&self) -> Option<u32> {
+        self.image_width
+    }
+
+    pub fn set_image_width(&mut self, width: Option<u32>) {
+        self.image_width = width.map(|width| width.clamp(1, MAX_IMAGE_PX));
+    }
+
+    pub fn record_cell_px(&self) -> u32 {
+        self.record_cell_px
+    }
+
+    pub fn set_record_cell_px(&mut self, cell_px: u32) {
+        self.record_cell_px = cell_px.clamp(MIN_RECORD_CELL_PX, MAX_RECORD_CELL_PX);
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            show_grid: self.show_grid,
+            heatmap: self.heatmap,
+            cell_shape: self.cell_shape,
+            theme: THEMES[self.theme],
+            neighbor_counts: self.neighbor_counts,
+        }
+    }
+
+    pub fn pattern_text(&self) -> &str {
+        &self.pattern_text
+    }
+
+    pub fn set_pattern_text(&mut self, text: String) {
+        self.pattern_text = text;
+    }
+
+    pub fn library_name(&self) -> &str {
+        &self.library_name
+    }
+
+    pub fn set_library_name(&mut self, name: String) {
+        self.library_name = name;
+    }
+
+    pub fn set_library(&mut self, names: Vec<String>) {
+        self.library = names;
+    }
+
+    pub fn catalogue_url(&self) -> &str {
+        &self.catalogue_url
+    }
+
+    pub fn set_catalogue_url(&mut self, url: String) {
+        self.catalogue_url = url;
+    }
+
+    pub fn set_catalogue(&mut self, entries: Vec<String>) {
+        self.catalogue = entries;
+    }
+
+    pub fn set_catalogue_query(&mut self, query: String) {
+        self.catalogue_query = query;
+    }
+
+    pub fn catalogue_preview(&self) -> Option<&CataloguePreview> {
+        self.catalogue_preview.as_ref()
+    }
+
+    pub fn set_catalogue_preview(&mut self, preview: Option<CataloguePreview>) {
+        self.catalogue_preview = preview;
+    }
+
+    /// Remember the rejected text of the rule field and the reason, or forget it.
+    pub fn set_invalid_rule(&mut self, invalid_rule: Option<(String, String)>) {
+        self.invalid_rule = invalid_rule;
+    }
+
+    pub fn toggle_natural_pan(&mut self) {
+        self.natural_pan = !self.natural_pan;
+    }
+
+    pub fn clamp_pan(&self) -> bool {
+        self.clamp_pan
+    }
+
+    pub fn toggle_clamp_pan(&mut self) {
+        self.clamp_pan = !self.clamp_pan;
+    }
+
+    /// Serialize the persistent settings as space separated values:
+    /// density, tick interval, natural panning, grid lines and heatmap.
+    pub fn to_storage(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.density, self.tick_ms, self.natural_pan, self.show_grid, self.heatmap
+        )
+    }
+
+    /// Read settings written by [`Settings::to_storage`], `None` if they are invalid.
+    pub fn from_storage(line: &str) -> Option<Self> {
+        let mut fields = line.split(' ');
+        let mut settings = Self::default();
+        settings.set_density(fields.next()?.parse().ok()?);
+        settings.set_tick_ms(fields.next()?.parse().ok()?);
+        settings.natural_pan = fields.next()?.parse().ok()?;
+        settings.show_grid = fields.next()?.parse().ok()?;
+        settings.heatmap = fields.next()?.parse().ok()?;
+        match fields.next() {
+            Some(_) => None,
+            None => Some(settings),
+        }
+    }
+
+    /// Translation to apply for a mouse drag of `drag` pixels.
+    pub fn pan_delta(&self, drag: Vector2<f64>) -> Vector2<f64> {
+        if self.natural_pan {
+            drag
+        } else {
+            -drag
+        }
+    }
+
+    pub fn html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        supervisor: &Supervisor<A>,
+        selected_pattern: Option<usize>,
+        recording: bool,
+    ) -> Html {
         let toggle = link.callback(|_| Msg::ToggleSettings);
         html! {
             <>
                 <button id="toggle-settings" onclick=toggle>
                 </button>
-                { if self.visible { self.menu_html(link) } else { html!{} } }
+                { if self.visible { self.menu_html(link, supervisor, selected_pattern, recording) } else { html!{} } }
             </>
         }
     }
 
-    fn menu_html<A: Automaton>(&self, link: &ComponentLink<Model<A>>) -> Html {
+    fn menu_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        supervisor: &Supervisor<A>,
+        selected_pattern: Option<usize>,
+        recording: bool,
+    ) -> Html {
         let auto_run = if self.auto_run {
             "auto-run-on"
         } else {
@@ -40,11 +402,927 @@ impl Settings {
         };
         let auto_run_cb = link.callback(|_| Msg::ToggleAutoRun);
         let auto_zoom_cb = link.callback(|_| Msg::ResetZoom);
+        let fit_cb = link.callback(|_| Msg::FitToPattern);
+        let fullscreen_cb = link.callback(|_| Msg::ToggleFullscreen);
+        let clear_cb = link.callback(|_| Msg::Clear);
+        let reset_cb = link.callback(|_| Msg::ResetDefaults);
+        let undo_cb = link.callback(|_| Msg::Undo);
+        let redo_cb = link.callback(|_| Msg::Redo);
+        let density = self.density;
+        let randomize_cb = link.callback(move |_| Msg::Randomize(density));
+        let seed_cb = link.callback(|ev: InputData| Msg::SetSeedText(ev.value));
+        let (seed_class, seed_placeholder) = match (self.seed(), supervisor.seed()) {
+            (Err(_), _) => ("invalid", String::new()),
+            (Ok(_), Some(seed)) => ("", format!("Random, last {}", seed)),
+            (Ok(_), None) => ("", "Random seed".to_owned()),
+        };
+        let density_cb = link.batch_callback(|ev: InputData| {
+            ev.value
+                .parse::<f64>()
+                .map(|p| Msg::SetDensity(p / 100.0))
+                .ok()
+        });
+        let natural_pan_cb = link.callback(|_| Msg::ToggleNaturalPan);
+        let clamp_pan_cb = link.callback(|_| Msg::ToggleClampPan);
+        let grid_cb = link.callback(|_| Msg::ToggleGrid);
+        let heatmap_cb = link.callback(|_| Msg::ToggleHeatmap);
+        let neighbor_counts_cb = link.callback(|_| Msg::ToggleNeighborCounts);
+        let cell_shape_cb = link.callback(|_| Msg::ToggleCellShape);
+        let pause_when_stable_cb = link.callback(|_| Msg::TogglePauseWhenStable);
+        let expanding_cb = link.callback(|_| Msg::ToggleExpanding);
+        let select_cb = link.callback(|_| Msg::ToggleSelect);
+        let cell_width_cb =
+            link.batch_callback(|ev: InputData| ev.value.parse().map(Msg::SetCellWidth).ok());
+        let speed_cb =
+            link.batch_callback(|ev: InputData| ev.value.parse().map(Msg::SetSpeed).ok());
         html! {
             <div id="settings">
                 <button id="auto-zoom" onclick=auto_zoom_cb />
                 <button id="auto-run" class={auto_run} onclick=auto_run_cb />
+                <button class="action" onclick=undo_cb title="Ctrl+Z">{ "Undo" }</button>
+                <button class="action" onclick=redo_cb title="Ctrl+Y">{ "Redo" }</button>
+                <button class="action" onclick=fit_cb>{ "Fit to pattern" }</button>
+                <button class="action" onclick=fullscreen_cb title="F">{ "Fullscreen" }</button>
+                <button class="action" onclick=clear_cb>{ "Clear" }</button>
+                { self.image_html(link) }
+                { self.recording_html(link, recording) }
+                <button class="action" onclick=randomize_cb>{ "Randomize" }</button>
+                <label class="setting">
+                    <input type="range" min="0" max="100"
+                           value=((self.density * 100.0).round()).to_string() oninput=density_cb />
+                    { format!("{:.0}% alive", self.density * 100.0) }
+                </label>
+                <input id="seed" type="text" class=seed_class value=self.seed_text.clone()
+                       placeholder=seed_placeholder oninput=seed_cb
+                       title="Seed for randomizing, leave blank for a new one every time" />
+                { Self::size_html(link, supervisor) }
+                <label class="setting" title="Grow the grid instead of wrapping around its edges">
+                    <input type="checkbox" checked=supervisor.expanding() onclick=expanding_cb />
+                    { "Expand at edges" }
+                </label>
+                { Self::topology_html(link, supervisor) }
+                { Self::automaton_html(link) }
+                { self.rule_html(link, supervisor.automaton()) }
+                { Self::parameters_html(link, supervisor.automaton()) }
+                { self.agents_html(link, supervisor.automaton()) }
+                { self.sand_html(link, supervisor.automaton()) }
+                { self.brush_html(link, supervisor.automaton()) }
+                <label class="setting" title="Drag to select, then T to rotate or H / V to flip">
+                    <input type="checkbox" checked=self.select onclick=select_cb />
+                    { "Select region" }
+                </label>
+                { self.pattern_html(link) }
+                { self.library_html(link) }
+                { self.catalogue_html(link) }
+                { Self::palette_html(link, selected_pattern) }
+                <label class="setting">
+                    <input type="range" min=MIN_TICK_MS.to_string() max=MAX_TICK_MS.to_string()
+                           value=self.tick_ms.to_string() oninput=speed_cb />
+                    { format!("{} ms", self.tick_ms) }
+                </label>
+                <label class="setting">
+                    <input type="checkbox" checked=self.pause_when_stable
+                           onclick=pause_when_stable_cb />
+                    { "Pause when stable" }
+                </label>
+                <label class="setting">
+                    <input type="checkbox" checked=self.natural_pan onclick=natural_pan_cb />
+                    { "Natural panning" }
+                </label>
+                <label class="setting" title="Don't let the grid be dragged off the canvas">
+                    <input type="checkbox" checked=self.clamp_pan onclick=clamp_pan_cb />
+                    { "Keep grid in view" }
+                </label>
+                <label class="setting">
+                    <input type="checkbox" checked=self.show_grid onclick=grid_cb />
+                    { "Grid lines" }
+                </label>
+                <label class="setting">
+                    <input type="checkbox" checked=self.heatmap onclick=heatmap_cb />
+                    { "Color by age" }
+                </label>
+                <label class="setting" title="Only shown when zoomed in far enough">
+                    <input type="checkbox" checked=self.neighbor_counts
+                           onclick=neighbor_counts_cb />
+                    { "Neighbor counts" }
+                </label>
+                <label class="setting">
+                    <input type="checkbox" checked=self.cell_shape == CellShape::Circle
+                           onclick=cell_shape_cb />
+                    { "Round cells" }
+                </label>
+                <label class="setting">
+                    <input type="range" min=MIN_CELL_WIDTH.to_string()
+                           max=MAX_CELL_WIDTH.to_string()
+                           value=supervisor.cell_width().to_string() oninput=cell_width_cb />
+                    { format!("{} px cells", supervisor.cell_width()) }
+                </label>
+                { self.theme_html(link) }
+                <button class="action" onclick=reset_cb
+                        title="Forget the saved grid and settings">{ "Reset to defaults" }</button>
+                { Self::shortcuts_html() }
             </div>
         }
     }
+
+    /// Export the grid as an image, optionally at a chosen width.
+    fn image_html<A: Automaton + Default>(&self, link: &ComponentLink<Model<A>>) -> Html {
+        let export_cb = link.callback(|_| Msg::ExportPng);
+        let export_svg_cb = link.callback(|_| Msg::ExportSvg);
+        let width_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Value(width) if width.trim().is_empty() => Some(Msg::SetImageWidth(None)),
+            ChangeData::Value(width) => width.trim().parse().map(Some).map(Msg::SetImageWidth).ok(),
+            _ => None,
+        });
+        let width = self
+            .image_width
+            .map(|width| width.to_string())
+            .unwrap_or_default();
+        let width_title = format!("Leave blank to draw cells {} px wide", EXPORT_CELL_PX);
+        html! {
+            <>
+                <button class="action" onclick=export_cb
+                        title="Download the whole grid as a PNG image">
+                    { "Export image" }
+                </button>
+                <button class="action" onclick=export_svg_cb
+                        title="Download the cells as a scalable SVG image">
+                    { "Export SVG" }
+                </button>
+                <label class="setting" title=width_title>
+                    <input class="size" type="number" min="1" max=MAX_IMAGE_PX.to_string()
+                           value=width placeholder="auto" onchange=width_cb />
+                    { "px wide" }
+                </label>
+            </>
+        }
+    }
+
+    /// Record a number of generations, or stop the recording in progress.
+    fn recording_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        recording: bool,
+    ) -> Html {
+        let frames = self.record_frames;
+        let record_cb = if recording {
+            link.callback(|_| Msg::StopRecording)
+        } else {
+            link.callback(move |_| Msg::StartRecording(frames))
+        };
+        let frames_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Value(frames) => frames.parse().map(Msg::SetRecordFrames).ok(),
+            _ => None,
+        });
+        let cell_px_cb =
+            link.batch_callback(|ev: InputData| ev.value.parse().map(Msg::SetRecordCellPx).ok());
+        let format_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Select(select) => select
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| RecordFormat::ALL.get(idx))
+                .map(|format| Msg::SetRecordFormat(*format)),
+            _ => None,
+        });
+        let formats = RecordFormat::ALL.iter().enumerate().map(|(idx, format)| {
+            html! {
+                <option value=idx.to_string() selected=*format == self.record_format>
+                    { format.name() }
+                </option>
+            }
+        });
+        html! {
+            <>
+                {
+                    if recording {
+                        html! {
+                            <button class="action selected" onclick=record_cb
+                                    title="Stop and download the generations recorded so far">
+                                { "Stop recording" }
+                            </button>
+                        }
+                    } else {
+                        html! {
+                            <button class="action" onclick=record_cb
+                                    title="Record the next generations as an animation">
+                                { format!("Record {}", self.record_format.name()) }
+                            </button>
+                        }
+                    }
+                }
+                <label class="setting" title="WebM videos are recorded in real time">
+                    <select onchange=format_cb disabled=recording>
+                        { for formats }
+                    </select>
+                    { "Format" }
+                </label>
+                <label class="setting">
+                    <input class="size" type="number" min="1" max=MAX_RECORD_FRAMES.to_string()
+                           value=self.record_frames.to_string() onchange=frames_cb />
+                    { "frames" }
+                </label>
+                <label class="setting">
+                    <input type="range" min=MIN_RECORD_CELL_PX.to_string()
+                           max=MAX_RECORD_CELL_PX.to_string()
+                           value=self.record_cell_px.to_string() oninput=cell_px_cb />
+                    { format!("{} px cells in recording", self.record_cell_px) }
+                </label>
+            </>
+        }
+    }
+
+    fn topology_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        supervisor: &Supervisor<A>,
+    ) -> Html {
+        let topology_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Select(select) => select
+                .value()
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| Topology::ALL.get(idx))
+                .map(|topology| Msg::SetTopology(*topology)),
+            _ => None,
+        });
+        let options = Topology::ALL.iter().enumerate().map(|(idx, topology)| {
+            html! {
+                <option value=idx.to_string() selected=*topology == supervisor.topology()>
+                    { topology.name() }
+                </option>
+            }
+        });
+        html! {
+            <label class="setting" title="How the edges are glued together when wrapping around">
+                <select onchange=topology_cb disabled=supervisor.expanding()>
+                    { for options }
+                </select>
+                { "Edges" }
+            </label>
+        }
+    }
+
+    /// Pick the automaton to run, which reloads the page.
+    fn automaton_html<A: Automaton + Default>(link: &ComponentLink<Model<A>>) -> Html {
+        let automaton_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Select(select) => Some(Msg::SelectAutomaton(select.value())),
+            _ => None,
+        });
+        let current = automaton_name();
+        let options = AUTOMATA.iter().map(|(name, label, _)| {
+            html! {
+                <option value=*name selected=*name == current>{ label }</option>
+            }
+        });
+        html! {
+            <label class="setting">
+                <select onchange=automaton_cb>{ for options }</select>
+                { "Automaton" }
+            </label>
+        }
+    }
+
+    fn theme_html<A: Automaton + Default>(&self, link: &ComponentLink<Model<A>>) -> Html {
+        let theme_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Select(select) => select.value().parse().map(Msg::SetTheme).ok(),
+            _ => None,
+        });
+        let options = THEMES.iter().enumerate().map(|(idx, theme)| {
+            html! {
+                <option value=idx.to_string() selected=idx == self.theme>{ theme.name }</option>
+            }
+        });
+        html! {
+            <label class="setting">
+                <select onchange=theme_cb>{ for options }</select>
+                { "Theme" }
+            </label>
+        }
+    }
+
+    fn size_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        supervisor: &Supervisor<A>,
+    ) -> Html {
+        let (width, height) = (supervisor.width(), supervisor.height());
+        let width_cb = link.batch_callback(move |ev| match ev {
+            ChangeData::Value(w) => w.parse().map(|w| Msg::Resize(w, height)).ok(),
+            _ => None,
+        });
+        let height_cb = link.batch_callback(move |ev| match ev {
+            ChangeData::Value(h) => h.parse().map(|h| Msg::Resize(width, h)).ok(),
+            _ => None,
+        });
+        html! {
+            <label class="setting">
+                <input class="size" type="number" min="1" value=width.to_string()
+                       onchange=width_cb />
+                { "×" }
+                <input class="size" type="number" min="1" value=height.to_string()
+                       onchange=height_cb />
+            </label>
+        }
+    }
+
+    fn pattern_html<A: Automaton + Default>(&self, link: &ComponentLink<Model<A>>) -> Html {
+        let text_cb = link.callback(|ev: InputData| Msg::SetPatternText(ev.value));
+        let export_cb = link.callback(|_| Msg::ExportPattern);
+        let export_rle_cb = link.callback(|_| Msg::DownloadPattern(Format::Rle));
+        let export_cells_cb = link.callback(|_| Msg::DownloadPattern(Format::Plaintext));
+        let export_lif_cb = link.callback(|_| Msg::DownloadPattern(Format::Life106));
+        let import_cb = link.callback(|_| Msg::ImportPattern);
+        let file_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Files(files) => files.get(0).map(Msg::OpenFile),
+            _ => None,
+        });
+        html! {
+            <>
+                <textarea id="pattern" value=self.pattern_text.clone() oninput=text_cb
+                          placeholder="Life 1.06, RLE or plaintext pattern" />
+                <button class="action" onclick=export_cb>{ "Export pattern" }</button>
+                <button class="action" onclick=export_rle_cb
+                        title="Download the live cells as RLE">{ "Export RLE" }</button>
+                <button class="action" onclick=export_cells_cb
+                        title="Download the live cells in plaintext format">
+                    { "Export .cells" }
+                </button>
+                <button class="action" onclick=export_lif_cb
+                        title="Download the coordinates of the live cells in Life 1.06 format">
+                    { "Export .lif" }
+                </button>
+                <button class="action" onclick=import_cb>{ "Import pattern" }</button>
+                <label class="action" title="Load a .rle, .cells, .lif or .mc file">
+                    <input id="pattern-file" type="file" accept=".rle,.cells,.lif,.life,.mc"
+                           onchange=file_cb />
+                    { "Open file" }
+                </label>
+            </>
+        }
+    }
+
+    /// Save the grid under a name and load or delete saved patterns.
+    fn library_html<A: Automaton + Default>(&self, link: &ComponentLink<Model<A>>) -> Html {
+        let name_cb = link.callback(|ev: InputData| Msg::SetLibraryName(ev.value));
+        let save_cb = link.callback(|_| Msg::SaveToLibrary);
+        let entry = |name: &String| {
+            let load_cb = {
+                let name = name.clone();
+                link.callback(move |_| Msg::LoadFromLibrary(name.clone()))
+            };
+            let delete_cb = {
+                let name = name.clone();
+                link.callback(move |_| Msg::DeleteFromLibrary(name.clone()))
+            };
+            html! {
+                <li>
+                    <button class="load" onclick=load_cb title="Load this pattern">
+                        { name }
+                    </button>
+                    <button class="delete" onclick=delete_cb title="Delete this pattern">
+                        { "✕" }
+                    </button>
+                </li>
+            }
+        };
+        html! {
+            <div id="library">
+                <input type="text" value=self.library_name.clone() oninput=name_cb
+                       placeholder="Pattern name" />
+                <button class="action" onclick=save_cb disabled=self.library_name.trim().is_empty()>
+                    { "Save to library" }
+                </button>
+                <ul>{ for self.library.iter().map(entry) }</ul>
+            </div>
+        }
+    }
+
+    /// Search the online catalogue and preview a pattern before stamping it.
+    fn catalogue_html<A: Automaton + Default>(&self, link: &ComponentLink<Model<A>>) -> Html {
+        let url_cb = link.callback(|ev: InputData| Msg::SetCatalogueUrl(ev.value));
+        let load_cb = link.callback(|_| Msg::LoadCatalogue);
+        let query_cb = link.callback(|ev: InputData| Msg::SetCatalogueQuery(ev.value));
+        let stamp_cb = link.callback(|_| Msg::StampCataloguePattern);
+        let entry = |entry: &String| {
+            let preview_cb = {
+                let entry = entry.clone();
+                link.callback(move |_| Msg::PreviewCataloguePattern(entry.clone()))
+            };
+            html! {
+                <li>
+                    <button class="load" onclick=preview_cb>{ catalogue::name(entry) }</button>
+                </li>
+            }
+        };
+        let preview = match &self.catalogue_preview {
+            Some(preview) => html! {
+                <div class="preview">
+                    { Html::VRef(preview.thumbnail.clone().into()) }
+                    <button class="action" onclick=stamp_cb
+                            title="Click on the grid to place the pattern">
+                        { format!("Stamp {}", preview.name) }
+                    </button>
+                </div>
+            },
+            None => html! {},
+        };
+        html! {
+            <div id="catalogue">
+                <input type="url" value=self.catalogue_url.clone() oninput=url_cb
+                       title="Index of RLE files, like a directory listing" />
+                <button class="action" onclick=load_cb>{ "Load catalogue" }</button>
+                {
+                    if self.catalogue.is_empty() {
+                        html! {}
+                    } else {
+                        html! {
+                            <>
+                                <input type="search" value=self.catalogue_query.clone()
+                                       oninput=query_cb placeholder="Search patterns" />
+                                <ul>
+                                    { for catalogue::search(&self.catalogue,
+                                                            &self.catalogue_query).map(entry) }
+                                </ul>
+                            </>
+                        }
+                    }
+                }
+                { preview }
+            </div>
+        }
+    }
+
+    /// Buttons to select a built-in pattern to stamp, clicking the selected one deselects it.
+    fn palette_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        selected_pattern: Option<usize>,
+    ) -> Html {
+        let buttons = PATTERNS.iter().enumerate().map(|(idx, (name, _))| {
+            let selected = selected_pattern == Some(idx);
+            let class = if selected {
+                "action selected"
+            } else {
+                "action"
+            };
+            let select_cb =
+                link.callback(move |_| Msg::SelectPattern(if selected { None } else { Some(idx) }));
+            html! {
+                <button class=class onclick=select_cb>{ name }</button>
+            }
+        });
+        let transforms = if selected_pattern.is_some() {
+            let rotate_cb = link.callback(|_| Msg::Transform(Transform::RotateCw));
+            let flip_cb = link.callback(|_| Msg::Transform(Transform::FlipH));
+            html! {
+                <>
+                    <button class="action" onclick=rotate_cb title="T">{ "Rotate" }</button>
+                    <button class="action" onclick=flip_cb title="H">{ "Flip" }</button>
+                </>
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <div id="palette">{ for buttons }{ transforms }</div>
+        }
+    }
+
+    fn shortcuts_html() -> Html {
+        let rows = SHORTCUT_HELP.iter().map(|(key, action)| {
+            html! {
+                <tr><td><kbd>{ key }</kbd></td><td>{ action }</td></tr>
+            }
+        });
+        html! {
+            <table id="shortcuts">{ for rows }</table>
+        }
+    }
+
+    /// A slider for each parameter of the rule, see [`Automaton::parameters`].
+    fn parameters_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        automaton: &A,
+    ) -> Html {
+        let sliders = automaton
+            .parameters()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, parameter)| {
+                let parameter_cb = link.batch_callback(move |ev: InputData| {
+                    ev.value
+                        .parse()
+                        .map(|value| Msg::SetParameter(idx, value))
+                        .ok()
+                });
+                html! {
+                    <label class="setting">
+                        <input type="range" min=parameter.min.to_string()
+                               max=parameter.max.to_string() step=parameter.step.to_string()
+                               value=parameter.value.to_string() oninput=parameter_cb />
+                        { format!("{} = {}", parameter.name, parameter.value) }
+                    </label>
+                }
+            });
+        html! { <>{ for sliders }</> }
+    }
+
+    /// Checkbox to place agents, only for automata that have agents.
+    fn agents_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        automaton: &A,
+    ) -> Html {
+        if automaton.toggle_agent(&A::State::default(), 0).is_none() {
+            return html! {};
+        }
+        let place_agents_cb = link.callback(|_| Msg::TogglePlaceAgents);
+        html! {
+            <label class="setting" title="Click to add or remove agents">
+                <input type="checkbox" checked=self.place_agents onclick=place_agents_cb />
+                { "Place agents" }
+            </label>
+        }
+    }
+
+    /// Checkbox to drop sand, only for automata that have sand.
+    fn sand_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        automaton: &A,
+    ) -> Html {
+        if automaton.drop_sand(&A::State::default()).is_none() {
+            return html! {};
+        }
+        let drop_sand_cb = link.callback(|_| Msg::ToggleDropSand);
+        html! {
+            <label class="setting" title="Hold the mouse to drop grains of sand at the cursor">
+                <input type="checkbox" checked=self.drop_sand onclick=drop_sand_cb />
+                { "Drop sand" }
+            </label>
+        }
+    }
+
+    /// Swatches to pick the painted state, only for automata with more than two states.
+    fn brush_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        automaton: &A,
+    ) -> Html {
+        let states = automaton.states();
+        if states.len() <= 2 {
+            return html! {};
+        }
+        let theme = &THEMES[self.theme];
+        let swatches = states.iter().enumerate().map(|(idx, state)| {
+            let class = if idx == self.brush {
+                "swatch selected"
+            } else {
+                "swatch"
+            };
+            let style = format!("background-color: {}", automaton.style(state, theme));
+            let select_cb = link.callback(move |_| Msg::SetBrush(idx));
+            html! {
+                <button class=class style=style onclick=select_cb
+                        title=format!("Paint state {}", idx) />
+            }
+        });
+        html! {
+            <div id="brush" title="State painted by dragging">{ for swatches }</div>
+        }
+    }
+
+    fn rule_html<A: Automaton + Default>(
+        &self,
+        link: &ComponentLink<Model<A>>,
+        automaton: &A,
+    ) -> Html {
+        let rule = match automaton.rule() {
+            Some(rule) => rule,
+            None => return html! {},
+        };
+        let set_rule_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Value(rule) => vec![Msg::SetRule(rule)],
+            _ => vec![],
+        });
+        let (text, class, title) = match &self.invalid_rule {
+            Some((text, error)) => (text.clone(), "invalid", format!("Invalid rule: {}", error)),
+            None => (
+                rule.clone(),
+                "",
+                "Rule, e.g. B3/S23 in B/S notation, B3/S23/I to count the center cell or 110 for elementary automata".to_owned(),
+            ),
+        };
+        html! {
+            <>
+                <input id="rule" type="text" class=class value=text onchange=set_rule_cb
+                       placeholder="B3/S23, or B3/S23/I to count the center" title=title />
+                { Self::rule_editor_html(link, &rule) }
+                { Self::turmite_editor_html(link, &rule) }
+            </>
+        }
+    }
+
+    /// Checkboxes for the birth and survival counts, if `rule` is in B/S notation,
+    /// and whether the center cell counts into the sum, if the automaton supports it.
+    fn rule_editor_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        rule: &str,
+    ) -> Html {
+        let rule = match TotalisticRule::parse(rule) {
+            Ok(rule) => rule,
+            Err(_) => return html! {},
+        };
+        let row = |name: &str, center: usize| {
+            let boxes = count_checks(rule.table[center])
+                .into_iter()
+                .enumerate()
+                .map(|(count, checked)| {
+                    let mut toggled = rule.clone();
+                    toggled.table[center] ^= 1 << count;
+                    let set_rule_cb = link.callback(move |_| Msg::SetTotalistic(toggled.clone()));
+                    html! {
+                        <label title=format!("{} with a sum of {}", name, count)>
+                            <input type="checkbox" checked=checked onclick=set_rule_cb />
+                            { count }
+                        </label>
+                    }
+                });
+            html! {
+                <div class="counts">{ name }{ for boxes }</div>
+            }
+        };
+        let kind_html = if has_inner_rules::<A>() {
+            let toggled = TotalisticRule {
+                kind: match rule.kind {
+                    TotalisticKind::Inner => TotalisticKind::Outer,
+                    TotalisticKind::Outer => TotalisticKind::Inner,
+                },
+                ..rule.clone()
+            };
+            let kind_cb = link.callback(move |_| Msg::SetTotalistic(toggled.clone()));
+            html! {
+                <label title="Inner totalistic, add the cell itself to the sum of its neighbors (/I)">
+                    <input type="checkbox" checked=rule.kind == TotalisticKind::Inner
+                           onclick=kind_cb />
+                    { "Count center" }
+                </label>
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <div id="rule-editor">
+                { row("Born", 0) }
+                { row("Survive", 1) }
+                { kind_html }
+            </div>
+        }
+    }
+
+    /// Presets and a transition table editor, if `rule` is a turmite table.
+    fn turmite_editor_html<A: Automaton + Default>(
+        link: &ComponentLink<Model<A>>,
+        rule: &str,
+    ) -> Html {
+        let turmite = match Turmite::parse(rule) {
+            Ok(turmite) => turmite,
+            Err(_) => return html! {},
+        };
+        let preset_cb = link.batch_callback(|ev| match ev {
+            ChangeData::Select(select) => TURMITE_PRESETS
+                .get(select.value().parse::<usize>().ok()?)
+                .map(|(_, table)| Msg::SetRule(table.to_string())),
+            _ => None,
+        });
+        let is_preset = TURMITE_PRESETS.iter().any(|(_, table)| *table == rule);
+        let presets = TURMITE_PRESETS
+            .iter()
+            .enumerate()
+            .map(|(idx, (name, table))| {
+                html! {
+                    <option value=idx.to_string() selected=*table == rule>{ name }</option>
+                }
+            });
+        let resize = |states, colors| {
+            let mut resized = turmite.clone();
+            resized.resize(states, colors);
+            let rule = resized.to_string();
+            link.callback(move |_| Msg::SetRule(rule.clone()))
+        };
+        let (states, colors) = (turmite.state_count(), turmite.colors());
+        let header = (0..colors).map(|color| html! { <th>{ format!("Color {}", color) }</th> });
+        let rows = turmite
+            .table()
+            .iter()
+            .enumerate()
+            .map(|(state, transitions)| {
+                let cells = transitions.iter().enumerate().map(|(color, &transition)| {
+                    let set = |change: fn(Transition, u8) -> Transition| {
+                        let turmite = turmite.clone();
+                        link.batch_callback(move |ev| {
+                            let value = match ev {
+                                ChangeData::Value(value) => value.parse().ok()?,
+                                ChangeData::Select(select) => select.value().parse().ok()?,
+                                _ => return None,
+                            };
+                            let mut turmite = turmite.clone();
+                            turmite.set_transition(state, color, change(transition, value));
+                            Some(Msg::SetRule(turmite.to_string()))
+                        })
+                    };
+                    let turns = Turn::ALL.iter().enumerate().map(|(idx, turn)| {
+                        html! {
+                            <option value=idx.to_string() selected=*turn == transition.turn>
+                                { turn.name() }
+                            </option>
+                        }
+                    });
+                    html! {
+                        <td>
+                            <input type="number" min="0" max=(colors - 1).to_string()
+                                   value=transition.write.to_string() title="Color to write"
+                                   onchange=set(|t, write| Transition { write, ..t }) />
+                            <select title="Turn" onchange=set(|t, turn| Transition {
+                                turn: Turn::ALL[turn as usize % Turn::ALL.len()],
+                                ..t
+                            })>
+                                { for turns }
+                            </select>
+                            <input type="number" min="0" max=(states - 1).to_string()
+                                   value=transition.next.to_string() title="Next state"
+                                   onchange=set(|t, next| Transition { next, ..t }) />
+                        </td>
+                    }
+                });
+                html! {
+                    <tr><th>{ format!("State {}", state) }</th>{ for cells }</tr>
+                }
+            });
+        html! {
+            <div id="turmite-editor">
+                <label class="setting">
+                    <select onchange=preset_cb>
+                        <option value="" selected=!is_preset disabled=true>{ "Preset…" }</option>
+                        { for presets }
+                    </select>
+                </label>
+                <table>
+                    <tr><th></th>{ for header }</tr>
+                    { for rows }
+                </table>
+                <button class="action" onclick=resize(states + 1, colors)
+                        disabled=states == MAX_TURMITE_STATES>{ "+ State" }</button>
+                <button class="action" onclick=resize(states - 1, colors)
+                        disabled=states == 1>{ "− State" }</button>
+                <button class="action" onclick=resize(states, colors + 1)
+                        disabled=colors == MAX_TURMITE_COLORS>{ "+ Color" }</button>
+                <button class="action" onclick=resize(states, colors - 1)
+                        disabled=colors == 1>{ "− Color" }</button>
+            </div>
+        }
+    }
+}
+
+/// Whether each neighbor count from zero to eight is set in the bit `mask`,
+/// see [`LifeLike`].
+fn count_checks(mask: u16) -> [bool; 9] {
+    [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|count| mask >> count & 1 == 1)
+}
+
+/// Whether automata of type `A` accept inner totalistic rules, i.e. the `/I` suffix.
+fn has_inner_rules<A: Automaton + Default>() -> bool {
+    A::default().set_rule("B3/S23/I").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::LifeLike;
+
+    /// Inverse of [`count_checks`].
+    fn checks_mask(checks: [bool; 9]) -> u16 {
+        checks
+            .iter()
+            .enumerate()
+            .filter(|(_, &checked)| checked)
+            .fold(0, |mask, (count, _)| mask | 1 << count)
+    }
+
+    #[test]
+    fn automaton_picker_lists_each_name_once() {
+        let names: Vec<_> = AUTOMATA.iter().map(|(name, ..)| *name).collect();
+        assert!(names.contains(&crate::DEFAULT_AUTOMATON));
+        for (idx, name) in names.iter().enumerate() {
+            assert!(!names[idx + 1..].contains(name), "{} is listed twice", name);
+        }
+    }
+
+    #[test]
+    fn day_and_night_is_in_the_picker() {
+        let (_, label, _) = AUTOMATA
+            .iter()
+            .find(|(name, ..)| *name == "day-and-night")
+            .unwrap();
+        assert_eq!(*label, "Day & Night");
+    }
+
+    #[test]
+    fn center_toggle_only_for_totalistic_rules() {
+        assert!(has_inner_rules::<TotalisticRule>());
+        assert!(!has_inner_rules::<LifeLike>());
+        let rule = TotalisticRule::parse("B3/S23/I").unwrap();
+        let outer = TotalisticRule {
+            kind: TotalisticKind::Outer,
+            ..rule.clone()
+        };
+        assert_eq!(outer.to_string(), "B3/S23");
+        assert_eq!(TotalisticRule::parse(&rule.to_string()), Ok(rule));
+    }
+
+    #[test]
+    fn rule_checkboxes_match_rule_strings() {
+        let life = LifeLike::default();
+        let born = count_checks(life.born);
+        let survive = count_checks(life.survive);
+        assert_eq!(born.iter().filter(|&&checked| checked).count(), 1);
+        assert!(born[3] && survive[2] && survive[3] && !survive[8]);
+        for rule in ["B3/S23", "B36/S23", "B0/S8", "B/S", "B012345678/S012345678"] {
+            let parsed = LifeLike::parse(rule).unwrap();
+            let born = count_checks(parsed.born);
+            let survive = count_checks(parsed.survive);
+            assert_eq!(
+                (checks_mask(born), checks_mask(survive)),
+                (parsed.born, parsed.survive)
+            );
+            // Checked boxes list the same counts as the string
+            let listed = |checks: [bool; 9]| -> String {
+                (0..9)
+                    .filter(|&n| checks[n])
+                    .map(|n| n.to_string())
+                    .collect()
+            };
+            assert_eq!(format!("B{}/S{}", listed(born), listed(survive)), rule);
+            assert_eq!(parsed.to_string(), rule);
+        }
+        // Checking a box adds the count to the string
+        let mut checks = count_checks(life.born);
+        checks[6] = true;
+        let highlife = LifeLike {
+            born: checks_mask(checks),
+            ..life
+        };
+        assert_eq!(highlife.to_string(), "B36/S23");
+        assert_eq!(LifeLike::parse("B36/S23"), Ok(highlife));
+    }
+
+    #[test]
+    fn blank_seed_is_random() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.seed(), Ok(None));
+        settings.set_seed_text(" 42 ".to_owned());
+        assert_eq!(settings.seed(), Ok(Some(42)));
+        settings.set_seed_text("forty-two".to_owned());
+        assert!(settings.seed().is_err());
+        settings.set_seed_text(String::new());
+        assert_eq!(settings.seed(), Ok(None));
+    }
+
+    #[test]
+    fn pan_delta_follows_natural_pan() {
+        let mut settings = Settings::default();
+        let drag = Vector2::new(3.0, -4.0);
+        assert_eq!(settings.pan_delta(drag), drag);
+        settings.toggle_natural_pan();
+        assert_eq!(settings.pan_delta(drag), Vector2::new(-3.0, 4.0));
+    }
+
+    #[test]
+    fn repeated_play_does_not_change_state() {
+        let mut settings = Settings::default();
+        assert!(settings.set_auto_run(true));
+        assert!(!settings.set_auto_run(true));
+        assert!(settings.auto_run());
+        assert!(settings.set_auto_run(false));
+        assert!(!settings.set_auto_run(false));
+    }
+
+    #[test]
+    fn cell_shape_reaches_render_options() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.render_options().cell_shape, CellShape::Square);
+        settings.toggle_cell_shape();
+        assert_eq!(settings.render_options().cell_shape, CellShape::Circle);
+        settings.toggle_cell_shape();
+        assert_eq!(settings.render_options().cell_shape, CellShape::Square);
+    }
+
+    #[test]
+    fn tick_interval_is_clamped() {
+        let mut settings = Settings::default();
+        settings.set_tick_ms(0);
+        assert_eq!(settings.tick_ms(), MIN_TICK_MS);
+        settings.set_tick_ms(u32::MAX);
+        assert_eq!(settings.tick_ms(), MAX_TICK_MS);
+        settings.set_tick_ms(250);
+        assert_eq!(settings.tick_ms(), 250);
+    }
 }
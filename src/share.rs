@@ -0,0 +1,338 @@
+//! Sharing the grid, rule and seed through the URL hash.
+//!
+//! The state is encoded as URL-safe base64 (without padding) of the bytes
+//! `[version, width (4 bytes BE), height (4 bytes BE), ...]`. In version
+//! [`VERSION`] the rule follows as a length byte and its UTF-8 bytes, an
+//! empty rule meaning none. Then a byte tells whether a seed follows, and
+//! the seed as LEB128 varint. The cells follow row by row as alternating
+//! runs of dead and alive cells, starting with dead ones, where every run
+//! length is a varint as well. Most grids are mostly dead, so this is a lot
+//! shorter than one bit per cell, which version [`VERSION_BITS`] used: the
+//! cells packed eight per byte, most significant bit first, without a rule.
+//! Version [`VERSION_RUNS`] is the current encoding without the seed.
+use wasm_bindgen::JsValue;
+
+use crate::automaton::{cell_count, Grid, LifeStates, MAX_CELLS};
+
+/// Tag byte of the current encoding.
+pub const VERSION: u8 = 3;
+
+/// Tag byte of the run-length encoding without a seed, still accepted by [`decode`].
+pub const VERSION_RUNS: u8 = 2;
+
+/// Tag byte of the original bit-packed encoding, still accepted by [`decode`].
+pub const VERSION_BITS: u8 = 1;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A grid with the rule of its automaton, as read from a link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shared {
+    pub cells: Grid<LifeStates>,
+    pub rule: Option<String>,
+    /// Seed the grid was randomized with, see [`Supervisor::seed`].
+    ///
+    /// [`Supervisor::seed`]: crate::supervisor::Supervisor::seed
+    pub seed: Option<u64>,
+}
+
+/// Encode the live cells of `grid`, the `rule` and the `seed` as a URL-safe string.
+///
+/// Rules longer than 255 bytes are left out.
+pub fn encode(grid: &Grid<LifeStates>, rule: Option<&str>, seed: Option<u64>) -> String {
+    let mut bytes = vec![VERSION];
+    bytes.extend_from_slice(&(grid.width() as u32).to_be_bytes());
+    bytes.extend_from_slice(&(grid.height() as u32).to_be_bytes());
+    let rule = rule
+        .filter(|rule| rule.len() <= u8::MAX as usize)
+        .unwrap_or("");
+    bytes.push(rule.len() as u8);
+    bytes.extend_from_slice(rule.as_bytes());
+    match seed {
+        Some(seed) => {
+            bytes.push(1);
+            push_varint(&mut bytes, seed);
+        }
+        None => bytes.push(0),
+    }
+    let cells = (0..grid.height() as isize)
+        .flat_map(|y| (0..grid.width() as isize).map(move |x| (x, y)))
+        .map(|pos| grid[pos] == LifeStates::Alife);
+    let (mut state, mut run) = (false, 0u64);
+    for alive in cells {
+        if alive != state {
+            push_varint(&mut bytes, run);
+            (state, run) = (alive, 0);
+        }
+        run += 1;
+    }
+    push_varint(&mut bytes, run);
+    to_base64(&bytes)
+}
+
+/// Decode a string created by [`encode`], `None` if it is invalid.
+pub fn decode(input: &str) -> Option<Shared> {
+    let bytes = from_base64(input)?;
+    let (&version, rest) = bytes.split_first()?;
+    if rest.len() < 8 {
+        return None;
+    }
+    let width = u32::from_be_bytes(rest[0..4].try_into().ok()?) as usize;
+    let height = u32::from_be_bytes(rest[4..8].try_into().ok()?) as usize;
+    let count = cell_count(width, height, MAX_CELLS).ok()?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut grid = Grid::generate(width, height);
+    let mut set = |idx: usize| {
+        grid[((idx % width) as isize, (idx / width) as isize)] = LifeStates::Alife;
+    };
+    let (rule, seed) = match version {
+        VERSION_BITS => {
+            let cells = &rest[8..];
+            if cells.len() != count.div_ceil(8) {
+                return None;
+            }
+            (0..count)
+                .filter(|idx| cells[idx / 8] >> (7 - idx % 8) & 1 == 1)
+                .for_each(&mut set);
+            (None, None)
+        }
+        VERSION_RUNS | VERSION => {
+            let (&rule_len, rest) = rest[8..].split_first()?;
+            let rule = rest.get(..rule_len as usize)?;
+            let rule = String::from_utf8(rule.to_vec()).ok()?;
+            let mut runs = &rest[rule_len as usize..];
+            let seed = match version {
+                VERSION_RUNS => None,
+                _ => {
+                    let (&has_seed, rest) = runs.split_first()?;
+                    runs = rest;
+                    match has_seed {
+                        0 => None,
+                        1 => Some(read_varint(&mut runs)?),
+                        _ => return None,
+                    }
+                }
+            };
+            let (mut idx, mut alive): (usize, bool) = (0, false);
+            while !runs.is_empty() {
+                let run = usize::try_from(read_varint(&mut runs)?).ok()?;
+                let end = idx.checked_add(run).filter(|&end| end <= count)?;
+                if alive {
+                    (idx..end).for_each(&mut set);
+                }
+                (idx, alive) = (end, !alive);
+            }
+            if idx != count {
+                return None;
+            }
+            (Some(rule).filter(|rule| !rule.is_empty()), seed)
+        }
+        _ => return None,
+    };
+    Some(Shared {
+        cells: grid,
+        rule,
+        seed,
+    })
+}
+
+/// Append `value` as LEB128 varint, seven bits per byte, least significant first.
+fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Read a LEB128 varint from the start of `bytes`, advancing past it.
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The grid, rule and seed encoded in the current URL hash, if there is a valid one.
+pub fn from_location() -> Option<Shared> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    decode(hash.strip_prefix('#')?)
+}
+
+/// Replace the URL hash with the encoded grid, rule and seed, without adding a history entry.
+pub fn to_location(
+    grid: &Grid<LifeStates>,
+    rule: Option<&str>,
+    seed: Option<u64>,
+) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let url = format!("#{}", encode(grid, rule, seed));
+    window
+        .history()?
+        .replace_state_with_url(&JsValue::NULL, "", Some(&url))
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (idx, &byte)| {
+            bits | (byte as u32) << (16 - 8 * idx)
+        });
+        // One more character than full bytes, i.e. no padding
+        for idx in 0..=chunk.len() {
+            out.push(BASE64[(bits >> (18 - 6 * idx) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn from_base64(input: &str) -> Option<Vec<u8>> {
+    let values = input
+        .bytes()
+        .map(|c| BASE64.iter().position(|&b| b == c).map(|v| v as u32))
+        .collect::<Option<Vec<_>>>()?;
+    let mut out = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (idx, value)| bits | value << (18 - 6 * idx));
+        for idx in 0..chunk.len() - 1 {
+            out.push((bits >> (16 - 8 * idx)) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(grid: &Grid<LifeStates>) {
+        for (rule, seed) in [(None, None), (Some("B36/S23"), Some(u64::MAX))] {
+            let encoded = encode(grid, rule, seed);
+            assert!(encoded
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+            let shared = decode(&encoded).unwrap();
+            assert_eq!(&shared.cells, grid);
+            assert_eq!(shared.rule.as_deref(), rule);
+            assert_eq!(shared.seed, seed);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(&Grid::generate(20, 20));
+        let mut full = Grid::generate(13, 7);
+        for x in 0..13 {
+            for y in 0..7 {
+                full[(x, y)] = LifeStates::Alife;
+            }
+        }
+        round_trip(&full);
+        let mut glider = Grid::generate(5, 5);
+        for pos in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            glider[pos] = LifeStates::Alife;
+        }
+        round_trip(&glider);
+        round_trip(&Grid::generate(1, 1));
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|b: u8| b.wrapping_mul(37) ^ 0xa5).collect();
+            assert_eq!(from_base64(&to_base64(&bytes)), Some(bytes));
+        }
+        assert_eq!(to_base64(b"Man"), "TWFu");
+    }
+
+    #[test]
+    fn sparse_grids_are_short() {
+        let mut grid = Grid::generate(200, 200);
+        for pos in [(101, 100), (102, 101), (100, 102), (101, 102), (102, 102)] {
+            grid[pos] = LifeStates::Alife;
+        }
+        // A bit per cell would take over 6000 characters
+        assert!(encode(&grid, Some("B3/S23"), Some(1234)).len() < 50);
+    }
+
+    #[test]
+    fn bit_packed_links_still_work() {
+        // A glider in a 5×5 grid, encoded with one bit per cell
+        let bytes = [1, 0, 0, 0, 5, 0, 0, 0, 5, 0b0100_0001, 0b0011_1000, 0, 0];
+        let shared = decode(&to_base64(&bytes)).unwrap();
+        assert_eq!(shared.rule, None);
+        let alive: Vec<_> = (0..5)
+            .flat_map(|y| (0..5).map(move |x| (x, y)))
+            .filter(|&pos| shared.cells[pos] == LifeStates::Alife)
+            .collect();
+        assert_eq!(alive, [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn links_without_seed_still_work() {
+        // A 2×1 grid with the right cell alive and a rule, but no seed
+        let mut bytes = vec![VERSION_RUNS, 0, 0, 0, 2, 0, 0, 0, 1, 2];
+        bytes.extend_from_slice(b"B2");
+        bytes.extend_from_slice(&[1, 1]);
+        let shared = decode(&to_base64(&bytes)).unwrap();
+        assert_eq!(shared.rule.as_deref(), Some("B2"));
+        assert_eq!(shared.seed, None);
+        assert_eq!(shared.cells[(1, 0)], LifeStates::Alife);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            push_varint(&mut bytes, value);
+            let mut slice = &bytes[..];
+            assert_eq!(read_varint(&mut slice), Some(value));
+            assert!(slice.is_empty());
+        }
+        assert_eq!(read_varint(&mut &[0x80][..]), None);
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let valid = encode(&Grid::generate(4, 4), None, None);
+        assert!(decode(&valid).is_some());
+        assert!(decode("").is_none());
+        assert!(decode("!!").is_none());
+        assert!(decode(&valid[..valid.len() - 2]).is_none());
+        // Wrong version tag
+        assert!(decode(&format!("C{}", &valid[1..])).is_none());
+        // Runs that don't cover the grid exactly
+        let mut bytes = vec![VERSION, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0];
+        for runs in [&[3][..], &[2, 3], &[1, 1, 1, 1, 1]] {
+            let mut bytes = bytes.clone();
+            bytes.extend_from_slice(runs);
+            assert!(decode(&to_base64(&bytes)).is_none());
+        }
+        // A run that overflows the cell index
+        let mut overflow = bytes.clone();
+        push_varint(&mut overflow, 1);
+        push_varint(&mut overflow, u64::MAX);
+        assert!(decode(&to_base64(&overflow)).is_none());
+        // An invalid seed marker
+        let mut marker = bytes.clone();
+        marker[10] = 2;
+        marker.extend_from_slice(&[1, 1, 2]);
+        assert!(decode(&to_base64(&marker)).is_none());
+        bytes.extend_from_slice(&[1, 1, 2]);
+        assert!(decode(&to_base64(&bytes)).is_some());
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "web")]
+use yew::{html, Html};
+
+/// Number of generations shown in the population graph.
+pub const POPULATION_HISTORY: usize = 200;
+/// Size of the population graph in pixels.
+#[cfg(feature = "web")]
+const GRAPH_WIDTH: usize = 200;
+#[cfg(feature = "web")]
+const GRAPH_HEIGHT: usize = 50;
+
+/// The last `capacity` values pushed, older values are dropped.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    values: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.values.len() >= self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// The values from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
+        self.values.iter()
+    }
+}
+
+/// Line graph of the population counts, scaled to the largest count.
+#[cfg(feature = "web")]
+pub fn population_graph(counts: &RingBuffer<usize>) -> Html {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let step = GRAPH_WIDTH as f64 / (POPULATION_HISTORY - 1) as f64;
+    let points: Vec<_> = counts
+        .iter()
+        .enumerate()
+        .map(|(idx, &count)| {
+            let y = GRAPH_HEIGHT as f64 * (1.0 - count as f64 / max as f64);
+            format!("{:.1},{:.1}", idx as f64 * step, y)
+        })
+        .collect();
+    html! {
+        <div id="population" class="over" title=format!("Population, at most {}", max)>
+            <svg width=GRAPH_WIDTH.to_string() height=GRAPH_HEIGHT.to_string()
+                 viewBox=format!("0 0 {} {}", GRAPH_WIDTH, GRAPH_HEIGHT)>
+                <polyline points=points.join(" ") />
+            </svg>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer_drops_oldest() {
+        let mut buffer = RingBuffer::new(3);
+        assert_eq!(buffer.iter().count(), 0);
+        for value in 1..=5 {
+            buffer.push(value);
+        }
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+        buffer.clear();
+        buffer.push(6);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![6]);
+    }
+}
@@ -0,0 +1,290 @@
+//! Persisting the grid, view and settings in the browser's local storage.
+//!
+//! # Format
+//!
+//! Every automaton saves its state as plain text under its own key, see
+//! [`storage_key`]. The keys contain the format version. Version 3 consists
+//! of the following lines:
+//!
+//! 1. The view as translation and scale, separated by spaces, or `auto`
+//!    if the view fits the grid into the canvas.
+//! 2. The settings, see [`Settings::to_storage`].
+//! 3. The rule of the automaton, empty if it has none.
+//! 4. The grid with all cell states as a JSON snapshot, see [`Grid::to_json`].
+//!
+//! Versions 1 and 2 were shared by all automata and only stored the live
+//! cells, so they are only read by the default automaton, see [`load_legacy`].
+//! Version 2, stored under [`STORAGE_KEY_V2`], consists of the following lines:
+//!
+//! 1. Grid width and height, separated by a space.
+//! 2. The view, like in version 3.
+//! 3. The settings.
+//! 4. The live cells in Life 1.06 format, relative to the grid center,
+//!    including the `#Life 1.06` header.
+//!
+//! Version 1, stored under [`STORAGE_KEY_V1`], lacks the view line.
+//!
+//! Incompatible changes must use a new key, so that old data is ignored
+//! (or migrated explicitly) instead of being misread.
+use nalgebra::Translation2;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+use web_sys::Storage;
+
+use crate::{
+    automaton::{cell_count, Grid, LifeStates, SnapshotError, MAX_CELLS},
+    pattern::{life106_cells, place_centered},
+    settings::Settings,
+};
+
+/// Prefix of the keys of version 3, see [`storage_key`].
+pub const STORAGE_KEY: &str = "cellular-automaton/v3";
+
+/// Key of the state saved before it was stored per automaton.
+pub const STORAGE_KEY_V2: &str = "cellular-automaton/v2";
+
+/// Key of the state saved before the view was persisted.
+pub const STORAGE_KEY_V1: &str = "cellular-automaton/v1";
+
+/// A manually chosen view as translation and scale, `None` if it fits the grid.
+pub type SavedView = Option<(Translation2<f64>, f64)>;
+
+/// Everything restored on startup.
+pub struct Saved<State> {
+    pub settings: Settings,
+    pub cells: Grid<State>,
+    pub rule: Option<String>,
+    pub view: SavedView,
+}
+
+/// Key of the state of the automaton called `automaton`.
+pub fn storage_key(automaton: &str) -> String {
+    format!("{}/{}", STORAGE_KEY, automaton)
+}
+
+/// Serialize the grid, rule, view and settings, see the [module documentation](self).
+pub fn serialize<State: Serialize + DeserializeOwned>(
+    settings: &Settings,
+    cells: &Grid<State>,
+    rule: Option<&str>,
+    view: SavedView,
+) -> Result<String, SnapshotError> {
+    Ok(format!(
+        "{}\n{}\n{}\n{}",
+        serialize_view(view),
+        settings.to_storage(),
+        rule.unwrap_or(""),
+        cells.to_json()?
+    ))
+}
+
+/// Read state written by [`serialize`], `None` if it is corrupt.
+pub fn deserialize<State: Serialize + DeserializeOwned>(input: &str) -> Option<Saved<State>> {
+    let mut lines = input.splitn(4, '\n');
+    let view = deserialize_view(lines.next()?)?;
+    let settings = Settings::from_storage(lines.next()?)?;
+    let rule = Some(lines.next()?.to_owned()).filter(|rule| !rule.is_empty());
+    let cells = Grid::from_json(lines.next()?).ok()?;
+    Some(Saved {
+        settings,
+        cells,
+        rule,
+        view,
+    })
+}
+
+fn serialize_view(view: SavedView) -> String {
+    match view {
+        Some((trans, scale)) => format!("{} {} {}", trans.x, trans.y, scale),
+        None => "auto".to_owned(),
+    }
+}
+
+/// Read a view line, the outer `None` if it is corrupt.
+fn deserialize_view(view: &str) -> Option<SavedView> {
+    if view == "auto" {
+        return Some(None);
+    }
+    let values: Vec<f64> = view
+        .split(' ')
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match values[..] {
+        [x, y, scale] if scale > 0.0 && values.iter().all(|v| v.is_finite()) => {
+            Some(Some((Translation2::new(x, y), scale)))
+        }
+        _ => None,
+    }
+}
+
+/// Read version 2 state, `None` if it is corrupt.
+fn deserialize_v2(input: &str) -> Option<Saved<LifeStates>> {
+    let (size, rest) = input.split_once('\n')?;
+    let (view, rest) = rest.split_once('\n')?;
+    let view = deserialize_view(view)?;
+    let (settings, cells) = deserialize_v1(&format!("{}\n{}", size, rest))?;
+    Some(Saved {
+        settings,
+        cells,
+        rule: None,
+        view,
+    })
+}
+
+/// Read version 1 state, which has no view line.
+fn deserialize_v1(input: &str) -> Option<(Settings, Grid<LifeStates>)> {
+    let mut parts = input.splitn(3, '\n');
+    let mut size = parts.next()?.split(' ').map(str::parse::<usize>);
+    let (width, height) = match (size.next(), size.next(), size.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) if width > 0 && height > 0 => (width, height),
+        _ => return None,
+    };
+    cell_count(width, height, MAX_CELLS).ok()?;
+    let settings = Settings::from_storage(parts.next()?)?;
+    let cells = life106_cells(parts.next()?).ok()?;
+    Some((settings, place_centered(&cells, width, height).ok()?))
+}
+
+/// Save the state of the automaton called `automaton`, replacing any
+/// previously saved state of it.
+pub fn save<State: Serialize + DeserializeOwned>(
+    automaton: &str,
+    settings: &Settings,
+    cells: &Grid<State>,
+    rule: Option<&str>,
+    view: SavedView,
+) -> Result<(), JsValue> {
+    let stored = serialize(settings, cells, rule, view).map_err(|e| e.to_string())?;
+    local_storage()?.set_item(&storage_key(automaton), &stored)
+}
+
+/// Load the saved state of the automaton called `automaton`, if there is any valid one.
+pub fn load<State: Serialize + DeserializeOwned>(automaton: &str) -> Option<Saved<State>> {
+    let stored = local_storage()
+        .ok()?
+        .get_item(&storage_key(automaton))
+        .ok()??;
+    deserialize(&stored)
+}
+
+/// Load the live cells saved before the state was stored per automaton.
+pub fn load_legacy() -> Option<Saved<LifeStates>> {
+    let storage = local_storage().ok()?;
+    if let Some(stored) = storage.get_item(STORAGE_KEY_V2).ok()? {
+        return deserialize_v2(&stored);
+    }
+    let stored = storage.get_item(STORAGE_KEY_V1).ok()??;
+    let (settings, cells) = deserialize_v1(&stored)?;
+    Some(Saved {
+        settings,
+        cells,
+        rule: None,
+        view: None,
+    })
+}
+
+/// Forget the saved state of the automaton called `automaton` and the
+/// state saved before it was stored per automaton.
+pub fn clear(automaton: &str) -> Result<(), JsValue> {
+    let storage = local_storage()?;
+    storage.remove_item(&storage_key(automaton))?;
+    storage.remove_item(STORAGE_KEY_V2)?;
+    storage.remove_item(STORAGE_KEY_V1)
+}
+
+fn local_storage() -> Result<Storage, JsValue> {
+    web_sys::window()
+        .ok_or("No window")?
+        .local_storage()?
+        .ok_or_else(|| "No local storage".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{automaton::WireState, pattern::to_life106};
+
+    #[test]
+    fn round_trip() {
+        let mut settings = Settings::default();
+        settings.set_density(0.75);
+        settings.set_tick_ms(250);
+        settings.toggle_grid();
+        let mut cells = Grid::generate(7, 4);
+        cells[(0, 0)] = WireState::Conductor;
+        cells[(6, 3)] = WireState::ElectronHead;
+        cells[(3, 1)] = WireState::ElectronTail;
+        let view = Some((Translation2::new(-12.5, 3.0), 1.75));
+        let stored = serialize(&settings, &cells, Some("B3/S23"), view).unwrap();
+        let restored = deserialize::<WireState>(&stored).unwrap();
+        assert_eq!(restored.cells, cells);
+        assert_eq!(restored.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(restored.settings.to_storage(), settings.to_storage());
+        assert_eq!(restored.view, view);
+        let stored = serialize(&settings, &cells, None, None).unwrap();
+        let restored = deserialize::<WireState>(&stored).unwrap();
+        assert_eq!((restored.rule, restored.view), (None, None));
+        // States of another automaton are rejected
+        assert!(deserialize::<LifeStates>(&stored).is_none());
+    }
+
+    #[test]
+    fn keys_are_per_automaton() {
+        assert_ne!(storage_key("wireworld"), storage_key("life"));
+        assert!(storage_key("life").starts_with(STORAGE_KEY));
+    }
+
+    #[test]
+    fn older_versions_are_read() {
+        let settings = Settings::default();
+        let mut cells = Grid::generate(4, 4);
+        cells[(1, 2)] = LifeStates::Alife;
+        let v1 = format!("4 4\n{}\n{}", settings.to_storage(), to_life106(&cells));
+        let (_, restored) = deserialize_v1(&v1).unwrap();
+        assert_eq!(restored, cells);
+        // ..but not mistaken for version 2
+        assert!(deserialize_v2(&v1).is_none());
+        let v2 = format!(
+            "4 4\n1 2 3\n{}\n{}",
+            settings.to_storage(),
+            to_life106(&cells)
+        );
+        let restored = deserialize_v2(&v2).unwrap();
+        assert_eq!(restored.cells, cells);
+        assert_eq!(restored.view, Some((Translation2::new(1.0, 2.0), 3.0)));
+        assert!(deserialize::<LifeStates>(&v2).is_none());
+    }
+
+    #[test]
+    fn corrupt_data_is_ignored() {
+        let valid =
+            serialize::<LifeStates>(&Settings::default(), &Grid::generate(3, 3), None, None)
+                .unwrap();
+        assert!(deserialize::<LifeStates>(&valid).is_some());
+        assert!(deserialize::<LifeStates>("").is_none());
+        assert!(
+            deserialize::<LifeStates>(&valid.replacen("\"width\":3", "\"width\":4", 1)).is_none()
+        );
+        assert!(
+            deserialize::<LifeStates>(&valid.replacen("\"width\":3", "\"width\":0", 1)).is_none()
+        );
+        assert!(
+            deserialize::<LifeStates>(&valid.replacen("\"version\":1", "\"version\":9", 1))
+                .is_none()
+        );
+        assert!(deserialize::<LifeStates>("auto\nnonsense\n\n{}").is_none());
+        assert!(deserialize::<LifeStates>(&valid.replacen("auto", "1 2", 1)).is_none());
+        assert!(deserialize::<LifeStates>(&valid.replacen("auto", "1 2 0", 1)).is_none());
+        assert!(deserialize::<LifeStates>(&valid.replacen("auto", "1 NaN 2", 1)).is_none());
+        let v2 = format!(
+            "3 3\nauto\n{}\n#Life 1.06\n",
+            Settings::default().to_storage()
+        );
+        assert!(deserialize_v2(&v2).is_some());
+        assert!(deserialize_v2(&v2.replacen("3 3", "3 x", 1)).is_none());
+        assert!(deserialize_v2(&v2.replacen("3 3", "0 3", 1)).is_none());
+        assert!(deserialize_v2(&v2.replacen("3 3", "100000 100000", 1)).is_none());
+        assert!(deserialize_v2(&v2.replace("#Life 1.06", "#Life 1.05")).is_none());
+    }
+}
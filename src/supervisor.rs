@@ -1,36 +1,221 @@
-use std::mem;
+#[cfg(feature = "web")]
+use std::f64::consts::TAU;
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    mem,
+};
 
-use nalgebra::{Point2, Translation2};
+use nalgebra::{Point2, Translation2, Vector2};
+#[cfg(feature = "web")]
 use web_sys::CanvasRenderingContext2d;
 
 use crate::{
-    automaton::{Automaton, Grid},
-    CELL_WIDTH,
+    automaton::{
+        cell_count,
+        hex::{axial_to_pixel, hexagon_corners, pixel_to_axial},
+        Automaton, BitGrid, Dimension, EdgeBehavior, Grid, GridError, LifeStates, Topology,
+        MAX_CELLS,
+    },
+    history::History,
+    pattern::Orientation,
+    random::XorShift,
+    stats::{RingBuffer, POPULATION_HISTORY},
+    theme::Theme,
+    DEFAULT_CELL_WIDTH,
 };
 
 pub struct Supervisor<A: Automaton> {
     pub trans: Translation2<f64>,
     pub scale: Scale,
+    automaton: A,
     front_buf: Grid<A::State>,
     swap_buf: Grid<A::State>,
+    history: History<A::State>,
+    /// Positions of all alive cells for sparse updates, `None` if it has to be rebuilt.
+    alive: Option<HashSet<(isize, isize)>>,
+    /// Number of generations each cell has been alive without interruption.
+    ///
+    /// Ages restart whenever the grid is replaced, e.g. by undo or redo.
+    ages: Grid<u32>,
+    /// Seed of the last [`Supervisor::randomize`], if the grid was randomized.
+    seed: Option<u64>,
+    generation: u64,
+    /// Population of the most recent generations, see [`Supervisor::populations`].
+    populations: RingBuffer<usize>,
+    /// Hashes of the most recent consecutive generations, newest last.
+    hashes: RingBuffer<u64>,
+    /// Period of the cycle detected by the last update, see [`Supervisor::stability`].
+    stability: Option<u32>,
+    /// Grow the grid instead of wrapping around, see [`Supervisor::set_expanding`].
+    expanding: bool,
+    /// How the edges are glued if the grid is not expanding.
+    topology: Topology,
+    /// Largest number of cells the grid may grow to by resizing or expanding.
+    max_cells: usize,
+    /// Width of a cell in world coordinates, i.e. in screen pixels at scale one.
+    cell_width: usize,
+    /// The grid before the last update, independent of the history.
+    ///
+    /// Dropped whenever the grid is edited or replaced.
+    previous: Option<Previous<A::State>>,
+    /// Id of the next agent placed by [`Supervisor::toggle_agent`].
+    next_agent: u8,
+    /// Buffers for [`Supervisor::update_bits`], kept to avoid allocating
+    /// them for every generation.
+    bits: [BitGrid; 2],
 }
 
 impl<A: Automaton> Supervisor<A> {
-    pub fn new(width: usize, height: usize) -> Self {
+    /// Like [`Supervisor::new`], unless the grid has more than [`MAX_CELLS`] cells.
+    pub fn try_new(automaton: A, width: usize, height: usize) -> Result<Self, GridError> {
+        cell_count(width, height, MAX_CELLS)?;
+        Ok(Self::new(automaton, width, height))
+    }
+
+    pub fn new(automaton: A, width: usize, height: usize) -> Self {
         let grid = Grid::generate(width, height);
         Self {
+            automaton,
             front_buf: grid.clone(),
             swap_buf: grid,
+            history: History::new(),
+            alive: None,
+            ages: Grid::generate(width, height),
+            seed: None,
+            generation: 0,
+            populations: RingBuffer::new(POPULATION_HISTORY),
+            hashes: RingBuffer::new(STABILITY_WINDOW),
+            stability: None,
+            expanding: false,
+            topology: Topology::default(),
+            max_cells: MAX_CELLS,
+            cell_width: DEFAULT_CELL_WIDTH,
+            previous: None,
+            next_agent: 0,
+            bits: Default::default(),
             trans: Translation2::from([0.0, 0.0]),
             scale: Scale::Auto(1.0),
         }
     }
 
+    /// The smallest and largest coordinates of alive cells, as
+    /// `(min_x, min_y, max_x, max_y)`, or `None` if no cell is alive.
+    pub fn bounding_box(&self) -> Option<Region> {
+        (0..self.width() as isize)
+            .flat_map(|x| (0..self.height() as isize).map(move |y| (x, y)))
+            .filter(|&pos| self.automaton.is_alive(&self.front_buf[pos]))
+            .fold(None, |bounds, (x, y)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((min_x, min_y, max_x, max_y)) => {
+                    Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+                }
+            })
+    }
+
+    /// The part of `region` inside the grid with ordered corners, `None` if there is none.
+    pub fn clip_region(&self, (x0, y0, x1, y1): Region) -> Option<Region> {
+        let (max_x, max_y) = (self.width() as isize - 1, self.height() as isize - 1);
+        let (x0, x1) = (x0.min(x1).max(0), x0.max(x1).min(max_x));
+        let (y0, y1) = (y0.min(y1).max(0), y0.max(y1).min(max_y));
+        (x0 <= x1 && y0 <= y1).then_some((x0, y0, x1, y1))
+    }
+
+    /// Copy of the cells in `region`, which has to be clipped, see [`Supervisor::clip_region`].
+    pub fn copy_region(&self, (x0, y0, x1, y1): Region) -> Grid<A::State> {
+        let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+        self.front_buf.sub_grid((x0, y0), width, height)
+    }
+
+    /// Rotate the cells in `region` by 90° clockwise, as a single undoable edit.
+    ///
+    /// Returns the region covered by the rotated cells, see [`Supervisor::transform_region`].
+    pub fn rotate_cw(&mut self, region: Region) -> Option<Region> {
+        self.transform_region(region, Grid::rotated_cw)
+    }
+
+    /// Rotate the cells in `region` by 90° counterclockwise, like [`Supervisor::rotate_cw`].
+    pub fn rotate_ccw(&mut self, region: Region) -> Option<Region> {
+        self.transform_region(region, Grid::rotated_ccw)
+    }
+
+    /// Mirror the cells in `region` left to right, as a single undoable edit.
+    pub fn flip_h(&mut self, region: Region) -> Option<Region> {
+        self.transform_region(region, Grid::flipped_h)
+    }
+
+    /// Mirror the cells in `region` top to bottom, as a single undoable edit.
+    pub fn flip_v(&mut self, region: Region) -> Option<Region> {
+        self.transform_region(region, Grid::flipped_v)
+    }
+
+    /// Replace the cells in `region` by `transform`ed ones, centered on the same cell.
+    ///
+    /// Rotating a region that is not square swaps its width and height
+    /// around its center, so the cells it no longer covers are cleared and
+    /// cells moved outside of the grid are dropped. Returns the region
+    /// covered by the transformed cells, which may extend beyond the grid so
+    /// that rotating it back restores the original region.
+    fn transform_region(
+        &mut self,
+        region: Region,
+        transform: impl FnOnce(&Grid<A::State>) -> Grid<A::State>,
+    ) -> Option<Region> {
+        let (x0, y0, x1, y1) = self.clip_region(region)?;
+        let cells = transform(&self.copy_region((x0, y0, x1, y1)));
+        let (width, height) = (cells.width() as isize, cells.height() as isize);
+        // Truncating division keeps the corners of rotations and their reversal in sync
+        let new_x0 = x0 + (x1 - x0 + 1 - width) / 2;
+        let new_y0 = y0 + (y1 - y0 + 1 - height) / 2;
+        self.checkpoint();
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.front_buf[(x, y)] = A::State::default();
+                self.cell_edited(x, y);
+            }
+        }
+        for x in 0..width {
+            for y in 0..height {
+                let (target_x, target_y) = (new_x0 + x, new_y0 + y);
+                let inside = (0..self.width() as isize).contains(&target_x)
+                    && (0..self.height() as isize).contains(&target_y);
+                if inside {
+                    self.front_buf[(target_x, target_y)] = cells[(x, y)].clone();
+                    self.cell_edited(target_x, target_y);
+                }
+            }
+        }
+        Some((new_x0, new_y0, new_x0 + width - 1, new_y0 + height - 1))
+    }
+
+    /// Zoom and pan to show the alive cells with a margin of
+    /// [`FIT_MARGIN_CELLS`], or the whole grid if no cell is alive.
+    pub fn fit_to_pattern(&mut self, target_width: u32, target_height: u32) {
+        let (min_x, min_y, max_x, max_y) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return self.reset_zoom(target_width, target_height),
+        };
+        // Cell centers of opposite corners plus the margin frame all cells,
+        // also for hexagons whose rows shift to the right
+        let margin = (FIT_MARGIN_CELLS + 0.5) * self.cell_width as f64;
+        let margin = Vector2::from([margin, margin]);
+        let top_left = self.cell_center((min_x, min_y)) - margin;
+        let bottom_right = self.cell_center((max_x, max_y)) + margin;
+        let size = bottom_right - top_left;
+        let scale = (target_width as f64 / size.x)
+            .min(target_height as f64 / size.y)
+            .clamp(MIN_SCALE, MAX_SCALE);
+        let target_center = Vector2::from([target_width as f64, target_height as f64]) / 2.0;
+        let center = nalgebra::center(&top_left, &bottom_right);
+        self.scale = Scale::Manual(scale);
+        self.trans = Translation2::from(target_center / scale - center.coords);
+    }
+
     pub fn reset_zoom(&mut self, target_width: u32, target_height: u32) {
         let target_width = target_width as f64;
         let target_height = target_height as f64;
-        let curr_width = self.width() as f64 * CELL_WIDTH as f64;
-        let curr_height = self.height() as f64 * CELL_WIDTH as f64;
+        let (curr_width, curr_height) = self.world_size();
         let width_scale = target_width / curr_width;
         let height_scale = target_height / curr_height;
         let min_scale = width_scale.min(height_scale);
@@ -40,47 +225,815 @@ impl<A: Automaton> Supervisor<A> {
         self.trans = Translation2::from([offset_x, offset_y]);
     }
 
+    /// Move the view so that at least [`MIN_VISIBLE_PX`] of the grid stay on
+    /// a canvas of the given size in both directions, or all of it if the grid is smaller.
+    ///
+    /// Returns whether the view moved.
+    pub fn clamp_pan(&mut self, target_width: u32, target_height: u32) -> bool {
+        let (left, top, width, height) = self.grid_screen_rect();
+        let shift = |low: f64, size: f64, target: f64| {
+            let visible = MIN_VISIBLE_PX.min(size).min(target);
+            if low + size < visible {
+                visible - low - size
+            } else if low > target - visible {
+                target - visible - low
+            } else {
+                0.0
+            }
+        };
+        let shift = Vector2::from([
+            shift(left, width, target_width as f64),
+            shift(top, height, target_height as f64),
+        ]);
+        if shift == Vector2::zeros() {
+            return false;
+        }
+        self.trans.vector += shift / self.scale.raw();
+        true
+    }
+
+    /// The area covered by the grid on screen as `(left, top, width, height)`.
+    pub fn grid_screen_rect(&self) -> (f64, f64, f64, f64) {
+        self.view().grid_rect(self.world_size())
+    }
+
     pub fn update(&mut self) {
+        self.checkpoint();
+        let radius = self.automaton.sparse_radius();
+        let generation = self.generation;
+        self.previous = Some(if let Some(radius) = radius.filter(|_| self.is_sparse()) {
+            Previous::Changed(generation, self.update_sparse(radius))
+        } else if self.update_bits() {
+            Previous::Swapped(generation)
+        } else if let Some(next) = self.automaton.step_grid(&self.front_buf, generation) {
+            self.swap_buf = mem::replace(&mut self.front_buf, next);
+            self.grid_stepped();
+            Previous::Swapped(self.generation)
+        } else if let Some(radius) = radius {
+            Previous::Changed(self.generation, self.update_sparse(radius))
+        } else {
+            self.update_full();
+            Previous::Swapped(self.generation)
+        });
+        self.generation += 1;
+        self.maybe_expand();
+        self.populations.push(self.population());
+        self.detect_stability();
+    }
+
+    /// Let the grid grow when alive cells reach its border, instead of wrapping around.
+    ///
+    /// Cells outside of an expanding grid are dead.
+    pub fn set_expanding(&mut self, expanding: bool) {
+        self.expanding = expanding;
+        self.apply_edge_behavior();
+        self.alive = None;
+    }
+
+    pub fn expanding(&self) -> bool {
+        self.expanding
+    }
+
+    /// Glue the edges of the grid according to `topology`, unless it is expanding.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.apply_edge_behavior();
+        self.alive = None;
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    fn apply_edge_behavior(&mut self) {
+        let edge_behavior = if self.expanding {
+            EdgeBehavior::Dead
+        } else {
+            EdgeBehavior::Wrap(self.topology)
+        };
+        self.front_buf.set_edge_behavior(edge_behavior);
+        self.swap_buf.set_edge_behavior(edge_behavior);
+    }
+
+    /// Pad the grid by [`EXPAND_MARGIN`] cells if it is expanding and alive
+    /// cells touch the border. Returns whether the grid grew.
+    ///
+    /// The view moves along, so that cells stay at the same place on screen.
+    /// Like [`Supervisor::resize`], this clears the history.
+    pub fn maybe_expand(&mut self) -> bool {
+        if !self.expanding || !self.border_alive() {
+            return false;
+        }
+        let (width, height) = (
+            self.width() + 2 * EXPAND_MARGIN,
+            self.height() + 2 * EXPAND_MARGIN,
+        );
+        if cell_count(width, height, self.max_cells).is_err() {
+            // Cells at the border die instead, like on a grid that doesn't expand
+            return false;
+        }
+        let shift = self.cell_center((EXPAND_MARGIN as isize, EXPAND_MARGIN as isize))
+            - self.cell_center((0, 0));
+        self.history.clear();
+        self.front_buf.pad(EXPAND_MARGIN);
+        // Keeps the previous generation, if there is one
+        self.swap_buf.pad(EXPAND_MARGIN);
+        if let Some(Previous::Changed(_, changed)) = &mut self.previous {
+            let margin = EXPAND_MARGIN as isize;
+            for ((x, y), _) in changed {
+                *x += margin;
+                *y += margin;
+            }
+        }
+        self.ages.pad(EXPAND_MARGIN);
+        self.alive = None;
+        self.reset_stability();
+        self.trans.vector -= shift;
+        true
+    }
+
+    /// Whether any cell in the outermost rows or columns is alive.
+    fn border_alive(&self) -> bool {
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        let rows = (0..width).flat_map(|x| [(x, 0), (x, height - 1)]);
+        let columns = (0..height).flat_map(|y| [(0, y), (width - 1, y)]);
+        rows.chain(columns)
+            .any(|pos| self.automaton.is_alive(&self.front_buf[pos]))
+    }
+
+    /// Compare the current grid with the recent generations to find a cycle.
+    fn detect_stability(&mut self) {
+        let mut hasher = DefaultHasher::new();
+        self.front_buf.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.stability = self
+            .hashes
+            .iter()
+            .rev()
+            .position(|&prev| prev == hash)
+            .map(|idx| idx as u32 + 1);
+        self.hashes.push(hash);
+    }
+
+    /// Period of the cycle the pattern has settled into, `1` for a still life.
+    ///
+    /// Only cycles of at most [`STABILITY_WINDOW`] generations are detected,
+    /// and only after the first full cycle has been computed since the last
+    /// edit. Hash collisions may very rarely report a cycle that isn't one.
+    pub fn stability(&self) -> Option<u32> {
+        self.stability
+    }
+
+    /// Forget the recent generations, since the grid no longer follows them.
+    fn reset_stability(&mut self) {
+        self.hashes.clear();
+        self.stability = None;
+    }
+
+    /// Update the data derived from the cells after replacing all of them
+    /// with the next generation.
+    fn grid_stepped(&mut self) {
+        self.alive = None;
+        for x in 0..self.width() as isize {
+            for y in 0..self.height() as isize {
+                let new = self.front_buf[(x, y)].clone();
+                self.update_age((x, y), &new);
+            }
+        }
+    }
+
+    /// Compute the next generation for every cell.
+    fn update_full(&mut self) {
+        self.alive = None;
         mem::swap(&mut self.front_buf, &mut self.swap_buf);
         for x in 0..self.front_buf.width() {
             let x = x as isize;
             for y in 0..self.front_buf.height() {
                 let y = y as isize;
-                let new = A::update((x, y), &self.swap_buf);
+                let new = self.automaton.update((x, y), &self.swap_buf);
+                self.update_age((x, y), &new);
                 self.front_buf[(x, y)] = new;
             }
         }
     }
 
-    pub fn draw(&self, ctx: &CanvasRenderingContext2d) {
-        for x in 0..self.front_buf.width() {
-            for y in 0..self.front_buf.height() {
-                let state = &self.front_buf[(x as isize, y as isize)];
-                ctx.set_fill_style(&A::style(state));
-                let pos = self.to_screen_coordinates(Point2::from([
-                    (x * CELL_WIDTH) as f64 + 1.0,
-                    (y * CELL_WIDTH) as f64 + 1.0,
-                ]));
-                let size = (CELL_WIDTH as f64 - 2.0) * self.scale.raw();
-                ctx.fill_rect(pos.x, pos.y, size, size);
+    /// Compute the next generation bit-packed, if the automaton has a
+    /// [`Automaton::two_state_rule`]. Returns whether it did.
+    fn update_bits(&mut self) -> bool {
+        let rule = match self.automaton.two_state_rule() {
+            // Mirrored edges are left to the per-cell update
+            Some(rule) if self.front_buf.edge_behavior().is_separable() => rule,
+            _ => return false,
+        };
+        let [bits, next] = &mut self.bits;
+        let automaton = &self.automaton;
+        bits.pack(&self.front_buf, |state| automaton.is_alive(state));
+        bits.step_life_like(rule.born, rule.survive, next);
+        let alive = automaton.toggle(A::State::default());
+        mem::swap(&mut self.front_buf, &mut self.swap_buf);
+        next.unpack_into(&mut self.front_buf, &alive);
+        self.grid_stepped();
+        true
+    }
+
+    /// Whether few enough cells are alive to prefer [`Supervisor::update_sparse`].
+    ///
+    /// Uses the tracked alive cells or the last population, so that no cells
+    /// have to be counted.
+    fn is_sparse(&self) -> bool {
+        let alive = match &self.alive {
+            Some(alive) => alive.len(),
+            None => match self.populations.iter().next_back() {
+                Some(&population) => population,
+                None => return false,
+            },
+        };
+        alive.saturating_mul(SPARSE_CELLS_PER_ALIVE) < self.width() * self.height()
+    }
+
+    /// Compute the next generation only for cells close to alive cells.
+    /// Returns the changed cells with their previous state.
+    ///
+    /// See [`Automaton::sparse_radius`] for when this is equivalent to [`Supervisor::update_full`].
+    fn update_sparse(&mut self, radius: usize) -> Vec<((isize, isize), A::State)> {
+        let alive = self.alive.take().unwrap_or_else(|| self.collect_alive());
+        let radius = radius as isize;
+        let candidates: HashSet<_> = alive
+            .iter()
+            .flat_map(|&(x, y)| {
+                (-radius..=radius)
+                    .flat_map(move |dx| (-radius..=radius).map(move |dy| (x + dx, y + dy)))
+            })
+            .filter_map(|(x, y)| self.front_buf.normalize(x, y))
+            .collect();
+        let updates: Vec<_> = candidates
+            .into_iter()
+            .map(|pos| (pos, self.automaton.update(pos, &self.front_buf)))
+            .collect();
+        // Every alive cell is a candidate, so all others stay in the default state
+        let mut next_alive = HashSet::with_capacity(alive.len());
+        let mut changed = Vec::new();
+        for (pos, new) in updates {
+            if self.automaton.is_alive(&new) {
+                next_alive.insert(pos);
+            }
+            self.update_age(pos, &new);
+            if self.front_buf[pos] != new {
+                changed.push((pos, mem::replace(&mut self.front_buf[pos], new)));
+            }
+        }
+        self.alive = Some(next_alive);
+        changed
+    }
+
+    fn collect_alive(&self) -> HashSet<(isize, isize)> {
+        (0..self.width() as isize)
+            .flat_map(|x| (0..self.height() as isize).map(move |y| (x, y)))
+            .filter(|&pos| self.automaton.is_alive(&self.front_buf[pos]))
+            .collect()
+    }
+
+    fn update_age(&mut self, pos: (isize, isize), new: &A::State) {
+        self.ages[pos] = if self.automaton.is_alive(new) {
+            self.ages[pos].saturating_add(1)
+        } else {
+            0
+        };
+    }
+
+    /// Forget all data derived from the cells, after replacing the whole grid.
+    fn grid_replaced(&mut self) {
+        self.previous = None;
+        if (self.swap_buf.width(), self.swap_buf.height()) != (self.width(), self.height()) {
+            // The history may contain grids from before the last resize
+            self.swap_buf = self.front_buf.clone();
+        }
+        self.apply_edge_behavior();
+        self.ages = Grid::generate(self.width(), self.height());
+        self.alive = None;
+        self.reset_stability();
+    }
+
+    /// Update the data derived from the cell at `(x, y)` after editing it.
+    fn cell_edited(&mut self, x: isize, y: isize) {
+        self.previous = None;
+        self.reset_stability();
+        if let Some(pos) = self.front_buf.normalize(x, y) {
+            self.ages[pos] = 0;
+            if let Some(alive) = &mut self.alive {
+                if self.automaton.is_alive(&self.front_buf[pos]) {
+                    alive.insert(pos);
+                } else {
+                    alive.remove(&pos);
+                }
+            }
+        }
+    }
+
+    /// Number of generations the cell has been alive, zero if it is dead.
+    pub fn age(&self, x: isize, y: isize) -> Option<u32> {
+        self.ages.get(x, y).copied()
+    }
+
+    /// Center of the cell at `pos`, before scaling.
+    fn cell_center(&self, (x, y): (isize, isize)) -> Point2<f64> {
+        if A::Dimension::HEXAGONAL {
+            let (px, py) = axial_to_pixel((x, y), self.cell_width as f64);
+            Point2::from([px, py])
+        } else {
+            let cell = self.cell_width as f64;
+            Point2::from([(x as f64 + 0.5) * cell, (y as f64 + 0.5) * cell])
+        }
+    }
+
+    fn view(&self) -> View {
+        View {
+            trans: self.trans,
+            scale: self.scale.raw(),
+        }
+    }
+
+    /// Record the current grid in the history, so that the next change can be undone.
+    pub fn checkpoint(&mut self) {
+        self.history.push(self.front_buf.clone(), self.generation);
+        self.history.clear_future();
+    }
+
+    /// Revert the last step or edit. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let changed = self.history.back(&mut self.front_buf, &mut self.generation);
+        self.grid_replaced();
+        changed
+    }
+
+    /// Reapply the last undone step or edit. Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let changed = self
+            .history
+            .forward(&mut self.front_buf, &mut self.generation);
+        self.grid_replaced();
+        changed
+    }
+
+    /// Advance one generation, replaying the history if possible.
+    pub fn step_forward(&mut self) {
+        if !self.redo() {
+            self.update();
+        }
+    }
+
+    /// Go back one generation. Returns `false` if there is no history.
+    ///
+    /// Without history, e.g. after the grid expanded, the grid before the
+    /// last update is restored if there was no edit since. Reversible
+    /// automata can go back further than that.
+    pub fn step_back(&mut self) -> bool {
+        if !self.history.can_go_back() {
+            let previous = self.previous.take().map(|previous| match previous {
+                Previous::Swapped(generation) => (self.swap_buf.clone(), generation),
+                Previous::Changed(generation, changed) => {
+                    let mut grid = self.front_buf.clone();
+                    for (pos, old) in changed {
+                        grid[pos] = old;
+                    }
+                    (grid, generation)
+                }
+            });
+            let previous = previous.or_else(|| {
+                let generation = self.generation.checked_sub(1)?;
+                let grid = self
+                    .automaton
+                    .step_grid_back(&self.front_buf, self.generation)?;
+                Some((grid, generation))
+            });
+            if let Some((grid, generation)) = previous {
+                self.history.push(grid, generation);
+            }
+        }
+        self.undo()
+    }
+
+    /// Go back to the oldest generation in the history.
+    pub fn rewind(&mut self) {
+        while self.step_back() {}
+    }
+
+    /// Replay the history up to the latest generation.
+    pub fn fast_forward(&mut self) {
+        while self.history.can_go_forward() {
+            self.step_forward();
+        }
+    }
+
+    /// Number of generations computed since the start.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Number of alive cells.
+    pub fn population(&self) -> usize {
+        (0..self.width() as isize)
+            .flat_map(|x| (0..self.height() as isize).map(move |y| (x, y)))
+            .filter(|&pos| self.automaton.is_alive(&self.front_buf[pos]))
+            .count()
+    }
+
+    /// Population of the most recent generations, restarting whenever the
+    /// grid is cleared, randomized, loaded or resized.
+    pub fn populations(&self) -> &RingBuffer<usize> {
+        &self.populations
+    }
+
+    fn reset_populations(&mut self) {
+        self.populations.clear();
+        self.populations.push(self.population());
+    }
+
+    pub fn history(&self) -> &History<A::State> {
+        &self.history
+    }
+
+    /// Reset every cell to the default state, keeping the view as is.
+    pub fn clear(&mut self) {
+        self.checkpoint();
+        self.front_buf = Grid::generate(self.width(), self.height());
+        self.swap_buf = self.front_buf.clone();
+        self.grid_replaced();
+        self.reset_populations();
+    }
+
+    /// Set every cell to a random non-default state with probability `density`.
+    ///
+    /// The same `seed`, density and grid size always result in the same grid.
+    pub fn randomize(&mut self, density: f64, seed: u64) {
+        self.checkpoint();
+        self.seed = Some(seed);
+        let mut rng = XorShift::new(seed);
+        // Space-time diagrams start from a random row without any history
+        let first_row = match A::Dimension::SPACETIME {
+            true => self.height() as isize - 1,
+            false => 0,
+        };
+        for x in 0..self.width() as isize {
+            for y in 0..self.height() as isize {
+                self.front_buf[(x, y)] = if y >= first_row && rng.next_f64() < density {
+                    self.automaton.random_state(&mut || rng.next_u32())
+                } else {
+                    A::State::default()
+                };
+            }
+        }
+        self.grid_replaced();
+        self.reset_populations();
+    }
+
+    /// Seed of the last [`Supervisor::randomize`], if any.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Remember `seed` as the seed of the grid, e.g. of a shared one.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Change the grid size, keeping the cells in the overlapping region.
+    ///
+    /// This clears the history, since generations of different size can't be mixed.
+    /// Sizes with more than [`Supervisor::max_cells`] cells are rejected, leaving
+    /// the grid unchanged.
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), GridError> {
+        cell_count(width, height, self.max_cells)?;
+        self.history.clear();
+        self.front_buf.resize(width, height);
+        self.swap_buf = self.front_buf.clone();
+        self.ages.resize(width, height);
+        self.alive = None;
+        self.previous = None;
+        self.reset_populations();
+        self.reset_stability();
+        Ok(())
+    }
+
+    /// Largest number of cells of the grid, [`MAX_CELLS`] by default.
+    pub fn max_cells(&self) -> usize {
+        self.max_cells
+    }
+
+    /// Limit the number of cells for future resizes and expansions.
+    pub fn set_max_cells(&mut self, max_cells: usize) {
+        self.max_cells = max_cells;
+    }
+
+    /// The grid reduced to alive and dead cells.
+    pub fn alive_grid(&self) -> Grid<LifeStates> {
+        let mut grid = Grid::generate(self.width(), self.height());
+        for x in 0..self.width() as isize {
+            for y in 0..self.height() as isize {
+                if self.automaton.is_alive(&self.front_buf[(x, y)]) {
+                    grid[(x, y)] = LifeStates::Alife;
+                }
+            }
+        }
+        grid
+    }
+
+    /// Like [`Supervisor::alive_grid`], but only the cells in `region`, which
+    /// has to be clipped, see [`Supervisor::clip_region`].
+    pub fn alive_region(&self, (x0, y0, x1, y1): Region) -> Grid<LifeStates> {
+        let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+        self.alive_grid().sub_grid((x0, y0), width, height)
+    }
+
+    /// Replace the grid with `cells`, using the toggled default state for alive cells.
+    ///
+    /// Like [`Supervisor::resize`], this clears the history and restarts at generation zero.
+    pub fn load_alive(&mut self, cells: &Grid<LifeStates>) {
+        let alive = self.automaton.toggle(A::State::default());
+        let mut grid = Grid::generate(cells.width(), cells.height());
+        for x in 0..cells.width() as isize {
+            for y in 0..cells.height() as isize {
+                if cells[(x, y)] == LifeStates::Alife {
+                    grid[(x, y)] = alive.clone();
+                }
             }
         }
+        self.load_grid(grid);
+    }
+
+    /// Replace the grid with `grid`, clearing the history.
+    ///
+    /// The edges keep behaving according to the current settings.
+    pub fn load_grid(&mut self, mut grid: Grid<A::State>) {
+        grid.set_edge_behavior(self.front_buf.edge_behavior());
+        self.history.clear();
+        self.generation = 0;
+        self.swap_buf = grid.clone();
+        self.front_buf = grid;
+        self.grid_replaced();
+        self.reset_populations();
+    }
+
+    /// The cells of the current generation.
+    pub fn grid(&self) -> &Grid<A::State> {
+        &self.front_buf
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&A::State> {
+        self.front_buf.get(x, y)
+    }
+
+    /// Set a single cell.
+    ///
+    /// Unlike [`Supervisor::toggle`], this does not record a checkpoint,
+    /// so that painting multiple cells can be undone as a single edit.
+    pub fn set(&mut self, x: isize, y: isize, state: A::State) {
+        self.history.clear_future();
+        if let Some(cell) = self.front_buf.get_mut(x, y) {
+            *cell = state;
+        }
+        self.cell_edited(x, y);
     }
 
     pub fn toggle(&mut self, x: isize, y: isize) {
-        let old = self.front_buf[(x, y)].clone();
-        self.front_buf[(x, y)] = A::toggle(old);
+        self.checkpoint();
+        if let Some(cell) = self.front_buf.get_mut(x, y) {
+            *cell = self.automaton.toggle(cell.clone());
+        }
+        self.cell_edited(x, y);
     }
 
-    pub fn to_screen_coordinates(&self, obj: Point2<f64>) -> Point2<f64> {
-        self.scale.raw() * self.trans.transform_point(&obj)
+    /// Add an agent to the cell or remove the one on it, see [`Automaton::toggle_agent`].
+    ///
+    /// Returns `false` if the automaton has no agents.
+    pub fn toggle_agent(&mut self, x: isize, y: isize) -> bool {
+        let toggled = self
+            .front_buf
+            .get(x, y)
+            .and_then(|cell| self.automaton.toggle_agent(cell, self.next_agent));
+        match toggled {
+            Some(state) => {
+                self.checkpoint();
+                self.front_buf[(x, y)] = state;
+                self.next_agent = self.next_agent.wrapping_add(1);
+                self.cell_edited(x, y);
+                true
+            }
+            None => false,
+        }
     }
 
+    /// Drop a grain of sand onto `(x, y)`.
+    ///
+    /// Drops are not recorded in the history, so that holding the mouse
+    /// button does not flood it. Call [`Supervisor::checkpoint`] before the
+    /// first drop to make them undoable. Returns `false` if the automaton has
+    /// no sand.
+    pub fn drop_sand(&mut self, x: isize, y: isize) -> bool {
+        let dropped = self
+            .front_buf
+            .get(x, y)
+            .and_then(|cell| self.automaton.drop_sand(cell));
+        match dropped {
+            Some(state) => {
+                self.front_buf[(x, y)] = state;
+                self.cell_edited(x, y);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the cells at `offsets` from `origin` alive, as a single undoable edit.
+    pub fn stamp(&mut self, (x, y): (isize, isize), offsets: &[(isize, isize)]) {
+        self.checkpoint();
+        let alive = self.automaton.toggle(A::State::default());
+        for (dx, dy) in offsets {
+            if let Some(cell) = self.front_buf.get_mut(x + dx, y + dy) {
+                *cell = alive.clone();
+            }
+            self.cell_edited(x + dx, y + dy);
+        }
+    }
+
+    /// Like [`Supervisor::stamp`], with the pattern turned into `orientation` first.
+    pub fn stamp_oriented(
+        &mut self,
+        origin: (isize, isize),
+        offsets: &[(isize, isize)],
+        orientation: Orientation,
+    ) {
+        self.stamp(origin, &orientation.apply(offsets));
+    }
+
+    /// Zoom by `delta`, keeping the point under `cursor` in place.
+    ///
+    /// Returns `false` if the zoom did not change, because it is already at its limit.
+    pub fn zoom_at(&mut self, cursor: Point2<f64>, delta: f64) -> bool {
+        let old = self.scale.raw();
+        let new = zoomed_scale(old, delta);
+        if new == old {
+            return false;
+        }
+        // `screen = scale * (world + trans)`, so moving the translation by the
+        // difference of the cursor position at both scales keeps `world` fixed.
+        self.trans.vector += cursor.coords * (1.0 / new - 1.0 / old);
+        self.scale = Scale::Manual(new);
+        true
+    }
+
+    pub fn cell_width(&self) -> usize {
+        self.cell_width
+    }
+
+    /// Change the width of a cell, clamped to [`MIN_CELL_WIDTH`]..=[`MAX_CELL_WIDTH`].
+    ///
+    /// The view is not adjusted, call [`Supervisor::reset_zoom`] afterwards.
+    pub fn set_cell_width(&mut self, cell_width: usize) {
+        self.cell_width = cell_width.clamp(MIN_CELL_WIDTH, MAX_CELL_WIDTH);
+    }
+
+    /// The cell under the given screen position.
+    pub fn cell_at(&self, screen: Point2<f64>) -> (isize, isize) {
+        let pos = self.from_screen_coordinates(screen);
+        if A::Dimension::HEXAGONAL {
+            return pixel_to_axial((pos.x, pos.y), self.cell_width as f64);
+        }
+        let pos = pos / self.cell_width as f64;
+        (pos.x.floor() as isize, pos.y.floor() as isize)
+    }
+
+    /// The grid coordinates of the cell under the given screen position, as
+    /// affected by a click there.
+    ///
+    /// Positions outside of a wrapping grid map to the cell they wrap to,
+    /// outside of an expanding grid there is no cell.
+    pub fn cell_under(&self, screen: Point2<f64>) -> Option<(isize, isize)> {
+        let (x, y) = self.cell_at(screen);
+        self.front_buf.normalize(x, y)
+    }
+
+    /// Width and height of the area covered by the grid, before scaling.
+    pub fn world_size(&self) -> (f64, f64) {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        let cell = self.cell_width as f64;
+        if A::Dimension::HEXAGONAL {
+            // Rows shift by half a hexagon and overlap by a quarter of their height
+            let row_height = cell * 3f64.sqrt() / 2.0;
+            (
+                cell * (width + (height - 1.0) / 2.0),
+                row_height * (height - 1.0) + cell * 2.0 / 3f64.sqrt(),
+            )
+        } else {
+            (width * cell, height * cell)
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     pub fn from_screen_coordinates(&self, obj: Point2<f64>) -> Point2<f64> {
         self.trans
             .inverse_transform_point(&(obj / self.scale.raw()))
     }
 
+    /// Inverse of [`Supervisor::from_screen_coordinates`].
+    pub fn to_screen_coordinates(&self, obj: Point2<f64>) -> Point2<f64> {
+        self.view().to_screen(obj)
+    }
+
+    /// All cells grouped by their fill color, in order of first appearance.
+    ///
+    /// Cells don't overlap, so drawing them batch by batch looks the same as
+    /// drawing them one by one, but only sets the fill style once per color.
+    pub fn fill_batches(&self, options: &RenderOptions) -> Vec<FillBatch> {
+        let mut batches: Vec<FillBatch> = vec![];
+        let mut index = HashMap::new();
+        for x in 0..self.width() as isize {
+            for y in 0..self.height() as isize {
+                let state = &self.front_buf[(x, y)];
+                let color = if options.heatmap && self.automaton.is_alive(state) {
+                    heat_color(self.age(x, y).unwrap_or_default()).into()
+                } else {
+                    self.automaton.style(state, &options.theme)
+                };
+                let idx = *index.entry(color.clone()).or_insert_with(|| {
+                    batches.push((color, vec![]));
+                    batches.len() - 1
+                });
+                batches[idx].1.push((x, y));
+            }
+        }
+        batches
+    }
+
+    /// An SVG document showing the whole grid, `size` pixels wide and high.
+    ///
+    /// Like [`Supervisor::draw_unscaled`], but without grid lines and
+    /// neighbor counts. Cells in the default state are left to the
+    /// background, all others are grouped by their fill color.
+    pub fn to_svg(&self, options: &RenderOptions, (width, height): (u32, u32)) -> String {
+        let (world_width, world_height) = self.world_size();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"0 0 {} {}\">\n",
+            width, height, world_width, world_height
+        );
+        svg += &format!(
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            world_width, world_height, options.theme.background
+        );
+        let dead = self.automaton.style(&A::State::default(), &options.theme);
+        for (color, cells) in self.fill_batches(options) {
+            if color == dead {
+                continue;
+            }
+            svg += &format!("<g fill=\"{}\">\n", color);
+            for pos in cells {
+                svg += &self.svg_cell(options.cell_shape, pos);
+                svg.push('\n');
+            }
+            svg += "</g>\n";
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    /// SVG element of the cell at `pos`, with the same outline as on the canvas.
+    fn svg_cell(&self, shape: CellShape, (x, y): (isize, isize)) -> String {
+        let cell = self.cell_width as f64;
+        match (shape, A::Dimension::HEXAGONAL) {
+            (CellShape::Circle, _) => {
+                let center = self.cell_center((x, y));
+                let radius = cell / 2.0 - 1.0;
+                format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>",
+                    center.x, center.y, radius
+                )
+            }
+            (CellShape::Square, true) => {
+                let points: Vec<_> = hexagon_corners((x, y), cell)
+                    .iter()
+                    .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+                    .collect();
+                format!("<polygon points=\"{}\"/>", points.join(" "))
+            }
+            (CellShape::Square, false) => format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                x as f64 * cell + 1.0,
+                y as f64 * cell + 1.0,
+                cell - 2.0,
+                cell - 2.0
+            ),
+        }
+    }
+
+    pub fn automaton(&self) -> &A {
+        &self.automaton
+    }
+
+    pub fn automaton_mut(&mut self) -> &mut A {
+        &mut self.automaton
+    }
+
     pub fn width(&self) -> usize {
         self.front_buf.width()
     }
@@ -90,6 +1043,313 @@ impl<A: Automaton> Supervisor<A> {
     }
 }
 
+/// Drawing onto a canvas, only available with the `web` feature.
+#[cfg(feature = "web")]
+impl<A: Automaton> Supervisor<A> {
+    pub fn draw(&self, ctx: &CanvasRenderingContext2d, options: &RenderOptions) {
+        self.draw_view(ctx, options, self.view());
+    }
+
+    /// Draw the whole grid with its top-left corner at the origin and
+    /// `cell_px` pixels per cell, ignoring the current pan and zoom.
+    pub fn draw_unscaled(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        options: &RenderOptions,
+        cell_px: f64,
+    ) {
+        let view = View {
+            trans: Translation2::identity(),
+            scale: cell_px / self.cell_width as f64,
+        };
+        self.draw_view(ctx, options, view);
+    }
+
+    fn draw_view(&self, ctx: &CanvasRenderingContext2d, options: &RenderOptions, view: View) {
+        let (left, top, width, height) = view.grid_rect(self.world_size());
+        ctx.set_fill_style_str(options.theme.background);
+        ctx.fill_rect(left, top, width, height);
+        for (color, cells) in self.fill_batches(options) {
+            ctx.set_fill_style_str(&color);
+            ctx.begin_path();
+            for pos in cells {
+                self.trace_cell(ctx, options.cell_shape, view, pos);
+            }
+            ctx.fill();
+        }
+        ctx.set_line_width(1.0);
+        ctx.set_stroke_style_str(options.theme.grid);
+        if options.show_grid && A::Dimension::HEXAGONAL {
+            ctx.begin_path();
+            for x in 0..self.width() as isize {
+                for y in 0..self.height() as isize {
+                    self.trace_hexagon(ctx, view, (x, y));
+                }
+            }
+            ctx.stroke();
+        }
+        if options.show_grid && !A::Dimension::HEXAGONAL {
+            self.draw_grid_lines(ctx, view);
+        }
+        if options.neighbor_counts {
+            self.draw_neighbor_counts(ctx, options, view);
+        }
+    }
+
+    /// Print the neighbor count centered on every visible cell, if cells are large enough.
+    fn draw_neighbor_counts(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        options: &RenderOptions,
+        view: View,
+    ) {
+        let cell_px = self.cell_width as f64 * view.scale;
+        if cell_px < MIN_LABEL_CELL_PX {
+            return;
+        }
+        let (width, height) = ctx
+            .canvas()
+            .map(|canvas| (canvas.width() as f64, canvas.height() as f64))
+            .unwrap_or_default();
+        ctx.set_fill_style_str(options.theme.palette.red);
+        ctx.set_font(&format!("{:.0}px monospace", cell_px / 2.0));
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        for x in 0..self.width() as isize {
+            for y in 0..self.height() as isize {
+                let center = view.to_screen(self.cell_center((x, y)));
+                let visible = (-cell_px..width + cell_px).contains(&center.x)
+                    && (-cell_px..height + cell_px).contains(&center.y);
+                if !visible {
+                    continue;
+                }
+                if let Some(count) = self.automaton.neighbor_count((x, y), &self.front_buf) {
+                    // Only fails for invalid arguments
+                    let _ = ctx.fill_text(&count.to_string(), center.x, center.y);
+                }
+            }
+        }
+    }
+
+    /// Outline the cells in `region` with a dashed line in `color`.
+    pub fn draw_region(&self, ctx: &CanvasRenderingContext2d, region: Region, color: &str) {
+        let view = self.view();
+        let (x0, y0, x1, y1) = region;
+        let half = self.cell_width as f64 / 2.0;
+        let top_left = self.cell_center((x0, y0)) - Vector2::from([half, half]);
+        let bottom_right = self.cell_center((x1, y1)) + Vector2::from([half, half]);
+        let (top_left, bottom_right) = (view.to_screen(top_left), view.to_screen(bottom_right));
+        ctx.save();
+        ctx.set_line_width(2.0);
+        ctx.set_stroke_style_str(color);
+        // Only fails for invalid dash lengths
+        let _ = ctx.set_line_dash(&js_sys::Array::of2(&6.0.into(), &4.0.into()));
+        ctx.stroke_rect(
+            top_left.x,
+            top_left.y,
+            bottom_right.x - top_left.x,
+            bottom_right.y - top_left.y,
+        );
+        ctx.restore();
+    }
+
+    /// Draw the cells at `offsets` from `origin` as alive, but translucent,
+    /// to preview a pattern before stamping it.
+    pub fn draw_stamp_preview(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        options: &RenderOptions,
+        (x, y): (isize, isize),
+        offsets: &[(isize, isize)],
+    ) {
+        let view = self.view();
+        let alive = self.automaton.toggle(A::State::default());
+        ctx.save();
+        ctx.set_global_alpha(STAMP_PREVIEW_ALPHA);
+        ctx.set_fill_style_str(&self.automaton.style(&alive, &options.theme));
+        ctx.begin_path();
+        // Show the cells where they end up on a wrapping grid
+        for pos in offsets
+            .iter()
+            .filter_map(|(dx, dy)| self.front_buf.normalize(x + dx, y + dy))
+        {
+            self.trace_cell(ctx, options.cell_shape, view, pos);
+        }
+        ctx.fill();
+        ctx.restore();
+    }
+
+    /// Add the outline of the cell at `pos` to the current path.
+    fn trace_cell(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        shape: CellShape,
+        view: View,
+        (x, y): (isize, isize),
+    ) {
+        match (shape, A::Dimension::HEXAGONAL) {
+            (CellShape::Circle, _) => {
+                let center = view.to_screen(self.cell_center((x, y)));
+                let radius = (self.cell_width as f64 / 2.0 - 1.0) * view.scale;
+                // Start a new subpath, instead of connecting to the previous circle
+                ctx.move_to(center.x + radius, center.y);
+                // Only fails for negative radii
+                let _ = ctx.arc(center.x, center.y, radius, 0.0, TAU);
+            }
+            (CellShape::Square, true) => self.trace_hexagon(ctx, view, (x, y)),
+            (CellShape::Square, false) => {
+                let cell = self.cell_width as f64;
+                let pos =
+                    view.to_screen(Point2::from([x as f64 * cell + 1.0, y as f64 * cell + 1.0]));
+                let size = (cell - 2.0) * view.scale;
+                ctx.rect(pos.x, pos.y, size, size);
+            }
+        }
+    }
+
+    /// Add the outline of the hexagon at `pos` to the current path.
+    fn trace_hexagon(&self, ctx: &CanvasRenderingContext2d, view: View, pos: (isize, isize)) {
+        for (idx, (x, y)) in hexagon_corners(pos, self.cell_width as f64)
+            .into_iter()
+            .enumerate()
+        {
+            let corner = view.to_screen(Point2::from([x, y]));
+            if idx == 0 {
+                ctx.move_to(corner.x, corner.y);
+            } else {
+                ctx.line_to(corner.x, corner.y);
+            }
+        }
+        ctx.close_path();
+    }
+
+    /// Stroke lines along all cell boundaries with the current stroke style.
+    fn draw_grid_lines(&self, ctx: &CanvasRenderingContext2d, view: View) {
+        let (width, height) = (
+            self.width() * self.cell_width,
+            self.height() * self.cell_width,
+        );
+        let top_left = view.to_screen(Point2::origin());
+        let bottom_right = view.to_screen(Point2::from([width as f64, height as f64]));
+        ctx.begin_path();
+        for x in (0..=width).step_by(self.cell_width) {
+            let screen_x = view.to_screen(Point2::from([x as f64, 0.0])).x;
+            // Align to the pixel grid to keep lines sharp
+            let screen_x = screen_x.round() + 0.5;
+            ctx.move_to(screen_x, top_left.y);
+            ctx.line_to(screen_x, bottom_right.y);
+        }
+        for y in (0..=height).step_by(self.cell_width) {
+            let screen_y = view.to_screen(Point2::from([0.0, y as f64])).y;
+            let screen_y = screen_y.round() + 0.5;
+            ctx.move_to(top_left.x, screen_y);
+            ctx.line_to(bottom_right.x, screen_y);
+        }
+        ctx.stroke();
+    }
+}
+
+/// How to restore the grid before the last update, see [`Supervisor::step_back`].
+enum Previous<State> {
+    /// The update left the grid of the given generation in `swap_buf`.
+    Swapped(u64),
+    /// The update only changed the given cells, which had these states in
+    /// the given generation.
+    Changed(u64, Vec<((isize, isize), State)>),
+}
+
+/// Mapping from grid to screen coordinates.
+#[derive(Debug, Clone, Copy)]
+struct View {
+    trans: Translation2<f64>,
+    scale: f64,
+}
+
+impl View {
+    fn to_screen(self, obj: Point2<f64>) -> Point2<f64> {
+        self.scale * self.trans.transform_point(&obj)
+    }
+
+    /// Screen area `(left, top, width, height)` of a grid covering `world_size`.
+    fn grid_rect(self, (width, height): (f64, f64)) -> (f64, f64, f64, f64) {
+        let top_left = self.to_screen(Point2::origin());
+        let bottom_right = self.to_screen(Point2::from([width, height]));
+        let size = bottom_right - top_left;
+        (top_left.x, top_left.y, size.x, size.y)
+    }
+}
+
+/// Opacity of the pattern preview, see [`Supervisor::draw_stamp_preview`].
+pub const STAMP_PREVIEW_ALPHA: f64 = 0.5;
+
+/// Smallest width of a cell on screen in pixels, for which neighbor counts are drawn.
+pub const MIN_LABEL_CELL_PX: f64 = 16.0;
+
+/// Age at which the heatmap color stops changing.
+pub const HEATMAP_MAX_AGE: u32 = 50;
+
+/// Smallest and largest width of a cell, see [`Supervisor::set_cell_width`].
+pub const MIN_CELL_WIDTH: usize = 4;
+pub const MAX_CELL_WIDTH: usize = 100;
+
+/// Cells sharing a fill color, see [`Supervisor::fill_batches`].
+pub type FillBatch = (Cow<'static, str>, Vec<(isize, isize)>);
+
+/// Inclusive box of cells `(x0, y0, x1, y1)`.
+pub type Region = (isize, isize, isize, isize);
+
+/// Number of cells shown around the pattern by [`Supervisor::fit_to_pattern`].
+pub const FIT_MARGIN_CELLS: f64 = 2.0;
+
+/// Screen pixels of the grid kept on the canvas by [`Supervisor::clamp_pan`].
+pub const MIN_VISIBLE_PX: f64 = 50.0;
+
+/// Number of cells added on every side when an expanding grid grows.
+pub const EXPAND_MARGIN: usize = 8;
+
+/// Grids with less than one alive cell in this many cells are updated
+/// sparsely, even if the automaton can step the whole grid at once.
+pub const SPARSE_CELLS_PER_ALIVE: usize = 16;
+
+/// Maximum period of the cycles detected by [`Supervisor::stability`].
+pub const STABILITY_WINDOW: usize = 16;
+
+/// Heatmap color for a cell alive for `age` generations, from yellow for
+/// newborn cells to blue for cells of [`HEATMAP_MAX_AGE`] or older.
+pub fn heat_color(age: u32) -> String {
+    let t = age.min(HEATMAP_MAX_AGE) as f64 / HEATMAP_MAX_AGE as f64;
+    format!("hsl({:.0}, 80%, 60%)", 60.0 + 180.0 * t)
+}
+
+/// Options affecting how the grid is drawn.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Draw lines between cells.
+    pub show_grid: bool,
+    /// Color alive cells by their age, see [`heat_color`].
+    pub heatmap: bool,
+    pub cell_shape: CellShape,
+    pub theme: Theme,
+    /// Print the neighbor count of each cell on it, see [`Automaton::neighbor_count`].
+    pub neighbor_counts: bool,
+}
+
+/// How a single cell is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CellShape {
+    /// Fill the whole cell, squares or hexagons depending on the grid.
+    #[default]
+    Square,
+    /// A dot in the center of the cell.
+    Circle,
+}
+
+/// Smallest allowed zoom, strictly positive to keep screen coordinates finite.
+pub const MIN_SCALE: f64 = 0.01;
+/// Largest allowed zoom.
+pub const MAX_SCALE: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Scale {
     Manual(f64),
     Auto(f64),
@@ -102,3 +1362,830 @@ impl Scale {
         }
     }
 }
+
+/// The scale after zooming by `delta`, clamped to [`MIN_SCALE`]..=[`MAX_SCALE`].
+pub fn zoomed_scale(scale: f64, delta: f64) -> f64 {
+    (scale + delta).clamp(MIN_SCALE, MAX_SCALE)
+}
+
+/// The zoom delta for [`Supervisor::zoom_at`] that scales `scale` by the
+/// ratio of the distances between two fingers during a pinch.
+pub fn pinch_delta(scale: f64, from_distance: f64, to_distance: f64) -> f64 {
+    if from_distance <= 0.0 {
+        return 0.0;
+    }
+    scale * (to_distance / from_distance - 1.0)
+}
+
+/// All cells on the line from `from` to `to`, both inclusive.
+///
+/// Uses Bresenham's line algorithm, so consecutive cells are always neighbors.
+pub fn line_cells(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = vec![(x, y)];
+    while (x, y) != to {
+        let err2 = 2 * err;
+        if err2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if err2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+        cells.push((x, y));
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use super::*;
+    use crate::automaton::{
+        tests::step, Elementary, Heading, HexLife, LangtonsAnt, Life, LifeLike, Margolus, Sandpile,
+        Turmite,
+    };
+
+    #[test]
+    fn dense_two_state_updates_match_per_cell_updates() {
+        let rule = LifeLike::parse("B36/S23").unwrap();
+        for topology in [Topology::Torus, Topology::KleinBottle] {
+            let mut supervisor = Supervisor::new(rule, 70, 20);
+            supervisor.set_topology(topology);
+            supervisor.randomize(0.5, 1);
+            for _ in 0..5 {
+                let expected = step(&rule, &supervisor.front_buf);
+                supervisor.update();
+                // Mirrored edges fall back to the sparse update
+                assert_eq!(supervisor.alive.is_none(), topology == Topology::Torus);
+                assert_eq!(supervisor.front_buf, expected);
+            }
+            assert!(supervisor.step_back());
+        }
+    }
+
+    #[test]
+    fn randomize_respects_density() {
+        let mut supervisor = Supervisor::new(Life, 8, 8);
+        supervisor.randomize(1.0, 0);
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(supervisor.get(x, y), Some(&LifeStates::Alife));
+            }
+        }
+        supervisor.randomize(0.0, 0);
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(supervisor.get(x, y), Some(&LifeStates::Dead));
+            }
+        }
+    }
+
+    #[test]
+    fn randomize_spacetime_fills_the_newest_row() {
+        let mut supervisor = Supervisor::new(Elementary::default(), 8, 6);
+        supervisor.randomize(1.0, 0);
+        for x in 0..8 {
+            assert_eq!(supervisor.get(x, 5), Some(&LifeStates::Alife));
+            for y in 0..5 {
+                assert_eq!(supervisor.get(x, y), Some(&LifeStates::Dead));
+            }
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_toggle() {
+        let mut supervisor = Supervisor::new(Life, 4, 4);
+        supervisor.toggle(1, 2);
+        assert!(supervisor.undo());
+        assert_eq!(supervisor.get(1, 2), Some(&LifeStates::Dead));
+        assert!(supervisor.redo());
+        assert_eq!(supervisor.get(1, 2), Some(&LifeStates::Alife));
+        assert!(!supervisor.redo());
+    }
+
+    #[test]
+    fn sand_drops_are_undone_together() {
+        let mut supervisor = Supervisor::new(Sandpile, 4, 4);
+        supervisor.checkpoint();
+        for _ in 0..3 {
+            assert!(supervisor.drop_sand(1, 1));
+        }
+        assert_eq!(supervisor.get(1, 1), Some(&3));
+        assert!(supervisor.undo());
+        assert_eq!(supervisor.get(1, 1), Some(&0));
+        assert!(!supervisor.undo());
+        assert!(!Supervisor::new(Life, 4, 4).drop_sand(1, 1));
+    }
+
+    #[test]
+    fn blinker_generation_and_population() {
+        let mut supervisor = Supervisor::new(Life, 5, 5);
+        for x in 1..=3 {
+            supervisor.toggle(x, 2);
+        }
+        supervisor.update();
+        supervisor.update();
+        assert_eq!(supervisor.generation(), 2);
+        assert_eq!(supervisor.population(), 3);
+        supervisor.undo();
+        assert_eq!(supervisor.generation(), 1);
+    }
+
+    #[test]
+    fn clear_resets_all_cells() {
+        let mut supervisor = Supervisor::new(Life, 4, 4);
+        for x in 0..4 {
+            supervisor.toggle(x, x);
+            supervisor.toggle(x, 0);
+        }
+        supervisor.clear();
+        for x in 0..4 {
+            for y in 0..4 {
+                assert_eq!(supervisor.get(x, y), Some(&LifeStates::Dead));
+            }
+        }
+    }
+
+    /// Run `generations` steps both sparse and with the brute-force reference.
+    fn compare_with_reference<A: Automaton>(supervisor: &mut Supervisor<A>, generations: usize)
+    where
+        A::State: PartialEq + fmt::Debug,
+    {
+        let mut reference = supervisor.front_buf.clone();
+        for _ in 0..generations {
+            supervisor.update();
+            reference = step(&supervisor.automaton, &reference);
+            assert_eq!(supervisor.front_buf, reference);
+        }
+    }
+
+    #[test]
+    fn sparse_update_matches_full_update() {
+        let mut supervisor = Supervisor::new(Life, 64, 48);
+        assert_eq!(supervisor.automaton.sparse_radius(), Some(1));
+        // A glider crossing the wrapped edges and a blinker
+        supervisor.stamp((58, 44), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        supervisor.stamp((10, 10), &[(0, 0), (1, 0), (2, 0)]);
+        compare_with_reference(&mut supervisor, 30);
+        // Life steps the whole grid only if it is dense
+        assert!(supervisor.alive.is_some());
+        // Edits between steps are picked up
+        supervisor.toggle(30, 30);
+        supervisor.toggle(31, 30);
+        supervisor.toggle(30, 31);
+        compare_with_reference(&mut supervisor, 5);
+        assert!(supervisor.undo());
+        compare_with_reference(&mut supervisor, 5);
+    }
+
+    #[test]
+    fn randomize_is_reproducible() {
+        let mut supervisor = Supervisor::new(Life, 16, 12);
+        supervisor.randomize(0.3, 1234);
+        let first = supervisor.front_buf.clone();
+        assert_eq!(supervisor.seed(), Some(1234));
+        supervisor.clear();
+        supervisor.randomize(0.3, 1234);
+        assert_eq!(first, supervisor.front_buf);
+        // Also independent of the history of the supervisor
+        let mut other = Supervisor::new(Life, 16, 12);
+        other.randomize(0.3, 1234);
+        assert_eq!(first, other.front_buf);
+        // Another seed gives another grid
+        other.randomize(0.3, 1235);
+        assert_ne!(first, other.front_buf);
+        assert_eq!(other.seed(), Some(1235));
+    }
+
+    #[test]
+    fn step_grid_matches_full_update() {
+        let mut supervisor = Supervisor::new(Life, 30, 20);
+        supervisor.randomize(0.4, 0);
+        compare_with_reference(&mut supervisor, 10);
+        assert!(supervisor.alive.is_none());
+    }
+
+    #[test]
+    fn full_update_without_sparse_radius() {
+        let mut supervisor = Supervisor::new(LifeLike::parse("B0/S8").unwrap(), 8, 8);
+        assert_eq!(supervisor.automaton.sparse_radius(), None);
+        supervisor.toggle(3, 3);
+        compare_with_reference(&mut supervisor, 3);
+    }
+
+    #[test]
+    fn reversible_step_back_beyond_history() {
+        let mut supervisor = Supervisor::new(Margolus::default(), 8, 8);
+        supervisor.randomize(0.5, 0);
+        let original = supervisor.front_buf.clone();
+        for _ in 0..4 {
+            supervisor.update();
+        }
+        supervisor.history.clear();
+        while supervisor.step_back() {}
+        assert_eq!(supervisor.generation(), 0);
+        assert_eq!(supervisor.front_buf, original);
+        // The undone generations can be replayed
+        supervisor.fast_forward();
+        assert_eq!(supervisor.generation(), 4);
+    }
+
+    #[test]
+    fn populations_follow_updates_and_reset_on_clear() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.stamp((1, 2), &[(0, 0), (1, 0), (2, 0)]);
+        for _ in 0..3 {
+            supervisor.update();
+        }
+        let populations: Vec<_> = supervisor.populations().iter().copied().collect();
+        assert_eq!(populations, vec![3, 3, 3]);
+        supervisor.clear();
+        let populations: Vec<_> = supervisor.populations().iter().copied().collect();
+        assert_eq!(populations, vec![0]);
+    }
+
+    #[test]
+    fn still_life_and_oscillator_periods() {
+        let mut block = Supervisor::new(Life, 6, 6);
+        block.stamp((2, 2), &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        block.update();
+        assert_eq!(block.stability(), None);
+        block.update();
+        assert_eq!(block.stability(), Some(1));
+
+        let mut blinker = Supervisor::new(Life, 6, 6);
+        blinker.stamp((1, 2), &[(0, 0), (1, 0), (2, 0)]);
+        for _ in 0..3 {
+            blinker.update();
+        }
+        assert_eq!(blinker.stability(), Some(2));
+        // Edits restart the detection
+        blinker.toggle(0, 0);
+        assert_eq!(blinker.stability(), None);
+    }
+
+    #[test]
+    fn glider_is_not_stable() {
+        let mut supervisor = Supervisor::new(Life, 8, 8);
+        supervisor.stamp((0, 0), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        // On an 8×8 torus the glider only returns after 32 generations
+        for _ in 0..STABILITY_WINDOW {
+            supervisor.update();
+            assert_eq!(supervisor.stability(), None);
+        }
+    }
+
+    #[test]
+    fn glider_expands_grid() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        let mut supervisor = Supervisor::new(Life, 10, 10);
+        supervisor.set_expanding(true);
+        supervisor.stamp((4, 4), &glider);
+        supervisor.trans = Translation2::from([3.0, -7.0]);
+        let mut expected = supervisor.front_buf.clone();
+        for _ in 0..12 {
+            let before = supervisor.view();
+            supervisor.update();
+            // Cells stay at the same place on screen when the grid grows
+            let margin = (supervisor.width() - expected.width()) as isize / 2;
+            let screen = |view: View, (x, y)| view.to_screen(supervisor.cell_center((x, y)));
+            assert_eq!(
+                screen(before, (0, 0)),
+                screen(supervisor.view(), (margin, margin))
+            );
+            expected.pad(margin as usize);
+            expected = step(&Life, &expected);
+            assert_eq!(supervisor.front_buf, expected);
+        }
+        assert_eq!(supervisor.width(), 10 + 2 * EXPAND_MARGIN);
+        assert_eq!(supervisor.population(), 5);
+    }
+
+    #[test]
+    fn step_back_without_history() {
+        let mut supervisor = Supervisor::new(Life, 5, 5);
+        supervisor.stamp((1, 2), &[(0, 0), (1, 0), (2, 0)]);
+        supervisor.update();
+        let before = supervisor.front_buf.clone();
+        supervisor.update();
+        supervisor.history.clear();
+        assert!(supervisor.step_back());
+        assert_eq!(supervisor.front_buf, before);
+        assert_eq!(supervisor.generation(), 1);
+        // Only the last generation is kept
+        assert!(!supervisor.step_back());
+        // The step can be replayed
+        supervisor.step_forward();
+        assert_eq!(supervisor.generation(), 2);
+
+        supervisor.update();
+        supervisor.toggle(0, 0);
+        supervisor.history.clear();
+        assert!(
+            !supervisor.step_back(),
+            "edits drop the previous generation"
+        );
+
+        // Sparse updates change the grid in place, but keep it as well
+        let mut sparse = Supervisor::new(Life, 20, 20);
+        sparse.stamp((1, 2), &[(0, 0), (1, 0), (2, 0)]);
+        sparse.update();
+        let before = sparse.front_buf.clone();
+        sparse.update();
+        assert!(sparse.alive.is_some());
+        // Only the changed cells are kept, not a copy of the grid
+        assert!(
+            matches!(&sparse.previous, Some(Previous::Changed(1, changed)) if changed.len() == 4)
+        );
+        sparse.history.clear();
+        assert!(sparse.step_back());
+        assert_eq!(sparse.front_buf, before);
+    }
+
+    #[test]
+    fn step_back_after_expanding() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.set_expanding(true);
+        supervisor.stamp((2, 2), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let mut expected = supervisor.front_buf.clone();
+        while supervisor.width() == 6 {
+            expected = supervisor.front_buf.clone();
+            supervisor.update();
+        }
+        assert!(!supervisor.history.can_go_back());
+        assert!(supervisor.step_back());
+        expected.pad(EXPAND_MARGIN);
+        assert_eq!(supervisor.front_buf, expected);
+        // Updating after going back across a resize works on the padded grid
+        supervisor.update();
+        assert_eq!(supervisor.population(), 5);
+    }
+
+    #[test]
+    fn sparse_step_back_after_expanding() {
+        let mut supervisor = Supervisor::new(Life, 30, 30);
+        supervisor.set_expanding(true);
+        supervisor.stamp((24, 24), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let mut expected = supervisor.front_buf.clone();
+        while supervisor.width() == 30 {
+            expected = supervisor.front_buf.clone();
+            supervisor.update();
+        }
+        assert!(matches!(supervisor.previous, Some(Previous::Changed(..))));
+        assert!(supervisor.step_back());
+        expected.pad(EXPAND_MARGIN);
+        assert_eq!(supervisor.front_buf, expected);
+    }
+
+    #[test]
+    fn svg_shows_alive_cells() {
+        let mut supervisor = Supervisor::new(Life, 4, 3);
+        supervisor.stamp((0, 0), &[(0, 0), (3, 2)]);
+        let svg = supervisor.to_svg(&RenderOptions::default(), (40, 30));
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("width=\"40\" height=\"30\" viewBox=\"0 0 200 150\""));
+        assert_eq!(svg.matches("<rect x=").count(), 2);
+        assert!(svg.contains("<rect x=\"151\" y=\"101\" width=\"48\" height=\"48\"/>"));
+
+        let mut hex = Supervisor::new(HexLife::default(), 3, 3);
+        hex.stamp((1, 1), &[(0, 0)]);
+        let svg = hex.to_svg(&RenderOptions::default(), (10, 10));
+        assert_eq!(svg.matches("<polygon").count(), 1);
+    }
+
+    #[test]
+    fn alive_region_is_cut_out() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.stamp((0, 0), &[(1, 1), (3, 2), (5, 5)]);
+        let region = supervisor.alive_region((1, 1, 3, 2));
+        assert_eq!((region.width(), region.height()), (3, 2));
+        assert_eq!(region[(0, 0)], LifeStates::Alife);
+        assert_eq!(region[(2, 1)], LifeStates::Alife);
+    }
+
+    #[test]
+    fn oversized_resize_keeps_grid() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.set_max_cells(100);
+        supervisor.stamp((1, 1), &[(0, 0)]);
+        assert!(supervisor.resize(20, 6).is_err());
+        assert!(supervisor.resize(usize::MAX, 2).is_err());
+        assert_eq!((supervisor.width(), supervisor.height()), (6, 6));
+        assert_eq!(supervisor.population(), 1);
+        assert_eq!(supervisor.resize(10, 10), Ok(()));
+        assert_eq!((supervisor.width(), supervisor.height()), (10, 10));
+        assert!(Supervisor::try_new(Life, 100_000, 100_000).is_err());
+        assert!(Supervisor::try_new(Life, usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn expanding_stops_at_max_cells() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.set_max_cells(6 * 6);
+        supervisor.set_expanding(true);
+        supervisor.stamp((0, 2), &[(0, 0), (0, 1), (0, 2)]);
+        assert!(!supervisor.maybe_expand());
+        assert_eq!(supervisor.width(), 6);
+    }
+
+    #[test]
+    fn block_ages() {
+        let mut supervisor = Supervisor::new(Life, 6, 6);
+        supervisor.stamp((2, 2), &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        for _ in 0..3 {
+            supervisor.update();
+        }
+        assert_eq!(supervisor.age(2, 2), Some(3));
+        assert_eq!(supervisor.age(0, 0), Some(0));
+        supervisor.toggle(2, 2);
+        assert_eq!(supervisor.age(2, 2), Some(0));
+    }
+
+    #[test]
+    fn stamp_in_orientation() {
+        let mut supervisor = Supervisor::new(Life, 8, 8);
+        let blinker = [(0, 0), (1, 0), (2, 0)];
+        let upright = Orientation::default().rotated_cw();
+        supervisor.stamp_oriented((2, 1), &blinker, upright);
+        assert_eq!(alive_cells(&supervisor), [(2, 1), (2, 2), (2, 3)]);
+        // Still a single edit
+        assert!(supervisor.undo());
+        assert_eq!(supervisor.population(), 0);
+    }
+
+    #[test]
+    fn cells_are_batched_by_color() {
+        let mut supervisor = Supervisor::new(Life, 20, 20);
+        supervisor.stamp((5, 5), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let options = RenderOptions::default();
+        let batches = supervisor.fill_batches(&options);
+        // One style switch per color instead of one per cell
+        let colors: Vec<_> = batches.iter().map(|(color, _)| color.as_ref()).collect();
+        assert_eq!(
+            colors,
+            [options.theme.palette.dead, options.theme.palette.alive]
+        );
+        assert_eq!(batches[0].1.len(), 395);
+        assert_eq!(batches[1].1, [(5, 7), (6, 5), (6, 7), (7, 6), (7, 7)]);
+        // Every cell is drawn exactly once
+        let cells: HashSet<_> = batches.iter().flat_map(|(_, cells)| cells).collect();
+        assert_eq!(cells.len(), 400);
+    }
+
+    #[test]
+    fn heat_color_saturates() {
+        assert_eq!(heat_color(0), "hsl(60, 80%, 60%)");
+        assert_eq!(heat_color(HEATMAP_MAX_AGE), heat_color(u32::MAX));
+    }
+
+    #[test]
+    fn erase_sets_default() {
+        let mut supervisor = Supervisor::new(Life, 4, 4);
+        supervisor.toggle(1, 1);
+        assert_eq!(supervisor.get(1, 1), Some(&LifeStates::Alife));
+        supervisor.set(1, 1, LifeStates::default());
+        assert_eq!(supervisor.get(1, 1), Some(&LifeStates::Dead));
+        // Erasing is not a toggle
+        supervisor.set(1, 1, LifeStates::default());
+        assert_eq!(supervisor.get(1, 1), Some(&LifeStates::Dead));
+    }
+
+    #[test]
+    fn stamp_glider() {
+        let mut supervisor = Supervisor::new(Life, 8, 8);
+        supervisor.stamp((3, 2), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(supervisor.population(), 5);
+        for pos in [(4, 2), (5, 3), (3, 4), (4, 4), (5, 4)] {
+            assert_eq!(supervisor.get(pos.0, pos.1), Some(&LifeStates::Alife));
+        }
+        assert!(supervisor.undo());
+        assert_eq!(supervisor.population(), 0);
+    }
+
+    #[test]
+    fn load_alive_round_trips() {
+        let mut supervisor = Supervisor::new(Life, 5, 5);
+        supervisor.toggle(1, 2);
+        supervisor.toggle(3, 4);
+        let cells = supervisor.alive_grid();
+        let mut other = Supervisor::new(Life, 2, 2);
+        other.update();
+        other.load_alive(&cells);
+        assert_eq!((other.width(), other.height()), (5, 5));
+        assert_eq!(other.generation(), 0);
+        assert_eq!(other.alive_grid(), cells);
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut supervisor = Supervisor::new(Life, 4, 4);
+        let scale = zoomed_scale(supervisor.scale.raw(), -1e6);
+        assert_eq!(scale, MIN_SCALE);
+        supervisor.scale = Scale::Manual(scale);
+        let pos = supervisor.from_screen_coordinates(Point2::from([300.0, 200.0]));
+        assert!(pos.x.is_finite() && pos.y.is_finite());
+        assert_eq!(zoomed_scale(1.0, 1e6), MAX_SCALE);
+        assert_eq!(zoomed_scale(1.0, 0.5), 1.5);
+    }
+
+    #[test]
+    fn zoom_at_keeps_cursor_fixed() {
+        let mut supervisor = Supervisor::new(Life, 4, 4);
+        supervisor.trans = Translation2::from([-37.0, 12.5]);
+        let cursor = Point2::from([310.0, 145.0]);
+        for delta in [0.25, -0.5, 2.0, -1e6, 1e6] {
+            let before = supervisor.from_screen_coordinates(cursor);
+            supervisor.zoom_at(cursor, delta);
+            let after = supervisor.from_screen_coordinates(cursor);
+            assert!((before - after).norm() < 1e-6, "{} vs {}", before, after);
+        }
+        assert!(!supervisor.zoom_at(cursor, 1.0));
+    }
+
+    #[test]
+    fn pinch_scales_by_distance_ratio() {
+        assert_eq!(zoomed_scale(1.0, pinch_delta(1.0, 100.0, 200.0)), 2.0);
+        assert_eq!(zoomed_scale(2.0, pinch_delta(2.0, 100.0, 50.0)), 1.0);
+        assert_eq!(pinch_delta(0.5, 80.0, 80.0), 0.0);
+        // Fingers at the same spot don't zoom
+        assert_eq!(pinch_delta(1.0, 0.0, 50.0), 0.0);
+        assert_eq!(zoomed_scale(5.0, pinch_delta(5.0, 10.0, 1000.0)), MAX_SCALE);
+    }
+
+    /// Assert that the center of every cell maps back to the cell.
+    fn assert_cells_round_trip<A: Automaton>(supervisor: &Supervisor<A>) {
+        for x in 0..supervisor.width() as isize {
+            for y in 0..supervisor.height() as isize {
+                let screen = supervisor.view().to_screen(supervisor.cell_center((x, y)));
+                assert_eq!(supervisor.cell_at(screen), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn cell_width_round_trip() {
+        let mut square = Supervisor::new(Life, 7, 5);
+        let mut hex = Supervisor::new(HexLife::default(), 7, 5);
+        for cell_width in [MIN_CELL_WIDTH, 13, DEFAULT_CELL_WIDTH, MAX_CELL_WIDTH] {
+            square.set_cell_width(cell_width);
+            hex.set_cell_width(cell_width);
+            square.reset_zoom(640, 480);
+            hex.reset_zoom(640, 480);
+            assert_eq!(square.cell_width(), cell_width);
+            assert_cells_round_trip(&square);
+            assert_cells_round_trip(&hex);
+            // The grid fills the canvas regardless of the cell size
+            let corner = square.view().to_screen(Point2::from([
+                (7 * cell_width) as f64,
+                (5 * cell_width) as f64,
+            ]));
+            assert!((corner.x - 640.0).abs() < 1e-6 || (corner.y - 480.0).abs() < 1e-6);
+        }
+        square.set_cell_width(1);
+        assert_eq!(square.cell_width(), MIN_CELL_WIDTH);
+    }
+
+    #[test]
+    fn cell_under_cursor() {
+        let mut supervisor = Supervisor::new(Life, 10, 8);
+        let cell = DEFAULT_CELL_WIDTH as f64;
+        let cases = [
+            // Translation, scale, screen position, cell
+            ((0.0, 0.0), 1.0, (0.0, 0.0), (0, 0)),
+            ((0.0, 0.0), 1.0, (cell * 2.5, cell * 7.9), (2, 7)),
+            ((-cell, 2.0 * cell), 1.0, (0.5 * cell, 2.5 * cell), (1, 0)),
+            ((0.0, 0.0), 0.5, (cell * 2.5, cell * 1.2), (5, 2)),
+            ((10.0, 0.0), 2.0, (cell * 3.0 + 21.0, 0.0), (1, 0)),
+            // Wrapping around the edges
+            ((0.0, 0.0), 1.0, (-0.5 * cell, -0.5 * cell), (9, 7)),
+            ((0.0, 0.0), 0.25, (cell * 3.0, cell * 2.25), (2, 1)),
+        ];
+        for ((dx, dy), scale, (x, y), expected) in cases {
+            supervisor.trans = Translation2::from([dx, dy]);
+            supervisor.scale = Scale::Manual(scale);
+            let pos = Point2::from([x, y]);
+            assert_eq!(supervisor.cell_under(pos), Some(expected), "at {}", pos);
+        }
+        supervisor.set_expanding(true);
+        assert_eq!(supervisor.cell_under(Point2::from([-1.0, 0.0])), None);
+    }
+
+    #[test]
+    fn bounding_box_of_alive_cells() {
+        let mut supervisor = Supervisor::new(Life, 10, 8);
+        assert_eq!(supervisor.bounding_box(), None);
+        supervisor.toggle(4, 6);
+        assert_eq!(supervisor.bounding_box(), Some((4, 6, 4, 6)));
+        supervisor.stamp((1, 2), &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(supervisor.bounding_box(), Some((1, 2, 4, 6)));
+        supervisor.toggle(9, 0);
+        assert_eq!(supervisor.bounding_box(), Some((1, 0, 9, 6)));
+    }
+
+    #[test]
+    fn fit_to_pattern_frames_alive_cells() {
+        let mut supervisor = Supervisor::new(Life, 100, 100);
+        supervisor.stamp((40, 60), &[(0, 0), (1, 0), (2, 0)]);
+        supervisor.fit_to_pattern(800, 600);
+        let view = supervisor.view();
+        let cell = DEFAULT_CELL_WIDTH as f64;
+        // The blinker plus two cells on each side fill the width
+        let left = view.to_screen(Point2::from([38.0 * cell, 58.0 * cell]));
+        let right = view.to_screen(Point2::from([45.0 * cell, 63.0 * cell]));
+        assert!(left.x.abs() < 1e-6 && (right.x - 800.0).abs() < 1e-6);
+        // The pattern is centered vertically
+        assert!((left.y + right.y - 600.0).abs() < 1e-6);
+        // Without alive cells the whole grid is shown
+        supervisor.clear();
+        supervisor.fit_to_pattern(800, 600);
+        assert!(matches!(supervisor.scale, Scale::Auto(_)));
+    }
+
+    #[test]
+    fn pan_is_clamped_to_canvas() {
+        // 20x10 cells of 50 px at half scale cover 500x250 px
+        let mut supervisor = Supervisor::new(Life, 20, 10);
+        supervisor.scale = Scale::Manual(0.5);
+        let screen_box = |supervisor: &Supervisor<Life>| {
+            let (width, height) = supervisor.world_size();
+            (
+                supervisor.to_screen_coordinates(Point2::origin()),
+                supervisor.to_screen_coordinates(Point2::from([width, height])),
+            )
+        };
+        // Far off to the top left, only the bottom right corner stays visible
+        supervisor.trans = Translation2::from([-5000.0, -3000.0]);
+        assert!(supervisor.clamp_pan(800, 600));
+        let (_, bottom_right) = screen_box(&supervisor);
+        assert!((bottom_right - Point2::from([MIN_VISIBLE_PX; 2])).norm() < 1e-9);
+        // Far off to the bottom right, only the top left corner stays visible
+        supervisor.trans = Translation2::from([5000.0, 3000.0]);
+        assert!(supervisor.clamp_pan(800, 600));
+        let (top_left, _) = screen_box(&supervisor);
+        assert!((top_left - Point2::from([750.0, 550.0])).norm() < 1e-9);
+        // Views that show part of the grid are untouched
+        supervisor.trans = Translation2::from([-800.0, 100.0]);
+        assert!(!supervisor.clamp_pan(800, 600));
+        assert_eq!(supervisor.trans, Translation2::from([-800.0, 100.0]));
+    }
+
+    #[test]
+    fn grid_rect_follows_view() {
+        let mut supervisor = Supervisor::new(Life, 20, 10);
+        supervisor.scale = Scale::Manual(0.5);
+        supervisor.trans = Translation2::from([100.0, -40.0]);
+        // 20x10 cells of 50 px, moved by the translation and halved
+        assert_eq!(supervisor.grid_screen_rect(), (50.0, -20.0, 500.0, 250.0));
+        supervisor.reset_zoom(800, 600);
+        // A wide grid fills the width and is centered vertically
+        let (left, top, width, height) = supervisor.grid_screen_rect();
+        assert!(left.abs() < 1e-9 && (width - 800.0).abs() < 1e-9);
+        assert!((top - 100.0).abs() < 1e-9 && (height - 400.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tiny_grids_stay_fully_visible() {
+        // 2x2 cells of 50 px at a tenth of the scale are only 10 px wide
+        let mut supervisor = Supervisor::new(Life, 2, 2);
+        supervisor.scale = Scale::Manual(0.1);
+        supervisor.trans = Translation2::from([-1000.0, 9000.0]);
+        assert!(supervisor.clamp_pan(400, 300));
+        let top_left = supervisor.to_screen_coordinates(Point2::origin());
+        assert!((top_left - Point2::from([0.0, 290.0])).norm() < 1e-9);
+    }
+
+    #[test]
+    fn placed_agents_get_distinct_ids() {
+        let mut supervisor = Supervisor::new(Turmite::default(), 6, 6);
+        assert!(supervisor.toggle_agent(1, 1));
+        assert!(supervisor.toggle_agent(4, 2));
+        let id = |x, y| {
+            supervisor
+                .get(x, y)
+                .and_then(|cell| cell.agent)
+                .map(|a| a.id)
+        };
+        assert_eq!((id(1, 1), id(4, 2)), (Some(0), Some(1)));
+        // Toggling again removes the agent, as a single undoable edit
+        assert!(supervisor.toggle_agent(1, 1));
+        assert_eq!(supervisor.get(1, 1).unwrap().agent, None);
+        supervisor.undo();
+        assert!(supervisor.get(1, 1).unwrap().agent.is_some());
+        // Automata without agents are left alone
+        let mut life = Supervisor::new(Life, 6, 6);
+        assert!(!life.toggle_agent(1, 1));
+        assert!(!life.undo());
+    }
+
+    #[test]
+    fn ant_follows_steps_and_history() {
+        let mut supervisor = Supervisor::new(LangtonsAnt, 8, 8);
+        assert!(supervisor.toggle_agent(5, 5));
+        let ant = |supervisor: &Supervisor<LangtonsAnt>| {
+            (0..8)
+                .flat_map(|x| (0..8).map(move |y| (x, y)))
+                .find_map(|(x, y)| Some(((x, y), supervisor.get(x, y)?.ant?)))
+        };
+        assert_eq!(ant(&supervisor), Some(((5, 5), Heading::North)));
+        for _ in 0..5 {
+            supervisor.step_forward();
+        }
+        assert_eq!(ant(&supervisor), Some(((4, 5), Heading::West)));
+        assert_eq!(supervisor.population(), 3);
+        // Stepping back moves the ant back and restores the cells it flipped
+        supervisor.step_back();
+        supervisor.step_back();
+        assert_eq!(ant(&supervisor), Some(((5, 6), Heading::West)));
+        assert_eq!(supervisor.population(), 3);
+    }
+
+    /// Alive cells of `supervisor`, sorted.
+    fn alive_cells<A: Automaton>(supervisor: &Supervisor<A>) -> Vec<(isize, isize)> {
+        (0..supervisor.width() as isize)
+            .flat_map(|x| (0..supervisor.height() as isize).map(move |y| (x, y)))
+            .filter(|&(x, y)| supervisor.automaton.is_alive(supervisor.get(x, y).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn rotating_four_times_restores_region() {
+        // An R-pentomino in a 3×4 region, which is not symmetric in any way
+        let mut supervisor = Supervisor::new(Life, 10, 10);
+        supervisor.stamp((3, 2), &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]);
+        supervisor.toggle(5, 5);
+        let original = alive_cells(&supervisor);
+        for rotate in [Supervisor::rotate_cw, Supervisor::rotate_ccw] {
+            let mut region = (3, 2, 5, 5);
+            for _ in 0..4 {
+                region = rotate(&mut supervisor, region).unwrap();
+            }
+            assert_eq!(region, (3, 2, 5, 5));
+            assert_eq!(alive_cells(&supervisor), original);
+        }
+    }
+
+    #[test]
+    fn rotation_swaps_region_around_center() {
+        let mut supervisor = Supervisor::new(Life, 10, 10);
+        // A horizontal line of four cells becomes a vertical one
+        supervisor.stamp((3, 4), &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        let region = supervisor.rotate_cw((3, 4, 6, 4)).unwrap();
+        assert_eq!(region, (4, 3, 4, 6));
+        assert_eq!(
+            alive_cells(&supervisor),
+            vec![(4, 3), (4, 4), (4, 5), (4, 6)]
+        );
+        assert_eq!(supervisor.rotate_ccw(region), Some((3, 4, 6, 4)));
+        // Corners are ordered and clipped to the grid
+        assert_eq!(supervisor.clip_region((12, 3, -2, 1)), Some((0, 1, 9, 3)));
+        assert_eq!(supervisor.clip_region((10, 0, 12, 3)), None);
+        // Cells rotated beyond the edge are dropped
+        supervisor.clear();
+        supervisor.stamp((0, 0), &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(supervisor.rotate_cw((0, 0, 3, 0)), Some((1, -1, 1, 2)));
+        assert_eq!(alive_cells(&supervisor), vec![(1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn flips_are_undoable() {
+        let mut supervisor = Supervisor::new(Life, 10, 10);
+        supervisor.stamp((2, 2), &[(0, 0), (0, 1), (1, 1)]);
+        let original = alive_cells(&supervisor);
+        supervisor.flip_h((2, 2, 3, 3));
+        assert_eq!(alive_cells(&supervisor), vec![(2, 3), (3, 2), (3, 3)]);
+        supervisor.flip_v((2, 2, 3, 3));
+        assert_eq!(alive_cells(&supervisor), vec![(2, 2), (3, 2), (3, 3)]);
+        supervisor.undo();
+        supervisor.undo();
+        assert_eq!(alive_cells(&supervisor), original);
+        assert_eq!(supervisor.copy_region((2, 2, 3, 2)).width(), 2);
+    }
+
+    #[test]
+    fn line_cells_are_connected() {
+        assert_eq!(line_cells((2, 2), (2, 2)), vec![(2, 2)]);
+        assert_eq!(
+            line_cells((0, 0), (3, 0)),
+            vec![(0, 0), (1, 0), (2, 0), (3, 0)]
+        );
+        assert_eq!(
+            line_cells((0, 0), (-2, -2)),
+            vec![(0, 0), (-1, -1), (-2, -2)]
+        );
+        let cells = line_cells((0, 0), (7, -3));
+        assert_eq!(cells.first(), Some(&(0, 0)));
+        assert_eq!(cells.last(), Some(&(7, -3)));
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1);
+        }
+    }
+}
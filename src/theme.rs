@@ -0,0 +1,71 @@
+/// Colors used for the canvas and the cell states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Fill behind the grid.
+    pub background: &'static str,
+    /// Fill of the canvas around the grid, beyond its edges.
+    pub outside: &'static str,
+    /// Grid lines and hexagon outlines.
+    pub grid: &'static str,
+    pub palette: Palette,
+}
+
+/// Colors for cell states, each automaton picks the ones matching its states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub dead: &'static str,
+    pub alive: &'static str,
+    pub red: &'static str,
+    pub blue: &'static str,
+    pub yellow: &'static str,
+}
+
+/// Themes selectable in the settings, the first one is the default.
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "Gruvbox dark",
+        background: "rgb(40,40,40)",
+        outside: "rgb(18,18,18)",
+        grid: "#504945",
+        palette: Palette {
+            dead: "#1d2021",
+            alive: "#ebdbb2",
+            red: "#cc241d",
+            blue: "#458588",
+            yellow: "#d79921",
+        },
+    },
+    Theme {
+        name: "Light",
+        background: "#fbf1c7",
+        outside: "#bdae93",
+        grid: "#d5c4a1",
+        palette: Palette {
+            dead: "#f9f5d7",
+            alive: "#3c3836",
+            red: "#9d0006",
+            blue: "#076678",
+            yellow: "#b57614",
+        },
+    },
+    Theme {
+        name: "High contrast",
+        background: "#000000",
+        outside: "#303030",
+        grid: "#808080",
+        palette: Palette {
+            dead: "#000000",
+            alive: "#ffffff",
+            red: "#ff0000",
+            blue: "#00ffff",
+            yellow: "#ffff00",
+        },
+    },
+];
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
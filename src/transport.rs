@@ -0,0 +1,110 @@
+use yew::{html, ComponentLink, Html};
+
+use crate::{automaton::Automaton, supervisor::Supervisor, Model, Msg};
+
+/// The buttons of the transport control, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportAction {
+    /// Go to the oldest generation in the history.
+    Start,
+    StepBack,
+    ToggleRun,
+    StepForward,
+    /// Go to the latest generation in the history.
+    Latest,
+}
+
+impl TransportAction {
+    pub const ALL: [Self; 5] = [
+        Self::Start,
+        Self::StepBack,
+        Self::ToggleRun,
+        Self::StepForward,
+        Self::Latest,
+    ];
+
+    pub fn msg(self) -> Msg {
+        match self {
+            Self::Start => Msg::Rewind,
+            Self::StepBack => Msg::StepBack,
+            Self::ToggleRun => Msg::ToggleAutoRun,
+            Self::StepForward => Msg::StepForward,
+            Self::Latest => Msg::FastForward,
+        }
+    }
+
+    pub fn label(self, running: bool) -> &'static str {
+        match self {
+            Self::Start => "⏮",
+            Self::StepBack => "⏪",
+            Self::ToggleRun if running => "⏸",
+            Self::ToggleRun => "⏵",
+            Self::StepForward => "⏩",
+            Self::Latest => "⏭",
+        }
+    }
+
+    /// Whether the action is currently unavailable.
+    pub fn disabled<A: Automaton>(self, supervisor: &Supervisor<A>) -> bool {
+        match self {
+            Self::Start | Self::StepBack => !supervisor.history().can_go_back(),
+            Self::Latest => !supervisor.history().can_go_forward(),
+            Self::ToggleRun | Self::StepForward => false,
+        }
+    }
+}
+
+/// Render the video-player-like transport control.
+pub fn html<A: Automaton + Default>(
+    link: &ComponentLink<Model<A>>,
+    supervisor: &Supervisor<A>,
+    running: bool,
+) -> Html {
+    let buttons = TransportAction::ALL.iter().map(|&action| {
+        let cb = link.callback(move |_| action.msg());
+        html! {
+            <button disabled=action.disabled(supervisor) onclick=cb>
+                { action.label(running) }
+            </button>
+        }
+    });
+    html! {
+        <div id="transport">
+            { for buttons }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Life;
+
+    #[test]
+    fn actions_dispatch_messages() {
+        assert!(matches!(TransportAction::Start.msg(), Msg::Rewind));
+        assert!(matches!(TransportAction::StepBack.msg(), Msg::StepBack));
+        assert!(matches!(
+            TransportAction::ToggleRun.msg(),
+            Msg::ToggleAutoRun
+        ));
+        assert!(matches!(
+            TransportAction::StepForward.msg(),
+            Msg::StepForward
+        ));
+        assert!(matches!(TransportAction::Latest.msg(), Msg::FastForward));
+    }
+
+    #[test]
+    fn disabled_reflects_history() {
+        let mut supervisor = Supervisor::new(Life, 3, 3);
+        let disabled = |supervisor: &Supervisor<Life>| {
+            TransportAction::ALL.map(|action| action.disabled(supervisor))
+        };
+        assert_eq!(disabled(&supervisor), [true, true, false, false, true]);
+        supervisor.step_forward();
+        assert_eq!(disabled(&supervisor), [false, false, false, false, true]);
+        supervisor.step_back();
+        assert_eq!(disabled(&supervisor), [true, true, false, false, false]);
+    }
+}